@@ -0,0 +1,56 @@
+//! Lookup benchmarks, using the test BTF files.
+use std::{fs::read, hint::black_box};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use btf_rs::{Btf, Type};
+
+fn lookup(c: &mut Criterion) {
+    let base = Btf::from_bytes(&read("tests/data/btf/vmlinux").unwrap()).unwrap();
+    let ovs = Btf::from_split_bytes(&read("tests/data/btf/openvswitch").unwrap(), &base).unwrap();
+    let skb = match base
+        .resolve_types_by_name("sk_buff")
+        .unwrap()
+        .pop()
+        .unwrap()
+    {
+        Type::Struct(skb) => skb,
+        _ => panic!("sk_buff is not a struct"),
+    };
+
+    let mut group = c.benchmark_group("lookup");
+
+    // Maps keyed by ids and string offsets.
+    group.bench_function("type_by_id", |b| {
+        b.iter(|| base.resolve_type_by_id(black_box(4984)).unwrap())
+    });
+    group.bench_function("split_type_by_id", |b| {
+        b.iter(|| ovs.resolve_type_by_id(black_box(4984)).unwrap())
+    });
+    group.bench_function("name", |b| {
+        b.iter(|| base.resolve_name(black_box(&skb)).unwrap())
+    });
+    group.bench_function("member_names", |b| {
+        b.iter(|| {
+            for member in skb.members.iter() {
+                base.resolve_name(black_box(member)).unwrap();
+            }
+        })
+    });
+
+    // Maps keyed by names.
+    group.bench_function("ids_by_name", |b| {
+        b.iter(|| base.resolve_ids_by_name(black_box("sk_buff")).unwrap())
+    });
+    group.bench_function("split_ids_by_name", |b| {
+        b.iter(|| {
+            ovs.resolve_ids_by_name(black_box("ovs_vport_send"))
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, lookup);
+criterion_main!(benches);
@@ -0,0 +1,33 @@
+//! Parsing benchmarks, using the test BTF files.
+use std::{fs::read, hint::black_box};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use btf_rs::{Btf, BtfOptions};
+
+fn parse(c: &mut Criterion) {
+    let vmlinux = read("tests/data/btf/vmlinux").unwrap();
+    let ovs = read("tests/data/btf/openvswitch").unwrap();
+    let base = Btf::from_bytes(&vmlinux).unwrap();
+
+    let mut group = c.benchmark_group("parse");
+    // Parsing the vmlinux BTF is slow, keep the number of runs low.
+    group.sample_size(20);
+
+    group.bench_function("vmlinux", |b| {
+        b.iter(|| Btf::from_bytes(black_box(&vmlinux)).unwrap())
+    });
+    // Exact table capacities, to compare with the estimated ones.
+    group.bench_function("vmlinux_capacity", |b| {
+        let opts = BtfOptions::new().capacity(177768, 179234);
+        b.iter(|| Btf::from_bytes_with_options(black_box(&vmlinux), &opts).unwrap())
+    });
+    group.bench_function("openvswitch", |b| {
+        b.iter(|| Btf::from_split_bytes(black_box(&ovs), &base).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,241 @@
+//! Derive macros for [btf-rs](https://crates.io/crates/btf-rs). Those are
+//! re-exported by btf-rs when its derive feature is enabled and should be used
+//! from there.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, punctuated::Punctuated, Attribute, Data, DeriveInput, Error, Field, Fields,
+    LitStr, Result, Token,
+};
+
+/// Implement `btf_rs::utils::layout::FromBtf` for a struct with named fields,
+/// mirroring a kernel struct or union.
+///
+/// The BTF name of the struct defaults to the Rust one and can be set using
+/// `#[btf(name = "...")]`; the same attribute can be used on fields. Field types
+/// must implement `btf_rs::utils::layout::RawField`.
+///
+/// For each field an associated constant `BTF_<FIELD>` is generated, holding
+/// the index of the field to use with `BtfOffsets::offset()`.
+#[proc_macro_derive(FromBtf, attributes(btf))]
+pub fn derive_from_btf(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_btf(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Implement `btf_rs::utils::raw::CBtfType` for a struct with named fields,
+/// reading the fields in declaration order without padding, as packed C
+/// structs are. Field types must implement `CBtfType`: integers, arrays of
+/// `CBtfType` types and other structs deriving it.
+///
+/// `CBtfType::SIZE` is the sum of the fields sizes, and for each field an
+/// associated constant `OFFSET_<FIELD>` is generated, holding its offset in
+/// the raw representation.
+///
+/// ```
+/// use btf_rs::{utils::raw::CBtfType, Endianness};
+///
+/// #[derive(CBtfType)]
+/// struct Header {
+///     magic: u16,
+///     version: u8,
+///     flags: u8,
+///     lens: [u32; 2],
+///     cookie: u64,
+/// }
+///
+/// let raw = [0xeb, 0x9f, 1, 0, 0, 0, 0, 24, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 42];
+/// let header = Header::from_reader(&mut raw.as_slice(), Endianness::Big).unwrap();
+/// assert_eq!(header.magic, 0xeb9f);
+/// assert_eq!(header.lens, [24, 8]);
+/// assert_eq!(header.cookie, 42);
+///
+/// assert_eq!(Header::SIZE, 20);
+/// assert_eq!(Header::OFFSET_LENS, 4);
+/// assert_eq!(Header::OFFSET_COOKIE, 12);
+/// ```
+///
+/// Unsupported field types are reported at compile time:
+///
+/// ```compile_fail
+/// use btf_rs::utils::raw::CBtfType;
+///
+/// #[derive(CBtfType)]
+/// struct Record {
+///     value: f32,
+/// }
+/// ```
+///
+/// As well as structs without named fields:
+///
+/// ```compile_fail
+/// use btf_rs::utils::raw::CBtfType;
+///
+/// #[derive(CBtfType)]
+/// struct Record(u32, u32);
+/// ```
+#[proc_macro_derive(CBtfType)]
+pub fn derive_cbtf_type(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    cbtf_type(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn from_btf(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let fields = named_fields(&input, "FromBtf")?;
+
+    let name = btf_name(&input.attrs)?
+        .unwrap_or_else(|| LitStr::new(&ident.to_string(), Span::call_site()));
+
+    let mut btf_fields = Vec::new();
+    let mut reads = Vec::new();
+    let mut consts = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        // Fields are named, checked above.
+        let Some(field_ident) = &field.ident else {
+            continue;
+        };
+        let ty = &field.ty;
+        let field_name = btf_name(&field.attrs)?.unwrap_or_else(|| {
+            LitStr::new(
+                field_ident.to_string().trim_start_matches("r#"),
+                Span::call_site(),
+            )
+        });
+        let r#const = format_ident!(
+            "BTF_{}",
+            field_ident
+                .to_string()
+                .trim_start_matches("r#")
+                .to_uppercase()
+        );
+
+        btf_fields.push(quote! { (#field_name, ::std::mem::size_of::<#ty>()) });
+        reads.push(quote! {
+            #field_ident: <#ty as ::btf_rs::utils::layout::RawField>::from_raw(
+                raw.get(*offsets.get(#i)?..)?,
+            )?
+        });
+        consts.push(quote! {
+            #[doc = concat!("Index of the `", #field_name, "` field offset.")]
+            pub const #r#const: usize = #i;
+        });
+    }
+
+    Ok(quote! {
+        impl ::btf_rs::utils::layout::FromBtf for #ident {
+            const BTF_NAME: &'static str = #name;
+            const BTF_FIELDS: &'static [(&'static str, usize)] = &[#(#btf_fields),*];
+
+            fn from_raw(raw: &[u8], offsets: &[usize]) -> ::std::option::Option<Self> {
+                ::std::option::Option::Some(#ident {
+                    #(#reads),*
+                })
+            }
+        }
+
+        impl #ident {
+            #(#consts)*
+        }
+    })
+}
+
+fn cbtf_type(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let fields = named_fields(&input, "CBtfType")?;
+
+    let mut reads = Vec::new();
+    let mut consts = Vec::new();
+    let mut offset = quote! { 0 };
+    for field in fields.iter() {
+        // Fields are named, checked above.
+        let Some(field_ident) = &field.ident else {
+            continue;
+        };
+        let ty = &field.ty;
+        let r#const = format_ident!(
+            "OFFSET_{}",
+            field_ident
+                .to_string()
+                .trim_start_matches("r#")
+                .to_uppercase()
+        );
+
+        reads.push(quote! {
+            #field_ident: <#ty as ::btf_rs::utils::raw::CBtfType>::from_reader(reader, endianness)?
+        });
+        consts.push(quote! {
+            #[doc = concat!("Offset of the `", stringify!(#field_ident), "` field in the raw representation.")]
+            pub const #r#const: usize = #offset;
+        });
+        offset = quote! { #offset + <#ty as ::btf_rs::utils::raw::CBtfType>::SIZE };
+    }
+
+    Ok(quote! {
+        impl ::btf_rs::utils::raw::CBtfType for #ident {
+            const SIZE: usize = #offset;
+
+            fn from_reader<R: ::std::io::Read>(
+                reader: &mut R,
+                endianness: ::btf_rs::Endianness,
+            ) -> ::std::io::Result<Self> {
+                ::std::io::Result::Ok(#ident {
+                    #(#reads),*
+                })
+            }
+        }
+
+        impl #ident {
+            #(#consts)*
+        }
+    })
+}
+
+// Get the named fields of a non-generic struct, failing otherwise.
+fn named_fields<'a>(
+    input: &'a DeriveInput,
+    derive: &str,
+) -> Result<&'a Punctuated<Field, Token![,]>> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            format!("{derive} can't be derived for generic structs"),
+        ));
+    }
+
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(Error::new_spanned(
+                &input.ident,
+                format!("{derive} can only be derived for structs with named fields"),
+            )),
+        },
+        _ => Err(Error::new_spanned(
+            &input.ident,
+            format!("{derive} can only be derived for structs"),
+        )),
+    }
+}
+
+// Get the name set using `#[btf(name = "...")]`, if any.
+fn btf_name(attrs: &[Attribute]) -> Result<Option<LitStr>> {
+    let mut name = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("btf")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported btf attribute"))
+            }
+        })?;
+    }
+    Ok(name)
+}
@@ -0,0 +1,16 @@
+//! Parse arbitrary data and, if it is valid BTF, check deduplicating it gives
+//! a valid object which can't be deduplicated further.
+#![no_main]
+
+use btf_rs::Btf;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(btf) = Btf::from_bytes(data) else {
+        return;
+    };
+
+    let dedup = btf.dedup().expect("Could not deduplicate parsed BTF");
+    assert!(dedup.type_ids().end <= btf.type_ids().end);
+    assert!(dedup.dedup().unwrap().eq_structural(&dedup));
+});
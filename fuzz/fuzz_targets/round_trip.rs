@@ -0,0 +1,17 @@
+//! Parse arbitrary data and, if it is valid BTF, check writing it back gives
+//! the same object.
+#![no_main]
+
+use btf_rs::Btf;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(btf) = Btf::from_bytes(data) else {
+        return;
+    };
+
+    let raw = btf.to_bytes().expect("Could not write parsed BTF");
+    let parsed = Btf::from_bytes(&raw).expect("Could not parse written BTF");
+    assert!(parsed.eq_structural(&btf));
+    assert_eq!(parsed.to_bytes().unwrap(), raw);
+});
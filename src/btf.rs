@@ -1,23 +1,414 @@
 #![allow(dead_code)]
 
 use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::HashMap,
     convert::AsRef,
+    fmt,
     fs::File,
-    io::{BufReader, Cursor, Read},
+    hash::Hash,
+    io::{BufReader, Cursor, Read, Seek, Write},
+    iter::FusedIterator,
+    ops::Range,
     path::Path,
     sync::Arc,
 };
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 
 use crate::cbtf;
+use crate::metrics::Lookup;
+#[cfg(feature = "metrics")]
+use crate::metrics::{Counters, Metrics};
 use crate::obj::BtfObj;
 
 /// Main representation of a parsed BTF object. Provides helpers to resolve
 /// types and their associated names.
+///
+/// Types can reference other types defined later in the BTF object (e.g. a
+/// pointer to a struct defined afterwards), references are only resolved on
+/// lookup. Use `Btf::dangling_references()` to check all of them are valid.
+///
+/// Parsed data is immutable and shared between clones, which makes cloning a
+/// `Btf` object cheap; clones can be sent to other threads to share a single
+/// parsed object (e.g. the kernel BTF) without parsing it again. Clones share
+/// their lookup statistics too (see `Btf::metrics()`).
+///
+/// `Btf` objects, as well as the types returned by lookups, are `Send` and
+/// `Sync`. This is checked at compile time and is part of the API.
+#[derive(Clone)]
 pub struct Btf {
     obj: Arc<BtfObj>,
     base: Option<Arc<BtfObj>>,
+    source: SourceKind,
+    order: ResolutionOrder,
+    // Name of the source of the object, used in error messages.
+    name: Option<Arc<str>>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<Counters>,
+}
+
+// Users share Btf objects and the types they return between threads, make
+// sure nothing (e.g. a new backend or a cache) breaks this.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Btf>();
+    assert_send_sync::<Type>();
+    assert_send_sync::<NamedType>();
+    assert_send_sync::<IdMap>();
+};
+
+/// Origin of the data a [`Btf`] object was constructed from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SourceKind {
+    /// A raw BTF file.
+    File,
+    /// A byte slice.
+    Bytes,
+    /// A generic reader, see `Btf::from_reader()`.
+    Reader,
+    /// The .BTF section of an ELF file.
+    Elf,
+    /// BTF information exposed by the running kernel (e.g. files in
+    /// `/sys/kernel/btf`).
+    Kernel,
+}
+
+/// Options controlling how BTF objects are parsed, used by the
+/// `*_with_options` constructors of [`Btf`].
+///
+/// ```no_run
+/// use btf_rs::{Btf, BtfOptions, StringDecoding};
+///
+/// let opts = BtfOptions::new().string_decoding(StringDecoding::Lossy);
+/// let btf = Btf::from_file_with_options("/sys/kernel/btf/vmlinux", &opts).unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BtfOptions {
+    pub(crate) strings: StringDecoding,
+    pub(crate) check_references: bool,
+    pub(crate) source_name: Option<String>,
+    pub(crate) order: ResolutionOrder,
+    pub(crate) capacity: Option<(usize, usize)>,
+    pub(crate) tolerant: bool,
+    pub(crate) kind_layouts: Vec<(u32, usize, usize)>,
+    pub(crate) keep_raw_records: bool,
+}
+
+impl BtfOptions {
+    /// Construct the default set of options.
+    pub fn new() -> BtfOptions {
+        BtfOptions::default()
+    }
+
+    /// Set how strings are decoded. Defaults to [`StringDecoding::Strict`].
+    pub fn string_decoding(mut self, decoding: StringDecoding) -> BtfOptions {
+        self.strings = decoding;
+        self
+    }
+
+    /// Check all the type references point to existing types once parsing is
+    /// done, making it fail otherwise. See `Btf::dangling_references()`.
+    /// Disabled by default.
+    pub fn check_references(mut self, check: bool) -> BtfOptions {
+        self.check_references = check;
+        self
+    }
+
+    /// Name of the source of the BTF object (e.g. a module name), which is
+    /// then part of the lookup error messages. Objects of a
+    /// [`BtfCollection`](crate::utils::collection::BtfCollection) are
+    /// automatically named.
+    pub fn source_name<S: Into<String>>(mut self, name: S) -> BtfOptions {
+        self.source_name = Some(name.into());
+        self
+    }
+
+    /// Set the order of the results of name lookups of split BTF objects.
+    /// Defaults to [`ResolutionOrder::BaseFirst`].
+    pub fn resolution_order(mut self, order: ResolutionOrder) -> BtfOptions {
+        self.order = order;
+        self
+    }
+
+    /// Set the expected number of types and strings of the object, used to
+    /// pre-allocate its internal tables. By default those are estimated from
+    /// the size of the type and string sections, using averages measured on
+    /// kernel BTF. Using exact numbers avoids resizing tables while parsing
+    /// objects whose content differs from kernel BTF.
+    pub fn capacity(mut self, types: usize, strings: usize) -> BtfOptions {
+        self.capacity = Some((types, strings));
+        self
+    }
+
+    /// Skip the type records which can't be parsed (e.g. vendor specific
+    /// kinds) instead of failing. The size of a skipped record is computed
+    /// from the layout of its kind, known for standard kinds and set for
+    /// others using `BtfOptions::kind_layout()`; records whose size can't be
+    /// computed still make parsing fail. Ids of skipped records are left
+    /// unused, to keep the numbering of the other types, and reported by
+    /// `Btf::skipped_types()`. Disabled by default.
+    pub fn tolerant(mut self, tolerant: bool) -> BtfOptions {
+        self.tolerant = tolerant;
+        self
+    }
+
+    /// Set the layout of a kind, used to skip records in tolerant mode (see
+    /// `BtfOptions::tolerant()`): records are made of the common type header,
+    /// followed by `info_size` bytes and `vlen` elements of `elem_size`
+    /// bytes. Takes precedence over the layout of standard kinds. Padding
+    /// made of zeroed records can be skipped by setting an empty layout for
+    /// kind 0.
+    pub fn kind_layout(mut self, kind: u8, info_size: usize, elem_size: usize) -> BtfOptions {
+        self.kind_layouts.push((kind as u32, info_size, elem_size));
+        self
+    }
+
+    /// Keep a copy of the original type records, so
+    /// [`dump_record`](crate::utils::debug::dump_record) shows their exact
+    /// bytes instead of re-encoding the parsed types. This costs a copy of the
+    /// type section. Disabled by default.
+    pub fn keep_raw_records(mut self, keep: bool) -> BtfOptions {
+        self.keep_raw_records = keep;
+        self
+    }
+}
+
+/// Order of the results of name lookups, when those can be found in both a
+/// base BTF object and split ones.
+/// `Btf` objects default to [`ResolutionOrder::BaseFirst`] while
+/// [`BtfCollection`](crate::utils::collection::BtfCollection) ones default to
+/// [`ResolutionOrder::SplitFirst`], as they historically did.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ResolutionOrder {
+    /// Results from the base BTF object come first.
+    #[default]
+    BaseFirst,
+    /// Results from the split BTF object(s) come first.
+    SplitFirst,
+}
+
+/// How strings of the BTF string section are decoded.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum StringDecoding {
+    /// Strings must be valid UTF-8, parsing fails otherwise.
+    #[default]
+    Strict,
+    /// Invalid UTF-8 sequences are replaced by `U+FFFD`. The original bytes
+    /// can be retrieved using [`Btf::resolve_name_bytes`].
+    Lossy,
+}
+
+/// Id of a BTF type. Ids are unique in a BTF object; for split BTF objects the
+/// ids of their own types follow the ones of the base types.
+///
+/// Conversions from and to `u32` are provided for interoperability.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct TypeId(u32);
+
+impl From<u32> for TypeId {
+    fn from(id: u32) -> TypeId {
+        TypeId(id)
+    }
+}
+
+impl From<TypeId> for u32 {
+    fn from(id: TypeId) -> u32 {
+        id.0
+    }
+}
+
+impl PartialEq<u32> for TypeId {
+    fn eq(&self, other: &u32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<TypeId> for u32 {
+    fn eq(&self, other: &TypeId) -> bool {
+        *self == other.0
+    }
+}
+
+impl fmt::Display for TypeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Range of type ids, iterated in increasing order. See `Btf::type_ids()`.
+///
+/// Comparisons with a `Range<u32>` are provided for interoperability.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct TypeIds(Range<u32>);
+
+impl TypeIds {
+    /// First id of the range.
+    pub fn start(&self) -> TypeId {
+        TypeId(self.0.start)
+    }
+
+    /// Id following the last one of the range.
+    pub fn end(&self) -> TypeId {
+        TypeId(self.0.end)
+    }
+
+    /// Check if an id is part of the range.
+    pub fn contains<I: Into<TypeId>>(&self, id: I) -> bool {
+        self.0.contains(&id.into().0)
+    }
+}
+
+impl Iterator for TypeIds {
+    type Item = TypeId;
+
+    fn next(&mut self) -> Option<TypeId> {
+        self.0.next().map(TypeId)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for TypeIds {
+    fn next_back(&mut self) -> Option<TypeId> {
+        self.0.next_back().map(TypeId)
+    }
+}
+
+impl ExactSizeIterator for TypeIds {}
+
+impl FusedIterator for TypeIds {}
+
+impl PartialEq<Range<u32>> for TypeIds {
+    fn eq(&self, other: &Range<u32>) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Mapping between type ids, e.g. from the ids of a BTF object to the ones of
+/// a transformed version of it (see `Btf::remap_ids()`). Ids not in the map
+/// are mapped to themselves.
+///
+/// ```
+/// use btf_rs::{IdMap, TypeId};
+///
+/// let first: IdMap = [(1.into(), 2.into())].into_iter().collect();
+/// let then: IdMap = [(2.into(), 3.into()), (4.into(), 5.into())].into_iter().collect();
+///
+/// let map = first.compose(&then);
+/// assert_eq!(map.apply(1), 3);
+/// assert_eq!(map.apply(2), 3);
+/// assert_eq!(map.apply(4), 5);
+/// assert_eq!(map.apply(6), 6);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IdMap(HashMap<u32, u32>);
+
+impl IdMap {
+    /// Create an empty map, i.e. mapping all ids to themselves.
+    pub fn new() -> IdMap {
+        IdMap::default()
+    }
+
+    /// Map `from` to `to`, returning the previous mapping of `from` if any.
+    pub fn insert<I: Into<TypeId>, J: Into<TypeId>>(&mut self, from: I, to: J) -> Option<TypeId> {
+        self.0.insert(from.into().0, to.into().0).map(TypeId::from)
+    }
+
+    /// Get the id `id` is explicitly mapped to, if any.
+    pub fn get<I: Into<TypeId>>(&self, id: I) -> Option<TypeId> {
+        self.0.get(&id.into().0).copied().map(TypeId::from)
+    }
+
+    /// Get the id `id` is mapped to, which is `id` itself if not explicitly
+    /// mapped.
+    pub fn apply<I: Into<TypeId>>(&self, id: I) -> TypeId {
+        let id = id.into();
+        self.get(id).unwrap_or(id)
+    }
+
+    /// Build the map equivalent to applying this map, then `then`.
+    pub fn compose(&self, then: &IdMap) -> IdMap {
+        let mut map = HashMap::new();
+        for (from, to) in self.0.iter() {
+            map.insert(*from, then.apply(*to).0);
+        }
+        for (from, to) in then.0.iter() {
+            map.entry(*from).or_insert(*to);
+        }
+        // Drop the entries which are not needed anymore.
+        map.retain(|from, to| from != to);
+        IdMap(map)
+    }
+
+    /// Get the number of explicitly mapped ids.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check if no id is explicitly mapped.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the explicit mappings, as (from, to) pairs in no
+    /// particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (TypeId, TypeId)> + '_ {
+        self.0.iter().map(|(from, to)| (TypeId(*from), TypeId(*to)))
+    }
+}
+
+impl FromIterator<(TypeId, TypeId)> for IdMap {
+    fn from_iter<T: IntoIterator<Item = (TypeId, TypeId)>>(iter: T) -> IdMap {
+        IdMap(iter.into_iter().map(|(from, to)| (from.0, to.0)).collect())
+    }
+}
+
+/// Offset of a string in the BTF string section. For split BTF objects, the
+/// offsets of their own strings follow the ones of the base strings: an
+/// offset lower than the length of the base string section refers to a base
+/// string.
+///
+/// Conversions from and to `u32` are provided for interoperability.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct StrOffset(u32);
+
+impl From<u32> for StrOffset {
+    fn from(offset: u32) -> StrOffset {
+        StrOffset(offset)
+    }
+}
+
+impl From<StrOffset> for u32 {
+    fn from(offset: StrOffset) -> u32 {
+        offset.0
+    }
+}
+
+impl PartialEq<u32> for StrOffset {
+    fn eq(&self, other: &u32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<StrOffset> for u32 {
+    fn eq(&self, other: &StrOffset) -> bool {
+        *self == other.0
+    }
+}
+
+impl fmt::Display for StrOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 impl Btf {
@@ -25,141 +416,1151 @@ impl Btf {
     /// use. Trying to open split BTF files using this function will fail. For split BTF
     /// files use `Btf::from_split_file()`.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Btf> {
+        Self::from_file_with_options(path, &BtfOptions::default())
+    }
+
+    /// Same as `from_file()`, using custom parsing options.
+    pub fn from_file_with_options<P: AsRef<Path>>(path: P, options: &BtfOptions) -> Result<Btf> {
+        let path = path.as_ref();
         Ok(Btf {
-            obj: Arc::new(BtfObj::from_reader(
-                &mut BufReader::new(File::open(path)?),
-                None,
-            )?),
+            source: Self::file_source(path),
+            obj: Arc::new(
+                BtfObj::from_reader(&mut BufReader::new(Self::open(path)?), None, options)
+                    .with_context(|| format!("Could not parse BTF file {}", path.display()))?,
+            ),
             base: None,
+            order: options.order,
+            name: options.source_name.as_deref().map(Arc::from),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
         })
     }
 
     /// Parse a split BTF object file and construct a Rust representation for later
     /// use. A base Btf object must be provided.
     pub fn from_split_file<P: AsRef<Path>>(path: P, base: &Btf) -> Result<Btf> {
+        Self::from_split_file_with_options(path, base, &BtfOptions::default())
+    }
+
+    /// Same as `from_split_file()`, using custom parsing options.
+    pub fn from_split_file_with_options<P: AsRef<Path>>(
+        path: P,
+        base: &Btf,
+        options: &BtfOptions,
+    ) -> Result<Btf> {
         if !path.as_ref().is_file() {
             bail!("Invalid BTF file {}", path.as_ref().display());
         }
 
+        let path = path.as_ref();
+        Ok(Btf {
+            source: Self::file_source(path),
+            obj: Arc::new(
+                BtfObj::from_reader(
+                    &mut BufReader::new(Self::open(path)?),
+                    Some(base.obj.clone()),
+                    options,
+                )
+                .with_context(|| format!("Could not parse split BTF file {}", path.display()))?,
+            ),
+            base: Some(base.obj.clone()),
+            order: options.order,
+            name: options.source_name.as_deref().map(Arc::from),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
+        })
+    }
+
+    /// Performs the same actions as from_file(), but reading from any seekable
+    /// source (e.g. an archive entry). The BTF object is read starting at the
+    /// current position of the reader, which does not need to be buffered.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Btf> {
+        Self::from_reader_with_options(reader, &BtfOptions::default())
+    }
+
+    /// Same as `from_reader()`, using custom parsing options.
+    pub fn from_reader_with_options<R: Read + Seek>(
+        reader: R,
+        options: &BtfOptions,
+    ) -> Result<Btf> {
         Ok(Btf {
             obj: Arc::new(BtfObj::from_reader(
-                &mut BufReader::new(File::open(path)?),
-                Some(base.obj.clone()),
+                &mut BufReader::new(reader),
+                None,
+                options,
             )?),
-            base: Some(base.obj.clone()),
+            base: None,
+            source: SourceKind::Reader,
+            order: options.order,
+            name: options.source_name.as_deref().map(Arc::from),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
+        })
+    }
+
+    /// Performs the same actions as from_split_file(), but reading from any
+    /// seekable source. See `Btf::from_reader()`.
+    pub fn from_split_reader<R: Read + Seek>(reader: R, base: &Btf) -> Result<Btf> {
+        Self::from_split_reader_with_options(reader, base, &BtfOptions::default())
+    }
+
+    /// Same as `from_split_reader()`, using custom parsing options.
+    pub fn from_split_reader_with_options<R: Read + Seek>(
+        reader: R,
+        base: &Btf,
+        options: &BtfOptions,
+    ) -> Result<Btf> {
+        let base = base.obj.clone();
+        Ok(Btf {
+            obj: Arc::new(BtfObj::from_reader(
+                &mut BufReader::new(reader),
+                Some(base.clone()),
+                options,
+            )?),
+            base: Some(base),
+            source: SourceKind::Reader,
+            order: options.order,
+            name: options.source_name.as_deref().map(Arc::from),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
         })
     }
 
     /// Performs the same actions as from_file(), but fed with a byte slice.
     pub fn from_bytes(bytes: &[u8]) -> Result<Btf> {
+        Self::from_bytes_with_options(bytes, &BtfOptions::default())
+    }
+
+    /// Same as `from_bytes()`, using custom parsing options.
+    pub fn from_bytes_with_options(bytes: &[u8], options: &BtfOptions) -> Result<Btf> {
         Ok(Btf {
-            obj: Arc::new(BtfObj::from_reader(&mut Cursor::new(bytes), None)?),
+            obj: Arc::new(BtfObj::from_reader(&mut Cursor::new(bytes), None, options)?),
             base: None,
+            source: SourceKind::Bytes,
+            order: options.order,
+            name: options.source_name.as_deref().map(Arc::from),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
         })
     }
 
     /// Performs the same actions as from_split_file(), but fed with a byte slice.
     pub fn from_split_bytes(bytes: &[u8], base: &Btf) -> Result<Btf> {
+        Self::from_split_bytes_with_options(bytes, base, &BtfOptions::default())
+    }
+
+    /// Same as `from_split_bytes()`, using custom parsing options.
+    pub fn from_split_bytes_with_options(
+        bytes: &[u8],
+        base: &Btf,
+        options: &BtfOptions,
+    ) -> Result<Btf> {
         let base = base.obj.clone();
         Ok(Btf {
             obj: Arc::new(BtfObj::from_reader(
                 &mut Cursor::new(bytes),
                 Some(base.clone()),
+                options,
             )?),
             base: Some(base),
+            source: SourceKind::Bytes,
+            order: options.order,
+            name: options.source_name.as_deref().map(Arc::from),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
         })
     }
 
-    /// Find a list of BTF ids using their name as a key.
-    pub fn resolve_ids_by_name(&self, name: &str) -> Result<Vec<u32>> {
-        let mut ids = Vec::new();
+    /// Get the kind of source the BTF object was constructed from.
+    pub fn source(&self) -> SourceKind {
+        self.source
+    }
 
-        if let Some(base) = &self.base {
-            if let Ok(mut ids_base) = base.resolve_ids_by_name(name) {
-                ids.append(&mut ids_base);
+    /// Check if the BTF object is a split one, built on top of a base BTF
+    /// object.
+    pub fn is_split(&self) -> bool {
+        self.base.is_some()
+    }
+
+    /// Get an identifier of the parsed BTF object, unique in the process. It
+    /// can be used to detect a BTF object was changed (e.g. replaced in a
+    /// collection) when caching information about it. For split BTF objects
+    /// this identifies the split part only.
+    pub fn fingerprint(&self) -> u64 {
+        self.obj.fingerprint()
+    }
+
+    /// Get the flags of the BTF header. For split BTF objects, those are the
+    /// flags of the split object header.
+    pub fn flags(&self) -> u8 {
+        self.obj.flags()
+    }
+
+    /// Get the byte order of the raw BTF object. For split BTF objects, this
+    /// is the byte order of the split object.
+    pub fn endianness(&self) -> cbtf::Endianness {
+        self.obj.endianness()
+    }
+
+    /// Serialize the BTF object back to raw BTF bytes, which can be parsed
+    /// again using `Btf::from_bytes()`. For split BTF objects only the split
+    /// part is serialized, to be parsed using `Btf::from_split_bytes()` with
+    /// the same base. Types modified after parsing (e.g. removed struct
+    /// members) are written as modified; see also [`crate::encode`] to build
+    /// BTF objects from scratch.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        self.obj.to_bytes(self.base.as_deref())
+    }
+
+    /// Deduplicate the types of the BTF object, merging structurally
+    /// identical ones and rewriting references accordingly, similarly to
+    /// libbpf's `btf__dedup()`. Types are identical if they have the same
+    /// kind, name and properties and if the types they reference are
+    /// identical too; this includes types referencing each other (e.g. two
+    /// copies of a linked list struct). Unlike libbpf, forward declarations
+    /// are not merged with the matching struct or union definitions.
+    ///
+    /// The first type of each set of identical types is kept, the resulting
+    /// object having the same types in the same order, minus the duplicates.
+    /// Ids are thus likely to change, see `Btf::dedup_map()`. The string
+    /// section is kept as-is. For split BTF objects only the split types are
+    /// deduplicated, the returned object using the same base.
+    pub fn dedup(&self) -> Result<Btf> {
+        self.remap_ids(&self.dedup_map()?)
+    }
+
+    /// Get the mapping between the ids of the types of the object and the ids
+    /// they would have once deduplicated, see `Btf::dedup()`.
+    pub fn dedup_map(&self) -> Result<IdMap> {
+        Ok(IdMap(self.obj.dedup_map()?))
+    }
+
+    /// Build a new BTF object by moving types to new ids, following `map`.
+    /// References are rewritten accordingly and the string section is kept
+    /// as-is. For split BTF objects only the split types are moved, the
+    /// returned object using the same base.
+    ///
+    /// Types mapped outside of the range of ids of the object are dropped. If
+    /// multiple types are mapped to the same id, the one with the lowest
+    /// original id is kept and the others are dropped. This allows to merge or
+    /// remove types, references to them being redirected to their new id
+    /// (which can be the one of a base type). The ids of the types kept must
+    /// be contiguous, starting at the first id of the object, and
+    /// `Type::Void` must keep id 0.
+    pub fn remap_ids(&self, map: &IdMap) -> Result<Btf> {
+        let bytes = self.obj.remap(self.base.as_deref(), &map.0)?;
+
+        // Strings are written back unchanged, including the ones decoded in
+        // a lossy way when parsing the original object.
+        let options = BtfOptions::new().string_decoding(StringDecoding::Lossy);
+        let obj = BtfObj::from_reader(&mut Cursor::new(bytes), self.base.clone(), &options)?;
+        Ok(Btf {
+            obj: Arc::new(obj),
+            base: self.base.clone(),
+            source: self.source,
+            order: self.order,
+            name: self.name.clone(),
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
+        })
+    }
+
+    /// Check two BTF objects are structurally identical: they define the same
+    /// types, with the same names and properties and referencing identical
+    /// types, regardless of their ids and order. Comparing the raw
+    /// representation would also depend on the byte order, string offsets and
+    /// layout of the string section. For split BTF objects the base types are
+    /// compared too.
+    ///
+    /// Each type of an object must have an identical counterpart in the other
+    /// one, and duplicates are taken into account: an object and its
+    /// deduplicated version (see `Btf::dedup()`) are only identical if there
+    /// was nothing to deduplicate.
+    pub fn eq_structural(&self, other: &Btf) -> bool {
+        let (ids, other_ids) = (self.id_range(), other.id_range());
+        if ids.len() != other_ids.len() {
+            return false;
+        }
+
+        let mut keys = Vec::with_capacity(ids.len() * 2);
+        let mut refs = Vec::with_capacity(ids.len() * 2);
+        for (btf, ids, start) in [(self, ids.clone(), 0), (other, other_ids, ids.len())] {
+            for id in ids.clone() {
+                let Ok(key) = btf.type_signature(id) else {
+                    return false;
+                };
+                keys.push(key);
+
+                let Ok(r#type) = btf.type_by_id(id) else {
+                    return false;
+                };
+                refs.push(
+                    r#type
+                        .references()
+                        .into_iter()
+                        .map(|r| match ids.contains(&r) {
+                            true => Ok(start + (r - ids.start) as usize),
+                            false => Err(r),
+                        })
+                        .collect(),
+                );
             }
         }
-        if let Ok(mut ids_obj) = self.resolve_split_ids_by_name(name) {
-            ids.append(&mut ids_obj);
+
+        let groups = group_types(keys, refs);
+        let mut counts: HashMap<u64, i64> = HashMap::new();
+        let (own, others) = groups.split_at(ids.len());
+        own.iter().for_each(|g| *counts.entry(*g).or_default() += 1);
+        others
+            .iter()
+            .for_each(|g| *counts.entry(*g).or_default() -= 1);
+        counts.values().all(|count| *count == 0)
+    }
+
+    // Get a representation of a type, independent of the raw layout of its
+    // BTF object: its raw data using a fixed byte order and without string
+    // offsets nor references, and the strings it references.
+    fn type_signature(&self, id: u32) -> Result<(Vec<u8>, Vec<Option<&str>>)> {
+        let offsets = RefCell::new(Vec::new());
+        let mut r#type = self.type_by_id(id)?.clone();
+        r#type.remap(
+            |_| 0,
+            |offset| {
+                offsets.borrow_mut().push(offset);
+                0
+            },
+        );
+
+        let mut raw = Vec::new();
+        r#type.write(&mut raw, &cbtf::Endianness::Little)?;
+        let strings = offsets
+            .into_inner()
+            .into_iter()
+            .map(|offset| self.str_by_offset(offset))
+            .collect();
+        Ok((raw, strings))
+    }
+
+    // Length of the string section of the object, i.e. the first string
+    // offset of split BTF objects using it as a base.
+    pub(crate) fn str_len(&self) -> u32 {
+        self.obj.str_len()
+    }
+
+    /// Get a snapshot of the lookup statistics of this object.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.snapshot()
+    }
+
+    // Run a lookup, recording it in the statistics.
+    #[cfg(feature = "metrics")]
+    fn instrument<T, F: FnOnce() -> Result<T>>(&self, kind: Lookup, lookup: F) -> Result<T> {
+        let start = std::time::Instant::now();
+        let res = lookup();
+        self.metrics.record(kind, res.is_ok(), start.elapsed());
+        res
+    }
+
+    // Run a lookup; statistics are only collected with the metrics feature.
+    #[cfg(not(feature = "metrics"))]
+    #[inline(always)]
+    fn instrument<T, F: FnOnce() -> Result<T>>(&self, _: Lookup, lookup: F) -> Result<T> {
+        lookup()
+    }
+
+    // Set the name of the source of the BTF object, used in lookup error
+    // messages. Clones sharing the parsed object keep their own name.
+    pub(crate) fn with_source_name(mut self, name: &str) -> Btf {
+        self.name = Some(Arc::from(name));
+        self
+    }
+
+    // Suffix of the error messages, identifying the source of the object.
+    fn in_source(&self) -> String {
+        match &self.name {
+            Some(name) => format!(" in {name}"),
+            None => String::new(),
         }
+    }
 
-        if ids.is_empty() {
-            bail!("No id linked to name {name}");
+    // Set the kind of source the BTF object was constructed from, for
+    // constructors built on top of the ones above.
+    pub(crate) fn with_source(mut self, source: SourceKind) -> Btf {
+        self.source = source;
+        self
+    }
+
+    // Open a BTF file. The underlying IO error is kept as the error source.
+    fn open(path: &Path) -> Result<File> {
+        File::open(path).with_context(|| format!("Could not open {}", path.display()))
+    }
+
+    // Guess the kind of source from a file path.
+    fn file_source(path: &Path) -> SourceKind {
+        match path.starts_with("/sys/kernel/btf") {
+            true => SourceKind::Kernel,
+            false => SourceKind::File,
         }
-        Ok(ids)
     }
 
-    /// Find a list of BTF ids using their name as a key, using the split BTF
-    /// definition only. For internal use only.
-    pub(crate) fn resolve_split_ids_by_name(&self, name: &str) -> Result<Vec<u32>> {
-        self.obj.resolve_ids_by_name(name)
+    /// Find a list of BTF ids using their name as a key. For split BTF the
+    /// results are ordered following the [`ResolutionOrder`] set in the
+    /// parsing options.
+    pub fn resolve_ids_by_name(&self, name: &str) -> Result<Vec<TypeId>> {
+        self.instrument(Lookup::Name, || {
+            let ids: Vec<TypeId> = self.ids_for_exact_name_bytes(name.as_bytes()).collect();
+            if ids.is_empty() {
+                bail!("No id linked to name {name}{}", self.in_source());
+            }
+            Ok(ids)
+        })
+    }
+
+    /// Same as `resolve_ids_by_name()` but using the bytes of the name as a
+    /// key, e.g. when the name comes from a C string. No UTF-8 validation is
+    /// performed. When strings were decoded using [`StringDecoding::Lossy`],
+    /// both the original bytes and the decoded ones can be used.
+    /// See `Btf::ids_for_exact_name_bytes()` for an allocation free version.
+    pub fn resolve_ids_by_name_bytes(&self, name: &[u8]) -> Result<Vec<TypeId>> {
+        self.instrument(Lookup::Name, || {
+            let ids: Vec<TypeId> = self.ids_for_exact_name_bytes(name).collect();
+            if ids.is_empty() {
+                bail!(
+                    "No id linked to name {}{}",
+                    String::from_utf8_lossy(name),
+                    self.in_source()
+                );
+            }
+            Ok(ids)
+        })
+    }
+
+    /// Iterate over the ids linked to a name, following the resolution order
+    /// for split BTF (see [`ResolutionOrder`]).
+    /// Unlike `resolve_ids_by_name()` this does not allocate nor fail, which
+    /// makes it suitable for high frequency lookups; an unknown name results
+    /// in an empty iterator.
+    pub fn ids_for_exact_name<'a>(&'a self, name: &str) -> impl Iterator<Item = TypeId> + 'a {
+        self.ids_for_exact_name_bytes(name.as_bytes())
+    }
+
+    /// Same as `ids_for_exact_name()` but using the bytes of the name as a key,
+    /// see `resolve_ids_by_name_bytes()`. This is the allocation free path for
+    /// lookups of names coming from C strings: no UTF-8 validation is
+    /// performed, nothing is allocated and an unknown name results in an
+    /// empty iterator.
+    pub fn ids_for_exact_name_bytes<'a>(
+        &'a self,
+        name: &[u8],
+    ) -> impl Iterator<Item = TypeId> + 'a {
+        let base = match &self.base {
+            Some(base) => base.ids_by_name_bytes(name),
+            None => &[],
+        };
+        let split = self.obj.ids_by_name_bytes(name);
+        let (first, second) = match self.order {
+            ResolutionOrder::BaseFirst => (base, split),
+            ResolutionOrder::SplitFirst => (split, base),
+        };
+        first.iter().chain(second).map(|id| TypeId(*id))
+    }
+
+    /// Get the BTF ids linked to a name, using the split BTF definition only.
+    /// For internal use only.
+    pub(crate) fn split_ids_by_name(&self, name: &str) -> &[u32] {
+        self.obj.ids_by_name_bytes(name.as_bytes())
     }
 
     /// Find a BTF type using its id as a key.
-    pub fn resolve_type_by_id(&self, id: u32) -> Result<Type> {
-        match &self.base {
-            Some(base) => base
-                .resolve_type_by_id(id)
-                .or_else(|_| self.obj.resolve_type_by_id(id)),
-            None => self.obj.resolve_type_by_id(id),
-        }
+    pub fn resolve_type_by_id<I: Into<TypeId>>(&self, id: I) -> Result<Type> {
+        let id = id.into().0;
+        self.instrument(Lookup::Id, || Ok(self.type_by_id(id)?.clone()))
     }
 
     /// Find a list of BTF types using their name as a key.
     pub fn resolve_types_by_name(&self, name: &str) -> Result<Vec<Type>> {
-        let mut types = Vec::new();
+        self.instrument(Lookup::Name, || {
+            let mut types = Vec::new();
+            for id in self.ids_for_exact_name_bytes(name.as_bytes()) {
+                types.push(self.type_by_id(id.0)?.clone());
+            }
 
-        if let Some(base) = &self.base {
-            if let Ok(mut types_base) = base.resolve_types_by_name(name) {
-                types.append(&mut types_base);
+            if types.is_empty() {
+                // Keep "id" and not "type" below to be consitent with
+                // BtfObj::resolve_types_by_name.
+                bail!("No id linked to name {name}{}", self.in_source());
             }
-        }
-        if let Ok(mut types_obj) = self.resolve_split_types_by_name(name) {
-            types.append(&mut types_obj);
-        }
+            Ok(types)
+        })
+    }
 
-        if types.is_empty() {
-            // Keep "id" and not "type" below to be consitent with
-            // BtfObj::resolve_types_by_name.
-            bail!("No id linked to name {name}");
-        }
-        Ok(types)
+    /// Find a struct or union using its name as a key. If multiple types are
+    /// linked to the name, the first struct or union found is returned.
+    pub fn resolve_struct_by_name(&self, name: &str) -> Result<Struct> {
+        self.resolve_types_by_name(name)?
+            .into_iter()
+            .find_map(|t| match t {
+                Type::Struct(s) | Type::Union(s) => Some(s),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("No struct or union named {name}{}", self.in_source()))
     }
 
     /// Find a list of BTF types using their name as a key, using the split BTF
     /// definition only. For internal use only.
     pub(crate) fn resolve_split_types_by_name(&self, name: &str) -> Result<Vec<Type>> {
-        self.obj.resolve_types_by_name(name)
+        let ids = self.split_ids_by_name(name);
+        if ids.is_empty() {
+            bail!("No id linked to name {name}{}", self.in_source());
+        }
+        ids.iter()
+            .map(|id| Ok(self.type_by_id(*id)?.clone()))
+            .collect()
     }
 
     /// Resolve a name referenced by a Type which is defined in the current BTF
     /// object.
-    pub fn resolve_name<T: BtfType + ?Sized>(&self, r#type: &T) -> Result<String> {
+    pub fn resolve_name<T: AsBtfType + ?Sized>(&self, r#type: &T) -> Result<String> {
+        Ok(self.resolve_name_ref(r#type)?.to_string())
+    }
+
+    /// Same as `resolve_name()` but returns a reference to the name instead of
+    /// a copy, which avoids an allocation per lookup.
+    pub fn resolve_name_ref<T: AsBtfType + ?Sized>(&self, r#type: &T) -> Result<&str> {
+        let offset = u32::from(r#type.as_btf_type().get_name_offset()?);
+        self.str_by_offset(offset)
+            .ok_or_else(|| anyhow!("No string at offset {}{}", offset, self.in_source()))
+    }
+
+    /// Same as `resolve_name()` but returns the raw bytes of the name, as found
+    /// in the BTF string section. This differs from the bytes of the name
+    /// returned by `resolve_name()` only when it contained invalid UTF-8
+    /// sequences and was decoded using [`StringDecoding::Lossy`].
+    pub fn resolve_name_bytes<T: AsBtfType + ?Sized>(&self, r#type: &T) -> Result<Vec<u8>> {
+        let offset = u32::from(r#type.as_btf_type().get_name_offset()?);
+        match &self.base {
+            Some(base) => base
+                .str_bytes_by_offset(offset)
+                .or_else(|| self.obj.str_bytes_by_offset(offset)),
+            None => self.obj.str_bytes_by_offset(offset),
+        }
+        .map(|bytes| bytes.to_vec())
+        .ok_or_else(|| anyhow!("No string at offset {}{}", offset, self.in_source()))
+    }
+
+    /// Same as `resolve_type_by_id()` but the returned type carries its id and
+    /// resolved name, see [`NamedType`].
+    pub fn resolve_named_type_by_id<I: Into<TypeId>>(&self, id: I) -> Result<NamedType> {
+        let id = id.into();
+        let r#type = self.resolve_type_by_id(id)?;
+        Ok(self.named_type(id, r#type))
+    }
+
+    /// Same as `resolve_types_by_name()` but the returned types carry their id
+    /// and resolved name, see [`NamedType`].
+    pub fn resolve_named_types_by_name(&self, name: &str) -> Result<Vec<NamedType>> {
+        let types = self.instrument(Lookup::Name, || {
+            self.ids_for_exact_name_bytes(name.as_bytes())
+                .map(|id| Ok((id, self.type_by_id(id.0)?.clone())))
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        if types.is_empty() {
+            bail!("No id linked to name {name}{}", self.in_source());
+        }
+        Ok(types
+            .into_iter()
+            .map(|(id, r#type)| self.named_type(id, r#type))
+            .collect())
+    }
+
+    /// Same as `resolve_chained_type()` but the returned type carries its id
+    /// and resolved name, see [`NamedType`].
+    pub fn resolve_named_chained_type<T: AsBtfType + ?Sized>(
+        &self,
+        r#type: &T,
+    ) -> Result<NamedType> {
+        self.resolve_named_type_by_id(r#type.as_btf_type().get_type_id()?)
+    }
+
+    /// Find all the types whose name starts with `prefix` and, if `kind` is
+    /// set, of the given kind. Types are returned along with their id and
+    /// name, sorted by name and then following the resolution order. An empty
+    /// prefix matches all named types, whose `NamedType::name` is always set.
+    ///
+    /// ```no_run
+    /// use btf_rs::{Btf, Kind, Type};
+    ///
+    /// let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+    ///
+    /// // Dump all the enums defined in the kernel, with their values.
+    /// for t in btf.resolve_named_types("", Some(Kind::Enum)).unwrap() {
+    ///     let name = t.name.unwrap();
+    ///     if let Type::Enum(e) = t.r#type {
+    ///         for value in e.values(&btf) {
+    ///             let (member, value) = value.unwrap();
+    ///             println!("{name}: {member} = {value}");
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn resolve_named_types(&self, prefix: &str, kind: Option<Kind>) -> Result<Vec<NamedType>> {
+        let mut types = Vec::new();
+        for name in self.names().into_iter().filter(|n| n.starts_with(prefix)) {
+            for id in self.ids_for_exact_name_bytes(name.as_bytes()) {
+                let r#type = self.type_by_id(id.0)?;
+                if kind.is_some_and(|kind| r#type.kind() != kind) {
+                    continue;
+                }
+                let named = self.named_type(id, r#type.clone());
+                if named.name.is_some() {
+                    types.push(named);
+                }
+            }
+        }
+        Ok(types)
+    }
+
+    // Attach its id and name to a type. Names are shared with the string
+    // cache, so this does not allocate.
+    fn named_type(&self, id: TypeId, r#type: Type) -> NamedType {
+        let name = r#type
+            .as_btf_type()
+            .get_name_offset()
+            .ok()
+            .map(u32::from)
+            .and_then(|offset| match &self.base {
+                Some(base) => base
+                    .str_arc_by_offset(offset)
+                    .or_else(|| self.obj.str_arc_by_offset(offset)),
+                None => self.obj.str_arc_by_offset(offset),
+            })
+            .filter(|name| !name.is_empty());
+
+        NamedType { id, name, r#type }
+    }
+
+    /// Get the number of members of a struct or union, given its id, without
+    /// materializing the full type.
+    pub fn struct_member_count<I: Into<TypeId>>(&self, id: I) -> Result<usize> {
+        let id = id.into().0;
+        match self.type_by_id(id)? {
+            Type::Struct(s) | Type::Union(s) => Ok(s.members.len()),
+            x => bail!("Type with id {id} is a {}, not a struct or union", x.name()),
+        }
+    }
+
+    /// Check if a struct or union, given its id, has a member named `name`,
+    /// without materializing the full type. Members of anonymous struct or
+    /// union members are not looked into.
+    pub fn has_member<I: Into<TypeId>>(&self, id: I, name: &str) -> Result<bool> {
+        let id = id.into().0;
+        match self.type_by_id(id)? {
+            Type::Struct(s) | Type::Union(s) => Ok(s
+                .members
+                .iter()
+                .any(|m| self.str_by_offset(m.btf_member.name_off) == Some(name))),
+            x => bail!("Type with id {id} is a {}, not a struct or union", x.name()),
+        }
+    }
+
+    /// List the references to non-existing types, as pairs of the id of the
+    /// referencing type and of the referenced one, sorted by the former. For
+    /// split BTF only the types defined in the split object are checked.
+    pub fn dangling_references(&self) -> Vec<(TypeId, TypeId)> {
+        self.obj
+            .dangling_references(self.base.as_deref())
+            .into_iter()
+            .map(|(from, to)| (TypeId(from), TypeId(to)))
+            .collect()
+    }
+
+    /// List the type records skipped while parsing the object in tolerant
+    /// mode, see `BtfOptions::tolerant()`. Their ids are not linked to any
+    /// type. For split BTF only the records of the split object are listed.
+    pub fn skipped_types(&self) -> &[SkippedType] {
+        self.obj.skipped()
+    }
+
+    /// Get the range of all the type ids, in increasing order. For split BTF
+    /// this includes the ids of the base types.
+    pub fn type_ids(&self) -> TypeIds {
+        TypeIds(self.id_range())
+    }
+
+    // Get the range of all the type ids, see `Btf::type_ids()`.
+    pub(crate) fn id_range(&self) -> Range<u32> {
+        match &self.base {
+            Some(base) => base.ids().start..self.obj.ids().end,
+            None => self.obj.ids(),
+        }
+    }
+
+    /// Iterate over all the types, as (id, type) pairs in increasing id order.
+    /// This includes `Type::Void` (id 0) and, for split BTF, the base types.
+    pub fn iter(&self) -> impl Iterator<Item = (TypeId, Type)> + '_ {
+        self.id_range()
+            .filter_map(|id| Some((TypeId(id), self.type_by_id(id).ok()?.clone())))
+    }
+
+    /// Same as `Btf::iter()`, but only iterating over the types defined in
+    /// this object. For split BTF the base types are skipped.
+    pub fn split_iter(&self) -> impl Iterator<Item = (TypeId, Type)> + '_ {
+        self.obj
+            .ids()
+            .filter_map(|id| Some((TypeId(id), self.obj.type_by_id(id)?.clone())))
+    }
+
+    /// Get the range of the ids of the types defined in this object only. For
+    /// split BTF the base types are not included.
+    pub(crate) fn split_type_ids(&self) -> Range<u32> {
+        self.obj.ids()
+    }
+
+    /// Iterate over the names linked to at least one type defined in this
+    /// object only, in no particular order. For internal use only.
+    pub(crate) fn split_names(&self) -> impl Iterator<Item = &str> {
+        self.obj.names()
+    }
+
+    /// Get all the names linked to at least one type, sorted and without
+    /// duplicates. For split BTF this includes the names of the base types.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = match &self.base {
+            Some(base) => base.names().chain(self.obj.names()).collect(),
+            None => self.obj.names().collect(),
+        };
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Find a BTF type using its id as a key, retrieving only its kind and
+    /// common information (name, size or referenced type, number of
+    /// entries). This does not copy the type specific data (e.g. struct
+    /// members or function parameters), which makes it cheaper when scanning
+    /// large numbers of ids.
+    pub fn resolve_type_shallow<I: Into<TypeId>>(&self, id: I) -> Result<ShallowType> {
+        let r#type = self.type_by_id(id.into().0)?;
+        Ok(ShallowType {
+            kind: r#type.kind(),
+            btf_type: r#type.btf_type().copied(),
+        })
+    }
+
+    // Get a reference to a BTF type using its id as a key.
+    fn type_by_id(&self, id: u32) -> Result<&Type> {
+        match &self.base {
+            Some(base) => base.type_by_id(id).or_else(|| self.obj.type_by_id(id)),
+            None => self.obj.type_by_id(id),
+        }
+        .ok_or_else(|| anyhow!("No type with id {}{}", id, self.in_source()))
+    }
+
+    // Get the bytes of the record of a type, given its id. See
+    // `BtfOptions::keep_raw_records()`.
+    pub(crate) fn raw_record(&self, id: u32) -> Result<Option<Cow<'_, [u8]>>> {
+        if let Some(base) = &self.base {
+            if let Some(raw) = base.raw_record(id)? {
+                return Ok(Some(raw));
+            }
+        }
+        self.obj.raw_record(id)
+    }
+
+    // Get a reference to a string given its offset.
+    pub(crate) fn str_by_offset(&self, offset: u32) -> Option<&str> {
         match &self.base {
             Some(base) => base
-                .resolve_name(r#type)
-                .or_else(|_| self.obj.resolve_name(r#type)),
-            None => self.obj.resolve_name(r#type),
+                .str_by_offset(offset)
+                .or_else(|| self.obj.str_by_offset(offset)),
+            None => self.obj.str_by_offset(offset),
         }
     }
 
     /// Types can have a reference to another one, e.g. `Ptr -> Int`. This
     /// helper resolve a Type referenced in an other one. It is the main helper
     /// to traverse the Type tree.
-    pub fn resolve_chained_type<T: BtfType + ?Sized>(&self, r#type: &T) -> Result<Type> {
-        self.resolve_type_by_id(r#type.get_type_id()?)
+    pub fn resolve_chained_type<T: AsBtfType + ?Sized>(&self, r#type: &T) -> Result<Type> {
+        self.resolve_type_by_id(r#type.as_btf_type().get_type_id()?)
     }
 
     /// This helper returns an iterator that allow to resolve a Type
     /// referenced in another one all the way down to the chain.
     /// The helper makes use of `Btf::resolve_chained_type()`.
-    pub fn type_iter<'a, T: BtfType + ?Sized>(&'a self, r#type: &'a T) -> TypeIter {
+    pub fn type_iter<'a, T: AsBtfType + ?Sized>(&'a self, r#type: &'a T) -> TypeIter<'a> {
         let ty = self.resolve_chained_type(r#type).ok();
         TypeIter {
             btf: self,
             r#type: ty,
         }
     }
+
+    /// Resolve the struct or union a member is part of.
+    pub fn resolve_struct_of_member(&self, member: &Member) -> Result<Struct> {
+        match self.resolve_type_by_id(member.parent_id())? {
+            Type::Struct(s) | Type::Union(s) => Ok(s),
+            x => bail!(
+                "Member parent is a {} instead of a struct or union",
+                x.name()
+            ),
+        }
+    }
+
+    /// Walk the chain of types referenced from `type`, checking each level is
+    /// of the expected kind, and return the last one. E.g. resolving the type
+    /// of a `struct sk_buff *` variable can be done using
+    /// `btf.expect_chain(&var, &[Kind::Ptr, Kind::Struct])`.
+    pub fn expect_chain<T: AsBtfType + ?Sized>(&self, r#type: &T, kinds: &[Kind]) -> Result<Type> {
+        let mut current: Option<Type> = None;
+
+        for (i, kind) in kinds.iter().enumerate() {
+            let next = match &current {
+                None => self.resolve_chained_type(r#type),
+                Some(current) => self.resolve_chained_type(current),
+            }
+            .with_context(|| format!("Could not resolve chained type at level {i}"))?;
+
+            if next.kind() != *kind {
+                bail!(
+                    "Expected {} at level {i} of the chain, found {}",
+                    kind.name(),
+                    next.name()
+                );
+            }
+            current = Some(next);
+        }
+
+        current.ok_or_else(|| anyhow!("Empty chain of kinds"))
+    }
+
+    /// Resolve a Type skipping all the typedef and qualifier (const, volatile,
+    /// restrict and type tag) levels, returning the first underlying Type.
+    pub(crate) fn resolve_underlying_type(&self, r#type: &Type) -> Result<Type> {
+        let mut r#type = r#type.clone();
+        loop {
+            r#type = match &r#type {
+                Type::Typedef(t) | Type::TypeTag(t) => self.resolve_chained_type(t)?,
+                Type::Volatile(v) | Type::Const(v) | Type::Restrict(v) => {
+                    self.resolve_chained_type(v)?
+                }
+                _ => return Ok(r#type),
+            };
+        }
+    }
+
+    /// Compute the size in bytes of a Type, following the chain of typedefs
+    /// and qualifiers if needed. Pointers are considered to be 8 bytes long as
+    /// BTF does not encode the target pointer size.
+    pub(crate) fn resolve_type_size(&self, r#type: &Type) -> Result<usize> {
+        Ok(match self.resolve_underlying_type(r#type)? {
+            Type::Void => 0,
+            Type::Ptr(_) => 8,
+            Type::Int(i) => i.size(),
+            Type::Struct(s) | Type::Union(s) => s.size(),
+            Type::Enum(e) => e.size(),
+            Type::Enum64(e) => e.size(),
+            Type::Float(f) => f.size(),
+            Type::Array(a) => a.len() * self.resolve_type_size(&self.resolve_chained_type(&a)?)?,
+            Type::Var(v) => self.resolve_type_size(&self.resolve_chained_type(&v)?)?,
+            x => bail!("Can't compute the size of a type of kind {}", x.name()),
+        })
+    }
+
+    /// Resolve a member given its fully qualified path, e.g. `sk_buff.dev.name`.
+    /// The first component is the name of a struct or union, the following
+    /// ones are member names, looked up in anonymous members too. Typedefs and
+    /// qualifiers are skipped and pointers to structs or unions are followed,
+    /// in which case offsets restart from the pointed object.
+    pub fn resolve_member_path(&self, path: &str) -> Result<MemberPath> {
+        let mut components = path.split('.');
+        let name = components.next().unwrap_or_default();
+
+        let mut current = None;
+        for r#type in self.resolve_types_by_name(name)? {
+            let r#type = self.resolve_underlying_type(&r#type)?;
+            if matches!(r#type, Type::Struct(_) | Type::Union(_)) {
+                current = Some(r#type);
+                break;
+            }
+        }
+        let mut current = current.ok_or_else(|| anyhow!("No struct or union named {name}"))?;
+
+        let mut walked = name.to_string();
+        let mut member = None;
+        let mut offset = 0;
+        let mut derefs = Vec::new();
+        for component in components {
+            let r#struct = match self.resolve_underlying_type(&current)? {
+                Type::Struct(s) | Type::Union(s) => s,
+                Type::Ptr(p) => {
+                    match self.resolve_underlying_type(&self.resolve_chained_type(&p)?)? {
+                        Type::Struct(s) | Type::Union(s) => {
+                            derefs.push(offset as usize / 8);
+                            offset = 0;
+                            s
+                        }
+                        x => bail!(
+                            "{walked} is a pointer to a {}, not to a struct or union",
+                            x.name()
+                        ),
+                    }
+                }
+                x => bail!("{walked} is a {}, not a struct or union", x.name()),
+            };
+
+            let (inner, inner_offset) = r#struct
+                .member_by_name(self, component)?
+                .ok_or_else(|| anyhow!("No member {component} in {walked}"))?;
+            offset += inner_offset;
+            current = self.resolve_chained_type(&inner)?;
+            member = Some(inner);
+
+            walked.push('.');
+            walked.push_str(component);
+        }
+
+        Ok(MemberPath {
+            member: member.ok_or_else(|| anyhow!("No member in path {path}"))?,
+            r#type: current,
+            offset: offset as usize / 8,
+            bit_offset: offset,
+            derefs,
+        })
+    }
+
+    /// Resolve the layout of a struct or union: offset, size and alignment of
+    /// its members, bitfields placement and padding holes. Nested structs and
+    /// unions, including array elements, are resolved recursively. As for
+    /// sizes, pointers are considered to be 8 bytes long (and aligned).
+    pub fn resolve_layout(&self, r#struct: &Struct) -> Result<StructLayout> {
+        let mut members = Vec::with_capacity(r#struct.members.len());
+        let mut alignment = 1;
+        let mut packed = false;
+
+        for member in r#struct.members.iter() {
+            let (size, member_alignment, layout) =
+                self.member_layout(&self.resolve_chained_type(member)?)?;
+            let bit_offset = member.bit_offset();
+            let bitfield_size = member.bitfield_size().filter(|size| *size != 0);
+
+            alignment = alignment.max(member_alignment);
+            if bitfield_size.is_none()
+                && !(bit_offset as usize).is_multiple_of(member_alignment * 8)
+            {
+                packed = true;
+            }
+
+            members.push(MemberLayout {
+                name: self.resolve_name(member)?,
+                offset: bit_offset as usize / 8,
+                bit_offset,
+                size,
+                alignment: member_alignment,
+                bitfield_size,
+                layout,
+            });
+        }
+
+        let size = r#struct.size();
+        if packed || !size.is_multiple_of(alignment) {
+            alignment = 1;
+        }
+
+        // Walk the members in offset order, looking for gaps between the end
+        // of the members seen so far and the start of the next one.
+        let mut ranges: Vec<(usize, usize)> = members
+            .iter()
+            .map(|m| {
+                let start = m.bit_offset as usize;
+                let bits = match m.bitfield_size {
+                    Some(bits) => bits as usize,
+                    None => m.size * 8,
+                };
+                (start, start + bits)
+            })
+            .collect();
+        ranges.sort_unstable();
+
+        let mut holes = Vec::new();
+        let mut end: usize = 0;
+        for (start, stop) in ranges
+            .into_iter()
+            .chain(std::iter::once((size * 8, size * 8)))
+        {
+            let (hole_start, hole_end) = (end.div_ceil(8), start / 8);
+            if hole_end > hole_start {
+                holes.push(LayoutHole {
+                    offset: hole_start,
+                    size: hole_end - hole_start,
+                });
+            }
+            end = end.max(stop);
+        }
+
+        Ok(StructLayout {
+            size,
+            alignment,
+            members,
+            holes,
+        })
+    }
+
+    // Compute the size, alignment and layout (for structs and unions) of the
+    // type of a member.
+    fn member_layout(&self, r#type: &Type) -> Result<(usize, usize, Option<Box<StructLayout>>)> {
+        Ok(match self.resolve_underlying_type(r#type)? {
+            Type::Struct(s) | Type::Union(s) => {
+                let layout = self.resolve_layout(&s)?;
+                (layout.size, layout.alignment, Some(Box::new(layout)))
+            }
+            Type::Array(a) => {
+                let (size, alignment, layout) =
+                    self.member_layout(&self.resolve_chained_type(&a)?)?;
+                (size * a.len(), alignment, layout)
+            }
+            r#type => {
+                let size = self.resolve_type_size(&r#type)?;
+                let alignment = match size.is_power_of_two() {
+                    true => size,
+                    false => 1,
+                };
+                (size, alignment, None)
+            }
+        })
+    }
+}
+
+/// Group identical types, given their own properties (`keys`) and the types
+/// they reference (`refs`), either as indexes in the same list or as ids of
+/// types outside of it which are only identical to themselves. Returns the
+/// group of each type.
+///
+/// This uses partition refinement: types are first grouped by their own
+/// properties, then groups are split according to the groups of the types
+/// they reference until no group can be split anymore. This handles reference
+/// cycles.
+pub(crate) fn group_types<K: Eq + Hash>(
+    keys: Vec<K>,
+    refs: Vec<Vec<std::result::Result<usize, u32>>>,
+) -> Vec<u64> {
+    let mut ids = HashMap::new();
+    let mut groups: Vec<u64> = keys
+        .into_iter()
+        .map(|key| {
+            let next = ids.len() as u64;
+            *ids.entry(key).or_insert(next)
+        })
+        .collect();
+    let mut count = ids.len();
+
+    loop {
+        let mut ids = HashMap::new();
+        groups = groups
+            .iter()
+            .zip(refs.iter())
+            .map(|(group, refs)| {
+                let refs = refs
+                    .iter()
+                    .map(|r| match r {
+                        Ok(i) => groups[*i],
+                        Err(id) => u64::from(*id) | 1 << 32,
+                    })
+                    .collect::<Vec<_>>();
+                let next = ids.len() as u64;
+                *ids.entry((*group, refs)).or_insert(next)
+            })
+            .collect();
+
+        // Groups are only ever split, a stable count means stable groups.
+        if ids.len() == count {
+            return groups;
+        }
+        count = ids.len();
+    }
+}
+
+/// Layout of a struct or union, see `Btf::resolve_layout()`. Offsets and sizes
+/// are in bytes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StructLayout {
+    pub size: usize,
+    /// Alignment, computed from the members ones. Packed structs have an
+    /// alignment of 1.
+    pub alignment: usize,
+    /// Members, in their definition order.
+    pub members: Vec<MemberLayout>,
+    /// Padding holes, including trailing padding, in increasing offset order.
+    pub holes: Vec<LayoutHole>,
+}
+
+/// Layout of a struct or union member, see [`StructLayout`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemberLayout {
+    /// Name of the member, empty for anonymous members.
+    pub name: String,
+    /// Offset of the byte the member starts in.
+    pub offset: usize,
+    /// Offset in bits.
+    pub bit_offset: u32,
+    /// Size of the type of the member.
+    pub size: usize,
+    pub alignment: usize,
+    /// Size in bits, for bitfields.
+    pub bitfield_size: Option<u32>,
+    /// Layout of the type of the member (or of its elements, for arrays) if
+    /// it is a struct or a union.
+    pub layout: Option<Box<StructLayout>>,
+}
+
+/// Range of unused bytes in a struct or union, see [`StructLayout`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LayoutHole {
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Member resolved from its path, see `Btf::resolve_member_path()`. Offsets
+/// are relative to the last object reached.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemberPath {
+    pub member: Member,
+    /// Type of the member, as returned by `Btf::resolve_chained_type()`.
+    pub r#type: Type,
+    /// Offset of the byte the member starts in.
+    pub offset: usize,
+    /// Offset in bits.
+    pub bit_offset: u32,
+    /// Offsets in bytes of the pointers followed along the path, each being
+    /// relative to the object the pointer is part of.
+    pub derefs: Vec<usize>,
+}
+
+/// Type returned along with its id and name, see e.g.
+/// `Btf::resolve_named_types_by_name()`. This avoids a second lookup for the
+/// common "get a type and its name" pattern.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NamedType {
+    pub id: TypeId,
+    /// Name of the type, `None` for anonymous types and types which can't be
+    /// named (e.g. pointers). The string is shared with the BTF object.
+    pub name: Option<Arc<str>>,
+    pub r#type: Type,
+}
+
+/// Type record skipped while parsing in tolerant mode, see
+/// `BtfOptions::tolerant()`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SkippedType {
+    /// Id the type would have had.
+    pub id: TypeId,
+    /// Raw kind of the record.
+    pub kind: u32,
+    /// Offset of the record in the type section.
+    pub offset: usize,
+    /// Why the record could not be parsed.
+    pub reason: String,
 }
 
 /// Iterator type returned by `Btf::type_iter()`.
@@ -173,18 +1574,10 @@ impl<'a> Iterator for TypeIter<'a> {
     type Item = Type;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.r#type.clone() {
-            None => None,
-            Some(ty) => {
-                self.r#type = match ty.as_btf_type() {
-                    Some(x) => self.btf.resolve_chained_type(x).ok(),
-                    // We might have encountered Void or other
-                    // non-BtfType types.
-                    None => None,
-                };
-                Some(ty)
-            }
-        }
+        let ty = self.r#type.take()?;
+        // The chain ends on types not referencing any other one (e.g. Void).
+        self.r#type = self.btf.resolve_chained_type(&ty).ok();
+        Some(ty)
     }
 }
 
@@ -216,65 +1609,444 @@ pub enum Type {
 
 impl Type {
     pub fn name(&self) -> &'static str {
+        self.kind().name()
+    }
+
+    /// Get the kind of the type.
+    pub fn kind(&self) -> Kind {
         match &self {
-            Type::Void => "void",
-            Type::Int(_) => "int",
-            Type::Ptr(_) => "ptr",
-            Type::Array(_) => "array",
-            Type::Struct(_) => "struct",
-            Type::Union(_) => "union",
-            Type::Enum(_) => "enum",
-            Type::Fwd(_) => "fwd",
-            Type::Typedef(_) => "typedef",
-            Type::Volatile(_) => "volatile",
-            Type::Const(_) => "const",
-            Type::Restrict(_) => "restrict",
-            Type::Func(_) => "func",
-            Type::FuncProto(_) => "func-proto",
-            Type::Var(_) => "var",
-            Type::Datasec(_) => "datasec",
-            Type::Float(_) => "float",
-            Type::DeclTag(_) => "decl-tag",
-            Type::TypeTag(_) => "type-tag",
-            Type::Enum64(_) => "enum64",
-        }
-    }
-
-    pub fn as_btf_type(&self) -> Option<&dyn BtfType> {
+            Type::Void => Kind::Void,
+            Type::Int(_) => Kind::Int,
+            Type::Ptr(_) => Kind::Ptr,
+            Type::Array(_) => Kind::Array,
+            Type::Struct(_) => Kind::Struct,
+            Type::Union(_) => Kind::Union,
+            Type::Enum(_) => Kind::Enum,
+            Type::Fwd(_) => Kind::Fwd,
+            Type::Typedef(_) => Kind::Typedef,
+            Type::Volatile(_) => Kind::Volatile,
+            Type::Const(_) => Kind::Const,
+            Type::Restrict(_) => Kind::Restrict,
+            Type::Func(_) => Kind::Func,
+            Type::FuncProto(_) => Kind::FuncProto,
+            Type::Var(_) => Kind::Var,
+            Type::Datasec(_) => Kind::Datasec,
+            Type::Float(_) => Kind::Float,
+            Type::DeclTag(_) => Kind::DeclTag,
+            Type::TypeTag(_) => Kind::TypeTag,
+            Type::Enum64(_) => Kind::Enum64,
+        }
+    }
+
+    // Get the underlying BTF type header, if any.
+    pub(crate) fn btf_type(&self) -> Option<&cbtf::btf_type> {
+        Some(match self {
+            Type::Void => return None,
+            Type::Int(i) => &i.btf_type,
+            Type::Ptr(p) => &p.btf_type,
+            Type::Array(a) => &a.btf_type,
+            Type::Struct(s) | Type::Union(s) => &s.btf_type,
+            Type::Enum(e) => &e.btf_type,
+            Type::Fwd(f) => &f.btf_type,
+            Type::Typedef(td) | Type::TypeTag(td) => &td.btf_type,
+            Type::Volatile(v) | Type::Const(v) | Type::Restrict(v) => &v.btf_type,
+            Type::Func(fu) => &fu.btf_type,
+            Type::FuncProto(fp) => &fp.btf_type,
+            Type::Var(v) => &v.btf_type,
+            Type::Datasec(ds) => &ds.btf_type,
+            Type::Float(f) => &f.btf_type,
+            Type::DeclTag(dt) => &dt.btf_type,
+            Type::Enum64(e64) => &e64.btf_type,
+        })
+    }
+
+    /// Get the ids of the types referenced by this one.
+    pub(crate) fn references(&self) -> Vec<u32> {
         match self {
-            Type::Int(i) => Some(i),
-            Type::Ptr(p) => Some(p),
-            Type::Array(a) => Some(a),
-            Type::Struct(s) => Some(s),
-            Type::Union(u) => Some(u),
-            Type::Enum(e) => Some(e),
-            Type::Fwd(f) => Some(f),
-            Type::Typedef(td) => Some(td),
-            Type::Volatile(v) => Some(v),
-            Type::Const(c) => Some(c),
-            Type::Restrict(r) => Some(r),
-            Type::Func(fu) => Some(fu),
-            Type::Var(v) => Some(v),
-            Type::Datasec(ds) => Some(ds),
-            Type::Float(f) => Some(f),
-            Type::DeclTag(dt) => Some(dt),
-            Type::TypeTag(tt) => Some(tt),
-            Type::Enum64(e64) => Some(e64),
-            _ => None,
+            Type::Ptr(Ptr { btf_type })
+            | Type::Typedef(Typedef { btf_type })
+            | Type::TypeTag(Typedef { btf_type })
+            | Type::Volatile(Volatile { btf_type })
+            | Type::Const(Volatile { btf_type })
+            | Type::Restrict(Volatile { btf_type })
+            | Type::Func(Func { btf_type })
+            | Type::Var(Var { btf_type, .. })
+            | Type::DeclTag(DeclTag { btf_type, .. }) => vec![btf_type.r#type()],
+            Type::Array(a) => vec![a.btf_array.r#type, a.btf_array.index_type],
+            Type::Struct(s) | Type::Union(s) => {
+                s.members.iter().map(|m| m.btf_member.r#type).collect()
+            }
+            Type::FuncProto(fp) => std::iter::once(fp.btf_type.r#type())
+                .chain(fp.parameters.iter().map(|p| p.btf_param.r#type))
+                .collect(),
+            Type::Datasec(ds) => ds
+                .variables
+                .iter()
+                .map(|v| v.btf_var_secinfo.r#type)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Rewrite the type ids and string offsets referenced by the type, using
+    // the given mappings.
+    pub(crate) fn remap(&mut self, ids: impl Fn(u32) -> u32, strs: impl Fn(u32) -> u32) {
+        let header = |bt: &mut cbtf::btf_type| bt.name_off = strs(bt.name_off);
+        let chained = |bt: &mut cbtf::btf_type| {
+            *bt = bt.with_type(ids(bt.r#type()));
+            bt.name_off = strs(bt.name_off);
+        };
+
+        match self {
+            Type::Void => (),
+            Type::Int(Int { btf_type, .. })
+            | Type::Fwd(Fwd { btf_type })
+            | Type::Float(Float { btf_type }) => header(btf_type),
+            Type::Ptr(Ptr { btf_type })
+            | Type::Typedef(Typedef { btf_type })
+            | Type::TypeTag(Typedef { btf_type })
+            | Type::Volatile(Volatile { btf_type })
+            | Type::Const(Volatile { btf_type })
+            | Type::Restrict(Volatile { btf_type })
+            | Type::Func(Func { btf_type })
+            | Type::Var(Var { btf_type, .. })
+            | Type::DeclTag(DeclTag { btf_type, .. }) => chained(btf_type),
+            Type::Array(a) => {
+                header(&mut a.btf_type);
+                a.btf_array.r#type = ids(a.btf_array.r#type);
+                a.btf_array.index_type = ids(a.btf_array.index_type);
+            }
+            Type::Struct(s) | Type::Union(s) => {
+                header(&mut s.btf_type);
+                for m in s.members.iter_mut() {
+                    m.btf_member.name_off = strs(m.btf_member.name_off);
+                    m.btf_member.r#type = ids(m.btf_member.r#type);
+                }
+            }
+            Type::Enum(e) => {
+                header(&mut e.btf_type);
+                for m in e.members.iter_mut() {
+                    m.btf_enum.name_off = strs(m.btf_enum.name_off);
+                }
+            }
+            Type::Enum64(e64) => {
+                header(&mut e64.btf_type);
+                for m in e64.members.iter_mut() {
+                    m.btf_enum64.name_off = strs(m.btf_enum64.name_off);
+                }
+            }
+            Type::FuncProto(fp) => {
+                chained(&mut fp.btf_type);
+                for p in fp.parameters.iter_mut() {
+                    p.btf_param.name_off = strs(p.btf_param.name_off);
+                    p.btf_param.r#type = ids(p.btf_param.r#type);
+                }
+            }
+            Type::Datasec(ds) => {
+                header(&mut ds.btf_type);
+                for v in ds.variables.iter_mut() {
+                    v.btf_var_secinfo.r#type = ids(v.btf_var_secinfo.r#type);
+                }
+            }
+        }
+    }
+
+    // Write the raw BTF representation of the type, as found in the type
+    // section. The vlen is taken from the type-specific data as it can be
+    // modified. Nothing is written for `Type::Void`, which is implicit.
+    pub(crate) fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        endianness: &cbtf::Endianness,
+    ) -> Result<()> {
+        match self {
+            Type::Void => (),
+            Type::Int(i) => {
+                i.btf_type.write(writer, endianness)?;
+                i.btf_int.write(writer, endianness)?;
+            }
+            Type::Array(a) => {
+                a.btf_type.write(writer, endianness)?;
+                a.btf_array.write(writer, endianness)?;
+            }
+            Type::Struct(s) | Type::Union(s) => {
+                s.btf_type
+                    .with_vlen(cbtf::vlen(s.members.len())?)
+                    .write(writer, endianness)?;
+                for m in s.members.iter() {
+                    m.btf_member.write(writer, endianness)?;
+                }
+            }
+            Type::Enum(e) => {
+                e.btf_type
+                    .with_vlen(cbtf::vlen(e.members.len())?)
+                    .write(writer, endianness)?;
+                for m in e.members.iter() {
+                    m.btf_enum.write(writer, endianness)?;
+                }
+            }
+            Type::FuncProto(fp) => {
+                fp.btf_type
+                    .with_vlen(cbtf::vlen(fp.parameters.len())?)
+                    .write(writer, endianness)?;
+                for p in fp.parameters.iter() {
+                    p.btf_param.write(writer, endianness)?;
+                }
+            }
+            Type::Var(v) => {
+                v.btf_type.write(writer, endianness)?;
+                v.btf_var.write(writer, endianness)?;
+            }
+            Type::Datasec(ds) => {
+                ds.btf_type
+                    .with_vlen(cbtf::vlen(ds.variables.len())?)
+                    .write(writer, endianness)?;
+                for v in ds.variables.iter() {
+                    v.btf_var_secinfo.write(writer, endianness)?;
+                }
+            }
+            Type::DeclTag(dt) => {
+                dt.btf_type.write(writer, endianness)?;
+                dt.btf_decl_tag.write(writer, endianness)?;
+            }
+            Type::Enum64(e64) => {
+                e64.btf_type
+                    .with_vlen(cbtf::vlen(e64.members.len())?)
+                    .write(writer, endianness)?;
+                for m in e64.members.iter() {
+                    m.btf_enum64.write(writer, endianness)?;
+                }
+            }
+            Type::Ptr(_)
+            | Type::Fwd(_)
+            | Type::Typedef(_)
+            | Type::Volatile(_)
+            | Type::Const(_)
+            | Type::Restrict(_)
+            | Type::Func(_)
+            | Type::Float(_)
+            | Type::TypeTag(_) => {
+                // Those types are only made of a header.
+                if let Some(bt) = self.btf_type() {
+                    bt.write(writer, endianness)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the type as a `BtfType` trait object. `Type::Void` is represented
+    /// by [`VoidType`].
+    pub fn as_btf_type(&self) -> &dyn BtfType {
+        match self {
+            Type::Void => &VoidType,
+            Type::Int(i) => i,
+            Type::Ptr(p) => p,
+            Type::Array(a) => a,
+            Type::Struct(s) => s,
+            Type::Union(u) => u,
+            Type::Enum(e) => e,
+            Type::Fwd(f) => f,
+            Type::Typedef(td) => td,
+            Type::Volatile(v) => v,
+            Type::Const(c) => c,
+            Type::Restrict(r) => r,
+            Type::Func(fu) => fu,
+            Type::FuncProto(fp) => fp,
+            Type::Var(v) => v,
+            Type::Datasec(ds) => ds,
+            Type::Float(f) => f,
+            Type::DeclTag(dt) => dt,
+            Type::TypeTag(tt) => tt,
+            Type::Enum64(e64) => e64,
+        }
+    }
+}
+
+/// Kind of a BTF type, without its associated data.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Kind {
+    Void,
+    Int,
+    Ptr,
+    Array,
+    Struct,
+    Union,
+    Enum,
+    Fwd,
+    Typedef,
+    Volatile,
+    Const,
+    Restrict,
+    Func,
+    FuncProto,
+    Var,
+    Datasec,
+    Float,
+    DeclTag,
+    TypeTag,
+    Enum64,
+}
+
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        match &self {
+            Kind::Void => "void",
+            Kind::Int => "int",
+            Kind::Ptr => "ptr",
+            Kind::Array => "array",
+            Kind::Struct => "struct",
+            Kind::Union => "union",
+            Kind::Enum => "enum",
+            Kind::Fwd => "fwd",
+            Kind::Typedef => "typedef",
+            Kind::Volatile => "volatile",
+            Kind::Const => "const",
+            Kind::Restrict => "restrict",
+            Kind::Func => "func",
+            Kind::FuncProto => "func-proto",
+            Kind::Var => "var",
+            Kind::Datasec => "datasec",
+            Kind::Float => "float",
+            Kind::DeclTag => "decl-tag",
+            Kind::TypeTag => "type-tag",
+            Kind::Enum64 => "enum64",
         }
     }
 }
 
 pub trait BtfType {
-    fn get_name_offset(&self) -> Result<u32> {
+    /// Offset of the name of the type in the string section, if any.
+    fn get_name_offset(&self) -> Result<StrOffset> {
         bail!("No name offset in type");
     }
 
-    fn get_type_id(&self) -> Result<u32> {
+    /// Id of the type referenced by this one, if any.
+    fn get_type_id(&self) -> Result<TypeId> {
         bail!("No type offset in type");
     }
 }
 
+/// Input accepted by the `Btf` resolvers (e.g. `Btf::resolve_name()`), allowing
+/// to use both concrete types (`Struct`, `Member`, etc) and `Type` directly:
+///
+/// ```no_run
+/// use btf_rs::Btf;
+///
+/// let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+/// let r#type = btf.resolve_types_by_name("sk_buff").unwrap().pop().unwrap();
+/// assert_eq!(btf.resolve_name(&r#type).unwrap(), "sk_buff");
+/// ```
+pub trait AsBtfType {
+    /// Get the underlying BTF type.
+    fn as_btf_type(&self) -> &dyn BtfType;
+}
+
+impl<T: BtfType> AsBtfType for T {
+    fn as_btf_type(&self) -> &dyn BtfType {
+        self
+    }
+}
+
+impl AsBtfType for dyn BtfType + '_ {
+    fn as_btf_type(&self) -> &dyn BtfType {
+        self
+    }
+}
+
+impl AsBtfType for Type {
+    fn as_btf_type(&self) -> &dyn BtfType {
+        Type::as_btf_type(self)
+    }
+}
+
+/// Lightweight representation of a BTF type, only holding its kind and common
+/// information. See `Btf::resolve_type_shallow()`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ShallowType {
+    kind: Kind,
+    btf_type: Option<cbtf::btf_type>,
+}
+
+impl ShallowType {
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Size of the type in bytes, for kinds having one (int, struct, union,
+    /// enum, enum64, float and datasec).
+    pub fn size(&self) -> Option<usize> {
+        match self.kind {
+            Kind::Int
+            | Kind::Struct
+            | Kind::Union
+            | Kind::Enum
+            | Kind::Enum64
+            | Kind::Float
+            | Kind::Datasec => self.btf_type.map(|bt| bt.size()),
+            _ => None,
+        }
+    }
+
+    /// Id of the type referenced by this one, for kinds having one (ptr,
+    /// typedef, qualifiers, func, var, decl and type tags). For func-proto the
+    /// id of the return type is returned. The element type of arrays is not
+    /// available in shallow types.
+    pub fn type_id(&self) -> Option<TypeId> {
+        match self.kind {
+            Kind::Ptr
+            | Kind::Typedef
+            | Kind::Volatile
+            | Kind::Const
+            | Kind::Restrict
+            | Kind::Func
+            | Kind::FuncProto
+            | Kind::Var
+            | Kind::DeclTag
+            | Kind::TypeTag => self.btf_type.map(|bt| TypeId(bt.r#type())),
+            _ => None,
+        }
+    }
+
+    /// Number of type specific entries (e.g. struct members or function
+    /// parameters).
+    pub fn vlen(&self) -> usize {
+        self.btf_type
+            .map(|bt| bt.vlen() as usize)
+            .unwrap_or_default()
+    }
+}
+
+impl BtfType for ShallowType {
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        match (self.kind, self.btf_type) {
+            (
+                Kind::Ptr
+                | Kind::Array
+                | Kind::Volatile
+                | Kind::Const
+                | Kind::Restrict
+                | Kind::FuncProto,
+                _,
+            )
+            | (_, None) => bail!("No name offset in type"),
+            (_, Some(bt)) => Ok(StrOffset(bt.name_off)),
+        }
+    }
+
+    fn get_type_id(&self) -> Result<TypeId> {
+        self.type_id()
+            .ok_or_else(|| anyhow!("No type offset in type"))
+    }
+}
+
+/// Marker for the special `void` type (id 0), which is not described in the
+/// BTF type section. It has neither a name nor a referenced type.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct VoidType;
+
+impl BtfType for VoidType {}
+
 /// Rust representation for BTF type `BTF_KIND_INT`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Int {
@@ -306,14 +2078,49 @@ impl Int {
         self.btf_int.encoding() & cbtf::BTF_INT_BOOL == cbtf::BTF_INT_BOOL
     }
 
+    /// Get the encoding of the integer. Encodings are exclusive; if more than
+    /// one is set (which the kernel rejects), signedness takes precedence,
+    /// then char.
+    pub fn encoding(&self) -> IntEncoding {
+        match (self.is_signed(), self.is_char(), self.is_bool()) {
+            (true, _, _) => IntEncoding::Signed,
+            (_, true, _) => IntEncoding::Char,
+            (_, _, true) => IntEncoding::Bool,
+            _ => IntEncoding::None,
+        }
+    }
+
     pub fn size(&self) -> usize {
         self.btf_type.size()
     }
+
+    /// Offset in bits, within the storage described by `size()`, at which the
+    /// value starts. Always 0 for integers emitted by compilers.
+    pub fn offset(&self) -> u32 {
+        self.btf_int.offset()
+    }
+
+    /// Number of bits actually holding the value, which can be lower than
+    /// `size() * 8`.
+    pub fn bits(&self) -> u32 {
+        self.btf_int.bits()
+    }
+}
+
+/// Encoding of an [`Int`], see `Int::encoding()`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IntEncoding {
+    /// Unsigned integer, with no specific encoding.
+    None,
+    Signed,
+    Char,
+    Bool,
 }
 
 impl BtfType for Int {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 }
 
@@ -330,8 +2137,8 @@ impl Ptr {
 }
 
 impl BtfType for Ptr {
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_type.r#type())
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_type.r#type()))
     }
 }
 
@@ -358,11 +2165,15 @@ impl Array {
     pub fn len(&self) -> usize {
         self.btf_array.nelems as usize
     }
+
+    pub(crate) fn index_type_id(&self) -> u32 {
+        self.btf_array.index_type
+    }
 }
 
 impl BtfType for Array {
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_array.r#type)
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_array.r#type))
     }
 }
 
@@ -378,6 +2189,7 @@ impl Struct {
         reader: &mut R,
         endianness: &cbtf::Endianness,
         btf_type: cbtf::btf_type,
+        id: u32,
     ) -> Result<Struct> {
         let mut members = Vec::new();
 
@@ -386,6 +2198,7 @@ impl Struct {
                 reader,
                 endianness,
                 btf_type.kind_flag(),
+                id,
             )?);
         }
 
@@ -395,11 +2208,76 @@ impl Struct {
     pub fn size(&self) -> usize {
         self.btf_type.size()
     }
+
+    /// Whether the struct or union may contain bitfield members, which is
+    /// reflected by the BTF `kind_flag` being set. In such case the bitfield
+    /// size of each member is encoded along its bit offset, and members not
+    /// being bitfields report a bitfield size of 0. See `Member::is_bitfield()`.
+    pub fn has_bitfield_members(&self) -> bool {
+        self.btf_type.kind_flag() == 1
+    }
+
+    /// Find a member given its name, looking into anonymous struct and union
+    /// members. Returns the member alongside its offset in bits from the start
+    /// of the struct, which for members of anonymous members differs from
+    /// `Member::bit_offset()`.
+    pub fn member_by_name(&self, btf: &Btf, name: &str) -> Result<Option<(Member, u32)>> {
+        for member in self.members.iter() {
+            let member_name = btf.resolve_name_ref(member)?;
+            if member_name == name {
+                return Ok(Some((member.clone(), member.bit_offset())));
+            }
+
+            if member_name.is_empty() {
+                if let Type::Struct(inner) | Type::Union(inner) =
+                    btf.resolve_underlying_type(&btf.resolve_chained_type(member)?)?
+                {
+                    if let Some((inner, offset)) = inner.member_by_name(btf, name)? {
+                        return Ok(Some((inner, member.bit_offset() + offset)));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find the member covering a given byte offset in the struct or union.
+    /// Anonymous struct and union members are inlined, in which case the
+    /// returned path starts with the anonymous member and ends with the inner
+    /// one covering the offset. For unions the first matching member is
+    /// used. An empty path is returned if no member covers the offset (e.g.
+    /// padding or out of bounds).
+    pub fn member_at_offset(&self, btf: &Btf, offset: usize) -> Result<Vec<Member>> {
+        let offset = offset as u64 * 8;
+
+        for member in self.members.iter() {
+            let start = member.bit_offset() as u64;
+            let end = start + member.effective_bit_size(btf)? as u64;
+            // Byte granularity: a bitfield covers all the bytes it touches.
+            if offset + 8 <= start - start % 8 || offset >= end {
+                continue;
+            }
+
+            let mut path = vec![member.clone()];
+            if member.btf_member.name_off == 0 {
+                if let Type::Struct(inner) | Type::Union(inner) =
+                    btf.resolve_underlying_type(&btf.resolve_chained_type(member)?)?
+                {
+                    let inner_offset = (offset - start) / 8;
+                    path.extend(inner.member_at_offset(btf, inner_offset as usize)?);
+                }
+            }
+            return Ok(path);
+        }
+
+        Ok(Vec::new())
+    }
 }
 
 impl BtfType for Struct {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 }
 
@@ -410,6 +2288,8 @@ pub type Union = Struct;
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Member {
     kind_flag: u32,
+    // Id of the struct or union this member is part of.
+    parent: u32,
     btf_member: cbtf::btf_member,
 }
 
@@ -418,13 +2298,21 @@ impl Member {
         reader: &mut R,
         endianness: &cbtf::Endianness,
         kind_flag: u32,
+        parent: u32,
     ) -> Result<Member> {
         Ok(Member {
             kind_flag,
+            parent,
             btf_member: cbtf::btf_member::from_reader(reader, endianness)?,
         })
     }
 
+    /// Id of the struct or union this member is part of. See
+    /// `Btf::resolve_struct_of_member()`.
+    pub fn parent_id(&self) -> TypeId {
+        TypeId(self.parent)
+    }
+
     pub fn bit_offset(&self) -> u32 {
         match self.kind_flag {
             1 => self.btf_member.offset & 0xffffff,
@@ -432,21 +2320,46 @@ impl Member {
         }
     }
 
+    /// Size in bits of the member if its parent has bitfield members (see
+    /// `Struct::has_bitfield_members()`), `None` otherwise. Members not being
+    /// bitfields report a size of 0.
     pub fn bitfield_size(&self) -> Option<u32> {
         match self.kind_flag {
             1 => Some(self.btf_member.offset >> 24),
             _ => None,
         }
     }
+
+    /// Whether the member is a bitfield.
+    pub fn is_bitfield(&self) -> bool {
+        matches!(self.bitfield_size(), Some(size) if size > 0)
+    }
+
+    /// Size in bits of the member, being it a bitfield or not. For members not
+    /// being bitfields, the size is the one of the underlying type (following
+    /// typedefs and qualifiers). For integers this is their number of bits,
+    /// which also describes bitfields in structs and unions without
+    /// `kind_flag` set.
+    pub fn effective_bit_size(&self, btf: &Btf) -> Result<u32> {
+        if let Some(size) = self.bitfield_size().filter(|size| *size > 0) {
+            return Ok(size);
+        }
+        Ok(
+            match btf.resolve_underlying_type(&btf.resolve_chained_type(self)?)? {
+                Type::Int(int) => int.bits(),
+                r#type => btf.resolve_type_size(&r#type)? as u32 * 8,
+            },
+        )
+    }
 }
 
 impl BtfType for Member {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_member.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_member.name_off))
     }
 
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_member.r#type)
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_member.r#type))
     }
 }
 
@@ -457,7 +2370,6 @@ pub struct Enum {
     pub members: Vec<EnumMember>,
 }
 
-#[allow(clippy::len_without_is_empty)]
 impl Enum {
     pub(super) fn from_reader<R: Read>(
         reader: &mut R,
@@ -477,8 +2389,38 @@ impl Enum {
         self.btf_type.kind_flag() == 1
     }
 
+    /// Number of members of the enum.
     pub fn len(&self) -> usize {
-        self.btf_type.vlen() as usize
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Iterate over the members of the enum, yielding their resolved name and
+    /// value.
+    ///
+    /// ```no_run
+    /// # use std::collections::HashMap;
+    /// # use btf_rs::*;
+    /// # let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+    /// # let e = match btf.resolve_types_by_name("skb_drop_reason").unwrap().pop().unwrap() {
+    /// #     Type::Enum(e) => e,
+    /// #     _ => panic!(),
+    /// # };
+    /// let values = e.values(&btf).collect::<anyhow::Result<HashMap<String, i64>>>().unwrap();
+    /// ```
+    pub fn values<'a>(&'a self, btf: &'a Btf) -> impl Iterator<Item = Result<(String, i64)>> + 'a {
+        self.members.iter().map(move |m| {
+            Ok((
+                btf.resolve_name(m)?,
+                match m.value(self) {
+                    EnumValue::Signed(v) => v,
+                    EnumValue::Unsigned(v) => v as i64,
+                },
+            ))
+        })
     }
 
     pub fn size(&self) -> usize {
@@ -487,8 +2429,8 @@ impl Enum {
 }
 
 impl BtfType for Enum {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 }
 
@@ -511,11 +2453,39 @@ impl EnumMember {
     pub fn val(&self) -> u32 {
         self.btf_enum.val
     }
+
+    /// Get the value interpreted as a signed integer. Only meaningful for
+    /// members of signed enums, see `Enum::is_signed()`.
+    pub fn val_signed(&self) -> i32 {
+        self.btf_enum.val as i32
+    }
+
+    /// Get the value taking into account the signedness of the enum the member
+    /// belongs to.
+    pub fn value(&self, parent: &Enum) -> EnumValue {
+        match parent.is_signed() {
+            true => EnumValue::Signed(self.val_signed() as i64),
+            false => EnumValue::Unsigned(self.val() as u64),
+        }
+    }
+}
+
+/// Value of an [`Enum`] or [`Enum64`] member, decoded according to the
+/// signedness of the enum.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
+pub enum EnumValue {
+    Signed(i64),
+    Unsigned(u64),
 }
 
 impl BtfType for EnumMember {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_enum.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_enum.name_off))
     }
 }
 
@@ -542,8 +2512,8 @@ impl Fwd {
 }
 
 impl BtfType for Fwd {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 }
 
@@ -560,12 +2530,12 @@ impl Typedef {
 }
 
 impl BtfType for Typedef {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_type.r#type())
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_type.r#type()))
     }
 }
 
@@ -585,8 +2555,8 @@ impl Volatile {
 }
 
 impl BtfType for Volatile {
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_type.r#type())
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_type.r#type()))
     }
 }
 
@@ -621,12 +2591,12 @@ impl Func {
 }
 
 impl BtfType for Func {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_type.r#type())
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_type.r#type()))
     }
 }
 
@@ -655,11 +2625,29 @@ impl FuncProto {
         })
     }
 
-    pub fn return_type_id(&self) -> u32 {
-        self.btf_type.r#type()
+    pub fn return_type_id(&self) -> TypeId {
+        TypeId(self.btf_type.r#type())
+    }
+
+    /// Resolve the return type of the function.
+    pub fn return_type(&self, btf: &Btf) -> Result<Type> {
+        btf.resolve_type_by_id(self.return_type_id())
+    }
+
+    /// Find a parameter given its name, returning its index in the prototype
+    /// along with the parameter itself.
+    pub fn param_by_name(&self, btf: &Btf, name: &str) -> Option<(usize, &Parameter)> {
+        self.parameters
+            .iter()
+            .enumerate()
+            .find(|(_, p)| btf.str_by_offset(p.btf_param.name_off) == Some(name))
     }
 }
 
+// The return type is not considered as a chained type, use
+// `FuncProto::return_type_id()` to retrieve it.
+impl BtfType for FuncProto {}
+
 /// Represents a [`FuncProto`] parameter.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Parameter {
@@ -679,15 +2667,20 @@ impl Parameter {
     pub fn is_variadic(&self) -> bool {
         self.btf_param.name_off == 0 && self.btf_param.r#type == 0
     }
+
+    /// Resolve the type of the parameter.
+    pub fn r#type(&self, btf: &Btf) -> Result<Type> {
+        btf.resolve_chained_type(self)
+    }
 }
 
 impl BtfType for Parameter {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_param.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_param.name_off))
     }
 
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_param.r#type)
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_param.r#type))
     }
 }
 
@@ -717,15 +2710,19 @@ impl Var {
     pub fn is_global(&self) -> bool {
         self.btf_var.linkage == 1
     }
+
+    pub(crate) fn linkage(&self) -> u32 {
+        self.btf_var.linkage
+    }
 }
 
 impl BtfType for Var {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_type.r#type())
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_type.r#type()))
     }
 }
 
@@ -753,11 +2750,15 @@ impl Datasec {
             variables,
         })
     }
+
+    pub fn size(&self) -> usize {
+        self.btf_type.size()
+    }
 }
 
 impl BtfType for Datasec {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 }
 
@@ -787,8 +2788,8 @@ impl VarSecinfo {
 }
 
 impl BtfType for VarSecinfo {
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_var_secinfo.r#type)
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_var_secinfo.r#type))
     }
 }
 
@@ -809,8 +2810,8 @@ impl Float {
 }
 
 impl BtfType for Float {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 }
 
@@ -843,12 +2844,12 @@ impl DeclTag {
 }
 
 impl BtfType for DeclTag {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 
-    fn get_type_id(&self) -> Result<u32> {
-        Ok(self.btf_type.r#type())
+    fn get_type_id(&self) -> Result<TypeId> {
+        Ok(TypeId(self.btf_type.r#type()))
     }
 }
 
@@ -859,7 +2860,6 @@ pub struct Enum64 {
     pub members: Vec<Enum64Member>,
 }
 
-#[allow(clippy::len_without_is_empty)]
 impl Enum64 {
     pub(super) fn from_reader<R: Read>(
         reader: &mut R,
@@ -879,8 +2879,39 @@ impl Enum64 {
         self.btf_type.kind_flag() == 1
     }
 
+    /// Number of members of the enum.
     pub fn len(&self) -> usize {
-        self.btf_type.vlen() as usize
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Iterate over the members of the enum, yielding their resolved name and
+    /// value. Values of unsigned 64-bit enums above `i64::MAX` are
+    /// reinterpreted as negative values (two's complement).
+    ///
+    /// ```no_run
+    /// # use std::collections::HashMap;
+    /// # use btf_rs::*;
+    /// # let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+    /// # let e = match btf.resolve_types_by_name("skb_drop_reason").unwrap().pop().unwrap() {
+    /// #     Type::Enum64(e) => e,
+    /// #     _ => panic!(),
+    /// # };
+    /// let values = e.values(&btf).collect::<anyhow::Result<HashMap<String, i64>>>().unwrap();
+    /// ```
+    pub fn values<'a>(&'a self, btf: &'a Btf) -> impl Iterator<Item = Result<(String, i64)>> + 'a {
+        self.members.iter().map(move |m| {
+            Ok((
+                btf.resolve_name(m)?,
+                match m.value(self) {
+                    EnumValue::Signed(v) => v,
+                    EnumValue::Unsigned(v) => v as i64,
+                },
+            ))
+        })
     }
 
     pub fn size(&self) -> usize {
@@ -889,8 +2920,8 @@ impl Enum64 {
 }
 
 impl BtfType for Enum64 {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_type.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_type.name_off))
     }
 }
 
@@ -913,10 +2944,25 @@ impl Enum64Member {
     pub fn val(&self) -> u64 {
         (self.btf_enum64.val_hi32 as u64) << 32 | self.btf_enum64.val_lo32 as u64
     }
+
+    /// Get the value interpreted as a signed integer. Only meaningful for
+    /// members of signed enums, see `Enum64::is_signed()`.
+    pub fn val_signed(&self) -> i64 {
+        self.val() as i64
+    }
+
+    /// Get the value taking into account the signedness of the enum the member
+    /// belongs to.
+    pub fn value(&self, parent: &Enum64) -> EnumValue {
+        match parent.is_signed() {
+            true => EnumValue::Signed(self.val_signed()),
+            false => EnumValue::Unsigned(self.val()),
+        }
+    }
 }
 
 impl BtfType for Enum64Member {
-    fn get_name_offset(&self) -> Result<u32> {
-        Ok(self.btf_enum64.name_off)
+    fn get_name_offset(&self) -> Result<StrOffset> {
+        Ok(StrOffset(self.btf_enum64.name_off))
     }
 }
@@ -5,17 +5,56 @@
 
 #![allow(non_camel_case_types, dead_code)]
 
-use std::io::Read;
+use std::{
+    io::{Read, Write},
+    mem,
+};
 
 use anyhow::{bail, Result};
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
 
-pub(super) enum Endianness {
+/// Byte order of raw BTF data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Endianness {
     Little,
     Big,
 }
 
 impl Endianness {
+    /// Byte order of the host.
+    pub fn native() -> Endianness {
+        match cfg!(target_endian = "big") {
+            true => Endianness::Big,
+            false => Endianness::Little,
+        }
+    }
+
+    /// Detect the byte order of raw BTF data from its magic, found in the
+    /// first two bytes. This also works for `.BTF.ext` data, which uses the
+    /// same magic.
+    pub fn detect(bytes: &[u8]) -> Result<Endianness> {
+        match bytes {
+            [0x9f, 0xeb, ..] => Ok(Endianness::Little),
+            [0xeb, 0x9f, ..] => Ok(Endianness::Big),
+            [a, b, ..] => bail!("Invalid BTF magic: {:#x}", u16::from_le_bytes([*a, *b])),
+            _ => bail!("Not enough data to detect the byte order"),
+        }
+    }
+
+    fn u32_to_writer<W: Write>(&self, writer: &mut W, val: u32) -> Result<(), std::io::Error> {
+        match &self {
+            Endianness::Little => writer.write_u32::<LittleEndian>(val),
+            Endianness::Big => writer.write_u32::<BigEndian>(val),
+        }
+    }
+
+    fn i32_to_writer<W: Write>(&self, writer: &mut W, val: i32) -> Result<(), std::io::Error> {
+        match &self {
+            Endianness::Little => writer.write_i32::<LittleEndian>(val),
+            Endianness::Big => writer.write_i32::<BigEndian>(val),
+        }
+    }
+
     fn u16_from_reader<R: Read>(&self, reader: &mut R) -> Result<u16, std::io::Error> {
         match &self {
             Endianness::Little => reader.read_u16::<LittleEndian>(),
@@ -38,6 +77,14 @@ impl Endianness {
     }
 }
 
+// Convert a number of type-specific entries to a vlen, checking it fits.
+pub(super) fn vlen(len: usize) -> Result<u32> {
+    if len > 0xffff {
+        bail!("Too many entries ({len}) for a single type");
+    }
+    Ok(len as u32)
+}
+
 #[derive(Clone, Copy, Debug)]
 #[repr(C, packed)]
 pub(super) struct btf_header {
@@ -52,6 +99,10 @@ pub(super) struct btf_header {
 }
 
 impl btf_header {
+    // Size of the known part of the header. Newer versions of the format
+    // might extend it, in which case `hdr_len` is bigger.
+    pub(super) const KNOWN_LEN: u32 = mem::size_of::<btf_header>() as u32;
+
     pub(super) fn from_reader<R: Read>(reader: &mut R) -> Result<(btf_header, Endianness)> {
         let magic = reader.read_u16::<LittleEndian>()?;
         #[allow(clippy::mixed_case_hex_literals)]
@@ -75,8 +126,71 @@ impl btf_header {
             endianness,
         ))
     }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        match endianness {
+            Endianness::Little => writer.write_u16::<LittleEndian>(0xeb9f)?,
+            Endianness::Big => writer.write_u16::<BigEndian>(0xeb9f)?,
+        }
+        writer.write_u8(self.version)?;
+        writer.write_u8(self.flags)?;
+        for val in [
+            self.hdr_len,
+            self.type_off,
+            self.type_len,
+            self.str_off,
+            self.str_len,
+        ] {
+            endianness.u32_to_writer(writer, val)?;
+        }
+        Ok(())
+    }
+
+    // Estimate the number of types and strings of the object, given the
+    // average size of a type (~25.5 bytes) and of a string (~18.8 bytes)
+    // measured on kernel BTF. Divisors are rounded down, slightly
+    // over-estimating the counts, to avoid resizing tables when the
+    // estimates are used as their capacity.
+    //
+    // Section lengths come from the object being parsed and can't be trusted:
+    // they are clamped to the `available` bytes of the object.
+    pub(super) fn estimates(&self, available: u64) -> (usize, usize) {
+        let len = |len: u32| (len as u64).min(available) as usize;
+        (len(self.type_len) / 24, len(self.str_len) / 16)
+    }
+
+    // Total length of the BTF object described by this header, header
+    // included.
+    pub(super) fn total_len(&self) -> u64 {
+        let (type_end, str_end) = (
+            self.type_off as u64 + self.type_len as u64,
+            self.str_off as u64 + self.str_len as u64,
+        );
+        self.hdr_len as u64 + type_end.max(str_end)
+    }
 }
 
+// Kinds of BTF types, as found in `btf_type::info`.
+pub(super) const BTF_KIND_INT: u32 = 1;
+pub(super) const BTF_KIND_PTR: u32 = 2;
+pub(super) const BTF_KIND_ARRAY: u32 = 3;
+pub(super) const BTF_KIND_STRUCT: u32 = 4;
+pub(super) const BTF_KIND_UNION: u32 = 5;
+pub(super) const BTF_KIND_ENUM: u32 = 6;
+pub(super) const BTF_KIND_FWD: u32 = 7;
+pub(super) const BTF_KIND_TYPEDEF: u32 = 8;
+pub(super) const BTF_KIND_VOLATILE: u32 = 9;
+pub(super) const BTF_KIND_CONST: u32 = 10;
+pub(super) const BTF_KIND_RESTRICT: u32 = 11;
+pub(super) const BTF_KIND_FUNC: u32 = 12;
+pub(super) const BTF_KIND_FUNC_PROTO: u32 = 13;
+pub(super) const BTF_KIND_VAR: u32 = 14;
+pub(super) const BTF_KIND_DATASEC: u32 = 15;
+pub(super) const BTF_KIND_FLOAT: u32 = 16;
+pub(super) const BTF_KIND_DECL_TAG: u32 = 17;
+pub(super) const BTF_KIND_TYPE_TAG: u32 = 18;
+pub(super) const BTF_KIND_ENUM64: u32 = 19;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(C, packed)]
 pub(super) struct btf_type {
@@ -95,6 +209,8 @@ pub(super) struct btf_type {
 }
 
 impl btf_type {
+    pub(super) const LEN: usize = mem::size_of::<btf_type>();
+
     pub(super) fn from_reader<R: Read>(
         reader: &mut R,
         endianness: &Endianness,
@@ -106,6 +222,44 @@ impl btf_type {
         })
     }
 
+    pub(super) fn new(
+        name_off: u32,
+        kind: u32,
+        vlen: u32,
+        kind_flag: bool,
+        size_type: u32,
+    ) -> btf_type {
+        btf_type {
+            name_off,
+            info: (kind_flag as u32) << 31 | (kind & 0x1f) << 24 | (vlen & 0xffff),
+            size_type,
+        }
+    }
+
+    // Same type header, with a different vlen.
+    pub(super) fn with_vlen(&self, vlen: u32) -> btf_type {
+        btf_type {
+            info: (self.info & !0xffff) | (vlen & 0xffff),
+            ..*self
+        }
+    }
+
+    // Same type header, referencing a different type. Only meaningful for
+    // kinds using `size_type` as a type id.
+    pub(super) fn with_type(&self, r#type: u32) -> btf_type {
+        btf_type {
+            size_type: r#type,
+            ..*self
+        }
+    }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.u32_to_writer(writer, self.name_off)?;
+        endianness.u32_to_writer(writer, self.info)?;
+        endianness.u32_to_writer(writer, self.size_type)?;
+        Ok(())
+    }
+
     pub(super) fn vlen(&self) -> u32 {
         self.info & 0xffff
     }
@@ -125,6 +279,25 @@ impl btf_type {
     pub(super) fn r#type(&self) -> u32 {
         self.size_type
     }
+
+    // Layout of the data following the type header of standard kinds, as
+    // (size of the kind specific data, size of each of the vlen elements).
+    pub(super) fn kind_layout(kind: u32) -> Option<(usize, usize)> {
+        Some(match kind {
+            BTF_KIND_INT => (mem::size_of::<btf_int>(), 0),
+            BTF_KIND_ARRAY => (mem::size_of::<btf_array>(), 0),
+            BTF_KIND_STRUCT | BTF_KIND_UNION => (0, mem::size_of::<btf_member>()),
+            BTF_KIND_ENUM => (0, mem::size_of::<btf_enum>()),
+            BTF_KIND_FUNC_PROTO => (0, mem::size_of::<btf_param>()),
+            BTF_KIND_VAR => (mem::size_of::<btf_var>(), 0),
+            BTF_KIND_DATASEC => (0, mem::size_of::<btf_var_secinfo>()),
+            BTF_KIND_DECL_TAG => (mem::size_of::<btf_decl_tag>(), 0),
+            BTF_KIND_ENUM64 => (0, mem::size_of::<btf_enum64>()),
+            BTF_KIND_PTR | BTF_KIND_FWD | BTF_KIND_TYPEDEF | BTF_KIND_VOLATILE | BTF_KIND_CONST
+            | BTF_KIND_RESTRICT | BTF_KIND_FUNC | BTF_KIND_FLOAT | BTF_KIND_TYPE_TAG => (0, 0),
+            _ => return None,
+        })
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -140,6 +313,17 @@ impl btf_int {
         })
     }
 
+    pub(super) fn new(encoding: u32, offset: u32, bits: u32) -> btf_int {
+        btf_int {
+            data: (encoding & 0xf) << 24 | (offset & 0xff) << 16 | (bits & 0xff),
+        }
+    }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.u32_to_writer(writer, self.data)?;
+        Ok(())
+    }
+
     pub(super) fn encoding(&self) -> u32 {
         (self.data & 0x0f000000) >> 24
     }
@@ -176,6 +360,13 @@ impl btf_array {
             nelems: endianness.u32_from_reader(reader)?,
         })
     }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.u32_to_writer(writer, self.r#type)?;
+        endianness.u32_to_writer(writer, self.index_type)?;
+        endianness.u32_to_writer(writer, self.nelems)?;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -197,6 +388,13 @@ impl btf_member {
             offset: endianness.u32_from_reader(reader)?,
         })
     }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.u32_to_writer(writer, self.name_off)?;
+        endianness.u32_to_writer(writer, self.r#type)?;
+        endianness.u32_to_writer(writer, self.offset)?;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -216,6 +414,12 @@ impl btf_enum {
             val: endianness.u32_from_reader(reader)?,
         })
     }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.u32_to_writer(writer, self.name_off)?;
+        endianness.u32_to_writer(writer, self.val)?;
+        Ok(())
+    }
 }
 
 pub(super) const BTF_FUNC_STATIC: u32 = 0;
@@ -239,6 +443,12 @@ impl btf_param {
             r#type: endianness.u32_from_reader(reader)?,
         })
     }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.u32_to_writer(writer, self.name_off)?;
+        endianness.u32_to_writer(writer, self.r#type)?;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -253,6 +463,11 @@ impl btf_var {
             linkage: endianness.u32_from_reader(reader)?,
         })
     }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.u32_to_writer(writer, self.linkage)?;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -274,6 +489,13 @@ impl btf_var_secinfo {
             size: endianness.u32_from_reader(reader)?,
         })
     }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.u32_to_writer(writer, self.r#type)?;
+        endianness.u32_to_writer(writer, self.offset)?;
+        endianness.u32_to_writer(writer, self.size)?;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -291,6 +513,11 @@ impl btf_decl_tag {
             component_idx: endianness.i32_from_reader(reader)?,
         })
     }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.i32_to_writer(writer, self.component_idx)?;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -312,4 +539,11 @@ impl btf_enum64 {
             val_hi32: endianness.u32_from_reader(reader)?,
         })
     }
+
+    pub(super) fn write<W: Write>(&self, writer: &mut W, endianness: &Endianness) -> Result<()> {
+        endianness.u32_to_writer(writer, self.name_off)?;
+        endianness.u32_to_writer(writer, self.val_lo32)?;
+        endianness.u32_to_writer(writer, self.val_hi32)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,394 @@
+//! ### Writing BTF
+//!
+//! [`BtfBuilder`] builds raw BTF objects from scratch, one type at a time,
+//! which can then be parsed using `Btf::from_bytes()` (or
+//! `Btf::from_split_bytes()` for split objects). Existing objects can be
+//! serialized back using `Btf::to_bytes()`. Objects can also be described
+//! using C-like declarations, see [`utils::asm`](crate::utils::asm).
+//!
+//! ```
+//! use btf_rs::{encode::BtfBuilder, Btf, Type};
+//!
+//! let mut builder = BtfBuilder::new();
+//! let int = builder.add_int("int", 4, true).unwrap();
+//! let ptr = builder.add_ptr(int).unwrap();
+//! builder
+//!     .add_struct("foo", 16, &[("a", int, 0), ("b", ptr, 64)])
+//!     .unwrap();
+//!
+//! let btf = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+//! match btf.resolve_types_by_name("foo").unwrap().pop().unwrap() {
+//!     Type::Struct(foo) => assert_eq!(foo.members.len(), 2),
+//!     _ => panic!("Resolved type is not a struct"),
+//! }
+//! ```
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::{cbtf, Btf, Kind, StrOffset, TypeId};
+
+/// Builder of raw BTF objects. Types are given ids incrementally in the order
+/// they are added, starting at 1 (0 being `void`) or right after the base ids
+/// for split objects. Types can reference types added later, as long as their
+/// ids are known.
+pub struct BtfBuilder {
+    endianness: cbtf::Endianness,
+    // Type section being built.
+    types: Vec<u8>,
+    // String section being built.
+    strings: Vec<u8>,
+    // Offsets of the strings already in the string section, to avoid
+    // duplicates.
+    offsets: HashMap<String, u32>,
+    // Offset of the first string of the section, non-zero for split objects.
+    start_str_off: u32,
+    // Id of the next type to be added.
+    next_id: u32,
+}
+
+impl BtfBuilder {
+    /// Start building a base BTF object, using the native endianness.
+    pub fn new() -> BtfBuilder {
+        BtfBuilder {
+            endianness: cbtf::Endianness::native(),
+            types: Vec::new(),
+            strings: vec![0],
+            offsets: HashMap::from([(String::new(), 0)]),
+            start_str_off: 0,
+            next_id: 1,
+        }
+    }
+
+    /// Start building a split BTF object on top of `base`, which must not be a
+    /// split object itself. Types of the base can be referenced using their
+    /// ids.
+    pub fn new_split(base: &Btf) -> Result<BtfBuilder> {
+        if base.is_split() {
+            bail!("Split BTF objects can't be used as a base");
+        }
+
+        // The empty string is always at offset 0, in the base.
+        Ok(BtfBuilder {
+            endianness: cbtf::Endianness::native(),
+            types: Vec::new(),
+            strings: vec![0],
+            offsets: HashMap::from([(String::new(), 0)]),
+            start_str_off: base.str_len(),
+            next_id: base.id_range().end,
+        })
+    }
+
+    /// Add a string to the string section, if not already there, and return
+    /// its offset.
+    pub fn add_string(&mut self, s: &str) -> Result<StrOffset> {
+        if let Some(offset) = self.offsets.get(s) {
+            return Ok(StrOffset::from(*offset));
+        }
+        if s.contains('\0') {
+            bail!("String {s:?} contains a nul byte");
+        }
+
+        let offset = self.start_str_off + self.strings.len() as u32;
+        self.strings.extend_from_slice(s.as_bytes());
+        self.strings.push(0);
+        self.offsets.insert(s.to_string(), offset);
+        Ok(StrOffset::from(offset))
+    }
+
+    /// Add an integer type given its size in bytes.
+    pub fn add_int(&mut self, name: &str, size: usize, signed: bool) -> Result<TypeId> {
+        let encoding = match signed {
+            true => cbtf::BTF_INT_SIGNED,
+            false => 0,
+        };
+        let id = self.add_header(name, Kind::Int, 0, false, size as u32)?;
+        cbtf::btf_int::new(encoding, 0, size as u32 * 8)
+            .write(&mut self.types, &self.endianness)?;
+        Ok(id)
+    }
+
+    /// Add a pointer to `r#type`.
+    pub fn add_ptr(&mut self, r#type: TypeId) -> Result<TypeId> {
+        self.add_header("", Kind::Ptr, 0, false, r#type.into())
+    }
+
+    /// Add an array of `nelems` elements of type `r#type`, indexed by
+    /// `index_type`.
+    pub fn add_array(&mut self, r#type: TypeId, index_type: TypeId, nelems: u32) -> Result<TypeId> {
+        let id = self.add_header("", Kind::Array, 0, false, 0)?;
+        cbtf::btf_array {
+            r#type: r#type.into(),
+            index_type: index_type.into(),
+            nelems,
+        }
+        .write(&mut self.types, &self.endianness)?;
+        Ok(id)
+    }
+
+    /// Add a struct given its size in bytes and its members, as (name, type,
+    /// offset in bits) tuples. Bitfields are not supported.
+    pub fn add_struct(
+        &mut self,
+        name: &str,
+        size: usize,
+        members: &[(&str, TypeId, u32)],
+    ) -> Result<TypeId> {
+        self.add_composite(Kind::Struct, name, size, members)
+    }
+
+    /// Add a union given its size in bytes and its members, as (name, type,
+    /// offset in bits) tuples. Bitfields are not supported.
+    pub fn add_union(
+        &mut self,
+        name: &str,
+        size: usize,
+        members: &[(&str, TypeId, u32)],
+    ) -> Result<TypeId> {
+        self.add_composite(Kind::Union, name, size, members)
+    }
+
+    /// Add an enum given its size in bytes and its values. The enum is signed
+    /// if any of its values is negative.
+    pub fn add_enum(&mut self, name: &str, size: usize, values: &[(&str, i32)]) -> Result<TypeId> {
+        let signed = values.iter().any(|(_, val)| *val < 0);
+        let id = self.add_header(
+            name,
+            Kind::Enum,
+            cbtf::vlen(values.len())?,
+            signed,
+            size as u32,
+        )?;
+        for (name, val) in values {
+            cbtf::btf_enum {
+                name_off: self.add_string(name)?.into(),
+                val: *val as u32,
+            }
+            .write(&mut self.types, &self.endianness)?;
+        }
+        Ok(id)
+    }
+
+    /// Add a 64-bit enum given its size in bytes and its values. The enum is
+    /// signed if any of its values is negative.
+    pub fn add_enum64(
+        &mut self,
+        name: &str,
+        size: usize,
+        values: &[(&str, i64)],
+    ) -> Result<TypeId> {
+        let signed = values.iter().any(|(_, val)| *val < 0);
+        let id = self.add_header(
+            name,
+            Kind::Enum64,
+            cbtf::vlen(values.len())?,
+            signed,
+            size as u32,
+        )?;
+        for (name, val) in values {
+            cbtf::btf_enum64 {
+                name_off: self.add_string(name)?.into(),
+                val_lo32: *val as u32,
+                val_hi32: (*val >> 32) as u32,
+            }
+            .write(&mut self.types, &self.endianness)?;
+        }
+        Ok(id)
+    }
+
+    /// Add a forward declaration of a struct, or of a union if `union` is
+    /// true.
+    pub fn add_fwd(&mut self, name: &str, union: bool) -> Result<TypeId> {
+        self.add_header(name, Kind::Fwd, 0, union, 0)
+    }
+
+    /// Add a typedef of `r#type`.
+    pub fn add_typedef(&mut self, name: &str, r#type: TypeId) -> Result<TypeId> {
+        self.add_header(name, Kind::Typedef, 0, false, r#type.into())
+    }
+
+    /// Add a volatile qualifier to `r#type`.
+    pub fn add_volatile(&mut self, r#type: TypeId) -> Result<TypeId> {
+        self.add_header("", Kind::Volatile, 0, false, r#type.into())
+    }
+
+    /// Add a const qualifier to `r#type`.
+    pub fn add_const(&mut self, r#type: TypeId) -> Result<TypeId> {
+        self.add_header("", Kind::Const, 0, false, r#type.into())
+    }
+
+    /// Add a restrict qualifier to `r#type`.
+    pub fn add_restrict(&mut self, r#type: TypeId) -> Result<TypeId> {
+        self.add_header("", Kind::Restrict, 0, false, r#type.into())
+    }
+
+    /// Add a global function, given the id of its prototype.
+    pub fn add_func(&mut self, name: &str, proto: TypeId) -> Result<TypeId> {
+        self.add_header(name, Kind::Func, cbtf::BTF_FUNC_GLOBAL, false, proto.into())
+    }
+
+    /// Add a function prototype given its return type and its parameters, as
+    /// (name, type) tuples.
+    pub fn add_func_proto(&mut self, ret: TypeId, params: &[(&str, TypeId)]) -> Result<TypeId> {
+        let id = self.add_header(
+            "",
+            Kind::FuncProto,
+            cbtf::vlen(params.len())?,
+            false,
+            ret.into(),
+        )?;
+        for (name, r#type) in params {
+            cbtf::btf_param {
+                name_off: self.add_string(name)?.into(),
+                r#type: (*r#type).into(),
+            }
+            .write(&mut self.types, &self.endianness)?;
+        }
+        Ok(id)
+    }
+
+    /// Add a global variable of type `r#type`.
+    pub fn add_var(&mut self, name: &str, r#type: TypeId) -> Result<TypeId> {
+        let id = self.add_header(name, Kind::Var, 0, false, r#type.into())?;
+        cbtf::btf_var { linkage: 1 }.write(&mut self.types, &self.endianness)?;
+        Ok(id)
+    }
+
+    /// Add a data section given its size in bytes and its variables, as (var
+    /// type, offset, size) tuples.
+    pub fn add_datasec(
+        &mut self,
+        name: &str,
+        size: usize,
+        vars: &[(TypeId, u32, u32)],
+    ) -> Result<TypeId> {
+        let id = self.add_header(
+            name,
+            Kind::Datasec,
+            cbtf::vlen(vars.len())?,
+            false,
+            size as u32,
+        )?;
+        for (r#type, offset, size) in vars {
+            cbtf::btf_var_secinfo {
+                r#type: (*r#type).into(),
+                offset: *offset,
+                size: *size,
+            }
+            .write(&mut self.types, &self.endianness)?;
+        }
+        Ok(id)
+    }
+
+    /// Add a floating point type given its size in bytes.
+    pub fn add_float(&mut self, name: &str, size: usize) -> Result<TypeId> {
+        self.add_header(name, Kind::Float, 0, false, size as u32)
+    }
+
+    /// Add a declaration tag to `r#type`. `component_idx` is the index of the
+    /// tagged member or parameter, or -1 if the type itself is tagged.
+    pub fn add_decl_tag(
+        &mut self,
+        name: &str,
+        r#type: TypeId,
+        component_idx: i32,
+    ) -> Result<TypeId> {
+        let id = self.add_header(name, Kind::DeclTag, 0, false, r#type.into())?;
+        cbtf::btf_decl_tag { component_idx }.write(&mut self.types, &self.endianness)?;
+        Ok(id)
+    }
+
+    /// Add a type tag to `r#type`.
+    pub fn add_type_tag(&mut self, name: &str, r#type: TypeId) -> Result<TypeId> {
+        self.add_header(name, Kind::TypeTag, 0, false, r#type.into())
+    }
+
+    /// Get the raw BTF object built so far.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let header = cbtf::btf_header {
+            magic: 0xeb9f,
+            version: 1,
+            flags: 0,
+            hdr_len: cbtf::btf_header::KNOWN_LEN,
+            type_off: 0,
+            type_len: self.types.len() as u32,
+            str_off: self.types.len() as u32,
+            str_len: self.strings.len() as u32,
+        };
+
+        let mut bytes = Vec::with_capacity(
+            cbtf::btf_header::KNOWN_LEN as usize + self.types.len() + self.strings.len(),
+        );
+        header.write(&mut bytes, &self.endianness)?;
+        bytes.extend_from_slice(&self.types);
+        bytes.extend_from_slice(&self.strings);
+        Ok(bytes)
+    }
+
+    // Add a struct or union.
+    fn add_composite(
+        &mut self,
+        kind: Kind,
+        name: &str,
+        size: usize,
+        members: &[(&str, TypeId, u32)],
+    ) -> Result<TypeId> {
+        let id = self.add_header(name, kind, cbtf::vlen(members.len())?, false, size as u32)?;
+        for (name, r#type, offset) in members {
+            cbtf::btf_member {
+                name_off: self.add_string(name)?.into(),
+                r#type: (*r#type).into(),
+                offset: *offset,
+            }
+            .write(&mut self.types, &self.endianness)?;
+        }
+        Ok(id)
+    }
+
+    // Write the common header of a new type and return its id.
+    fn add_header(
+        &mut self,
+        name: &str,
+        kind: Kind,
+        vlen: u32,
+        kind_flag: bool,
+        size_type: u32,
+    ) -> Result<TypeId> {
+        let kind = match kind {
+            Kind::Void => bail!("Void can't be added, it is implicit"),
+            Kind::Int => cbtf::BTF_KIND_INT,
+            Kind::Ptr => cbtf::BTF_KIND_PTR,
+            Kind::Array => cbtf::BTF_KIND_ARRAY,
+            Kind::Struct => cbtf::BTF_KIND_STRUCT,
+            Kind::Union => cbtf::BTF_KIND_UNION,
+            Kind::Enum => cbtf::BTF_KIND_ENUM,
+            Kind::Fwd => cbtf::BTF_KIND_FWD,
+            Kind::Typedef => cbtf::BTF_KIND_TYPEDEF,
+            Kind::Volatile => cbtf::BTF_KIND_VOLATILE,
+            Kind::Const => cbtf::BTF_KIND_CONST,
+            Kind::Restrict => cbtf::BTF_KIND_RESTRICT,
+            Kind::Func => cbtf::BTF_KIND_FUNC,
+            Kind::FuncProto => cbtf::BTF_KIND_FUNC_PROTO,
+            Kind::Var => cbtf::BTF_KIND_VAR,
+            Kind::Datasec => cbtf::BTF_KIND_DATASEC,
+            Kind::Float => cbtf::BTF_KIND_FLOAT,
+            Kind::DeclTag => cbtf::BTF_KIND_DECL_TAG,
+            Kind::TypeTag => cbtf::BTF_KIND_TYPE_TAG,
+            Kind::Enum64 => cbtf::BTF_KIND_ENUM64,
+        };
+
+        let name_off = self.add_string(name)?.into();
+        cbtf::btf_type::new(name_off, kind, vlen, kind_flag, size_type)
+            .write(&mut self.types, &self.endianness)?;
+
+        let id = self.next_id;
+        self.next_id += 1;
+        Ok(TypeId::from(id))
+    }
+}
+
+impl Default for BtfBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
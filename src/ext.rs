@@ -0,0 +1,391 @@
+//! ### BTF.ext
+//!
+//! Compiled eBPF objects come with a `.BTF.ext` ELF section alongside their
+//! `.BTF` one, holding per-instruction information: the function each
+//! instruction belongs to (func_info), the source line it was compiled from
+//! (line_info) and the CO-RE relocations to apply to it (core_relo).
+//! [`BtfExt`] parses this section, resolving its strings and checking its type
+//! references against the matching [`Btf`] object.
+#![cfg_attr(
+    feature = "elf",
+    doc = r#"
+```no_run
+use btf_rs::{ext::BtfExt, utils::elf::{extract_btf_ext_from_file, extract_btf_from_file}, Btf};
+
+let btf = Btf::from_bytes(&extract_btf_from_file("prog.bpf.o").unwrap()).unwrap();
+let ext = BtfExt::from_bytes(&extract_btf_ext_from_file("prog.bpf.o").unwrap(), &btf).unwrap();
+
+for sec in ext.line_info.iter() {
+    for info in sec.records.iter() {
+        println!("{} {}: {}:{}", sec.name, info.insn_off, info.file_name, info.line_num);
+    }
+}
+```
+"#
+)]
+use std::{io::Read, mem};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::{utils::raw::CBtfType, Btf, Endianness, TypeId};
+
+/// Parsed `.BTF.ext` section. Records are grouped by the ELF section of the
+/// instructions they describe.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BtfExt {
+    pub func_info: Vec<ExtSection<FuncInfo>>,
+    pub line_info: Vec<ExtSection<LineInfo>>,
+    /// CO-RE relocations, empty if the section does not have any.
+    pub core_relos: Vec<ExtSection<CoreRelo>>,
+}
+
+/// Records of a `.BTF.ext` info section, for a single ELF section.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExtSection<T> {
+    /// Name of the ELF section, e.g. `kprobe/tcp_v4_connect`.
+    pub name: String,
+    pub records: Vec<T>,
+}
+
+/// Function an instruction is the start of.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FuncInfo {
+    /// Offset of the instruction in bytes, from the start of the section.
+    pub insn_off: u32,
+    /// Id of the `Func` type of the function.
+    pub type_id: TypeId,
+}
+
+/// Source line an instruction was compiled from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineInfo {
+    /// Offset of the instruction in bytes, from the start of the section.
+    pub insn_off: u32,
+    pub file_name: String,
+    /// Content of the source line.
+    pub line: String,
+    pub line_num: u32,
+    pub line_col: u32,
+}
+
+/// CO-RE relocation of an instruction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CoreRelo {
+    /// Offset of the instruction in bytes, from the start of the section.
+    pub insn_off: u32,
+    /// Id of the root type of the relocation.
+    pub type_id: TypeId,
+    /// Access string, e.g. `0:1:2`.
+    pub access: String,
+    pub kind: CoreReloKind,
+}
+
+/// Kind of a CO-RE relocation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CoreReloKind {
+    FieldByteOffset,
+    FieldByteSize,
+    FieldExists,
+    FieldSigned,
+    FieldLshiftU64,
+    FieldRshiftU64,
+    TypeIdLocal,
+    TypeIdTarget,
+    TypeExists,
+    TypeSize,
+    EnumvalExists,
+    EnumvalValue,
+    TypeMatches,
+}
+
+impl TryFrom<u32> for CoreReloKind {
+    type Error = anyhow::Error;
+
+    fn try_from(kind: u32) -> Result<Self> {
+        Ok(match kind {
+            0 => CoreReloKind::FieldByteOffset,
+            1 => CoreReloKind::FieldByteSize,
+            2 => CoreReloKind::FieldExists,
+            3 => CoreReloKind::FieldSigned,
+            4 => CoreReloKind::FieldLshiftU64,
+            5 => CoreReloKind::FieldRshiftU64,
+            6 => CoreReloKind::TypeIdLocal,
+            7 => CoreReloKind::TypeIdTarget,
+            8 => CoreReloKind::TypeExists,
+            9 => CoreReloKind::TypeSize,
+            10 => CoreReloKind::EnumvalExists,
+            11 => CoreReloKind::EnumvalValue,
+            12 => CoreReloKind::TypeMatches,
+            x => bail!("Unknown CO-RE relocation kind {x}"),
+        })
+    }
+}
+
+impl BtfExt {
+    /// Parse a raw `.BTF.ext` section, given the BTF object parsed from the
+    /// `.BTF` section of the same ELF file.
+    pub fn from_bytes(bytes: &[u8], btf: &Btf) -> Result<BtfExt> {
+        let endianness = Endianness::detect(bytes).context("Could not parse BTF.ext header")?;
+        let mut reader = Reader {
+            data: bytes,
+            endianness,
+        };
+        reader.skip(2)?;
+
+        let header = reader
+            .read::<ExtHeader>()
+            .context("Could not parse BTF.ext header")?;
+        if header.version != 1 {
+            bail!("Unsupported BTF.ext version: {}", header.version);
+        }
+        let hdr_len = header.hdr_len;
+        if hdr_len < ExtHeader::LEN {
+            bail!("Invalid BTF.ext header length: {hdr_len}");
+        }
+
+        // CO-RE relocations were added later on, as an extension of the
+        // header.
+        let core_relo = match header.hdr_len >= ExtHeader::LEN + 8 {
+            true => (reader.read::<u32>()?, reader.read::<u32>()?),
+            false => (0, 0),
+        };
+
+        let ext = ExtParser {
+            data: bytes
+                .get(header.hdr_len as usize..)
+                .ok_or_else(|| anyhow!("BTF.ext header out of bounds"))?,
+            endianness,
+            btf,
+        };
+
+        let func_info = ext
+            .sections::<RawFuncInfo, _>(header.func_info_off, header.func_info_len, |r| {
+                Ok(FuncInfo {
+                    insn_off: r.insn_off,
+                    type_id: check_type_id(btf, r.type_id)?,
+                })
+            })
+            .context("Could not parse func_info")?;
+        let line_info = ext
+            .sections::<RawLineInfo, _>(header.line_info_off, header.line_info_len, |r| {
+                Ok(LineInfo {
+                    insn_off: r.insn_off,
+                    file_name: ext_str(btf, r.file_name_off)?,
+                    line: ext_str(btf, r.line_off)?,
+                    line_num: r.line_col >> 10,
+                    line_col: r.line_col & 0x3ff,
+                })
+            })
+            .context("Could not parse line_info")?;
+        let core_relos = ext
+            .sections::<RawCoreRelo, _>(core_relo.0, core_relo.1, |r| {
+                Ok(CoreRelo {
+                    insn_off: r.insn_off,
+                    type_id: check_type_id(btf, r.type_id)?,
+                    access: ext_str(btf, r.access_str_off)?,
+                    kind: CoreReloKind::try_from(r.kind)?,
+                })
+            })
+            .context("Could not parse core_relo")?;
+
+        Ok(BtfExt {
+            func_info,
+            line_info,
+            core_relos,
+        })
+    }
+}
+
+// Check a type id referenced by a record exists.
+fn check_type_id(btf: &Btf, id: u32) -> Result<TypeId> {
+    btf.resolve_type_by_id(id)?;
+    Ok(TypeId::from(id))
+}
+
+// Get a string referenced by a record.
+fn ext_str(btf: &Btf, offset: u32) -> Result<String> {
+    btf.str_by_offset(offset)
+        .map(String::from)
+        .ok_or_else(|| anyhow!("No string at offset {offset}"))
+}
+
+// Sequential reader of raw data.
+struct Reader<'a> {
+    data: &'a [u8],
+    endianness: Endianness,
+}
+
+impl Reader<'_> {
+    fn read<T: CBtfType>(&mut self) -> Result<T> {
+        let (obj, len) = T::from_bytes(self.data, self.endianness)?;
+        self.data = &self.data[len..];
+        Ok(obj)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.data = self
+            .data
+            .get(len..)
+            .ok_or_else(|| anyhow!("Unexpected end of data"))?;
+        Ok(())
+    }
+}
+
+// Parser of the info sections of a BTF.ext object.
+struct ExtParser<'a> {
+    // Data following the header.
+    data: &'a [u8],
+    endianness: Endianness,
+    btf: &'a Btf,
+}
+
+impl ExtParser<'_> {
+    // Parse an info section, made of a record size followed by a list of
+    // (ELF section name offset, number of records, records) entries.
+    fn sections<R, T>(
+        &self,
+        off: u32,
+        len: u32,
+        convert: impl Fn(R) -> Result<T>,
+    ) -> Result<Vec<ExtSection<T>>>
+    where
+        R: CBtfType,
+    {
+        let mut sections = Vec::new();
+        if len == 0 {
+            return Ok(sections);
+        }
+
+        let mut reader = Reader {
+            data: (off as usize)
+                .checked_add(len as usize)
+                .and_then(|end| self.data.get(off as usize..end))
+                .ok_or_else(|| anyhow!("Info section out of bounds"))?,
+            endianness: self.endianness,
+        };
+
+        // Records can be extended in future versions, which is reflected in
+        // their size. Unknown fields are skipped.
+        let rec_size = reader.read::<u32>()? as usize;
+        if rec_size < R::SIZE {
+            bail!(
+                "Invalid record size {rec_size}, expected at least {}",
+                R::SIZE
+            );
+        }
+
+        while !reader.data.is_empty() {
+            let name_off = reader.read::<u32>()?;
+            let num_info = reader.read::<u32>()?;
+
+            let mut records = Vec::with_capacity(num_info.min(len) as usize);
+            for _ in 0..num_info {
+                let record = reader.read::<R>()?;
+                reader.skip(rec_size - R::SIZE)?;
+                records.push(convert(record)?);
+            }
+
+            sections.push(ExtSection {
+                name: ext_str(self.btf, name_off)?,
+                records,
+            });
+        }
+
+        Ok(sections)
+    }
+}
+
+// Raw BTF.ext header, following the magic. Raw records are named after
+// their kernel counterparts (struct btf_ext_header, bpf_func_info, etc) and use
+// a packed C representation so mem::size_of can be used.
+#[repr(C, packed)]
+struct ExtHeader {
+    version: u8,
+    flags: u8,
+    hdr_len: u32,
+    func_info_off: u32,
+    func_info_len: u32,
+    line_info_off: u32,
+    line_info_len: u32,
+}
+
+impl ExtHeader {
+    // Length of the header without CO-RE relocations, magic included.
+    const LEN: u32 = 2 + Self::SIZE as u32;
+}
+
+impl CBtfType for ExtHeader {
+    const SIZE: usize = mem::size_of::<Self>();
+
+    fn from_reader<R: Read>(reader: &mut R, endianness: Endianness) -> std::io::Result<Self> {
+        Ok(ExtHeader {
+            version: u8::from_reader(reader, endianness)?,
+            // Flags are not used as of now.
+            flags: u8::from_reader(reader, endianness)?,
+            hdr_len: u32::from_reader(reader, endianness)?,
+            func_info_off: u32::from_reader(reader, endianness)?,
+            func_info_len: u32::from_reader(reader, endianness)?,
+            line_info_off: u32::from_reader(reader, endianness)?,
+            line_info_len: u32::from_reader(reader, endianness)?,
+        })
+    }
+}
+
+#[repr(C, packed)]
+struct RawFuncInfo {
+    insn_off: u32,
+    type_id: u32,
+}
+
+impl CBtfType for RawFuncInfo {
+    const SIZE: usize = mem::size_of::<Self>();
+
+    fn from_reader<R: Read>(reader: &mut R, endianness: Endianness) -> std::io::Result<Self> {
+        Ok(RawFuncInfo {
+            insn_off: u32::from_reader(reader, endianness)?,
+            type_id: u32::from_reader(reader, endianness)?,
+        })
+    }
+}
+
+#[repr(C, packed)]
+struct RawLineInfo {
+    insn_off: u32,
+    file_name_off: u32,
+    line_off: u32,
+    line_col: u32,
+}
+
+impl CBtfType for RawLineInfo {
+    const SIZE: usize = mem::size_of::<Self>();
+
+    fn from_reader<R: Read>(reader: &mut R, endianness: Endianness) -> std::io::Result<Self> {
+        Ok(RawLineInfo {
+            insn_off: u32::from_reader(reader, endianness)?,
+            file_name_off: u32::from_reader(reader, endianness)?,
+            line_off: u32::from_reader(reader, endianness)?,
+            line_col: u32::from_reader(reader, endianness)?,
+        })
+    }
+}
+
+#[repr(C, packed)]
+struct RawCoreRelo {
+    insn_off: u32,
+    type_id: u32,
+    access_str_off: u32,
+    kind: u32,
+}
+
+impl CBtfType for RawCoreRelo {
+    const SIZE: usize = mem::size_of::<Self>();
+
+    fn from_reader<R: Read>(reader: &mut R, endianness: Endianness) -> std::io::Result<Self> {
+        Ok(RawCoreRelo {
+            insn_off: u32::from_reader(reader, endianness)?,
+            type_id: u32::from_reader(reader, endianness)?,
+            access_str_off: u32::from_reader(reader, endianness)?,
+            kind: u32::from_reader(reader, endianness)?,
+        })
+    }
+}
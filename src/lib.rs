@@ -11,7 +11,7 @@
 //! ### Parsing BTF
 //!
 //! The main object this library offers is [`Btf`], which represents a parsed
-//! BTF object. It offers helpers to resolve ids ([`u32`]), names ([`String`])
+//! BTF object. It offers helpers to resolve ids ([`TypeId`]), names ([`String`])
 //! and types / chained types ([`Type`]).
 //!
 //! [`Btf`] can be constructed using a BTF file or a split BTF one. BTF files
@@ -89,6 +89,19 @@
 //! and members, etc. can be retrieved. For all those see the [`Type`] and its
 //! associated structures documentation.
 //!
+//! ### Writing BTF
+//!
+//! Parsed [`Btf`] objects can be serialized back to raw bytes using
+//! `Btf::to_bytes()`, and new BTF objects (including split ones) can be built
+//! from scratch using the [`encode`] sub-module. Structurally identical types
+//! can be merged using `Btf::dedup()`.
+//!
+//! ### BTF.ext
+//!
+//! The `.BTF.ext` ELF section of BPF objects, holding function, line and CO-RE
+//! relocation information, can be parsed using [`ext::BtfExt`]. Its records
+//! are cross-referenced against the [`Btf`] object of the same ELF file.
+//!
 //! ### Additional objects
 //!
 //! Additional objects built on top of the ones described here can be found in
@@ -97,16 +110,32 @@
 //!
 //! ### Feature flags
 //!
-//! - elf: Enable helpers parsing the .BTF section of ELF files in
+//! - derive: Enable `#[derive(FromBtf)]` in `utils::layout`, to read kernel
+//!   structs into Rust types, and `#[derive(CBtfType)]` in `utils::raw`, to
+//!   parse raw binary records.
+//! - elf: Enable helpers parsing the .BTF and .BTF.ext sections of ELF files in
 //!   `utils::elf`.
+//! - json: Enable exporting BTF objects as JSON, in the format of `bpftool`, in
+//!   `utils::export`. Implies `serde`.
+//! - metrics: Collect lookup statistics, retrievable using `Btf::metrics()`.
+//! - regex: Enable name lookups using regular expressions in
+//!   `utils::matcher`.
+//! - serde: Enable serializing and deserializing types, with their names and
+//!   values resolved, in `utils::resolved`.
 //! - test_runtime: Use the system's runtime BTF files to perform extra
 //!   integration tests.
 
 pub mod btf;
+pub mod encode;
+pub mod ext;
 pub mod utils;
 
 mod cbtf;
+mod metrics;
 mod obj;
 
 #[doc(inline)]
 pub use btf::*;
+pub use cbtf::Endianness;
+#[cfg(feature = "metrics")]
+pub use metrics::{Metrics, LOOKUP_TIME_BUCKETS};
@@ -0,0 +1,83 @@
+//! Lookup statistics, collected when the `metrics` feature is enabled.
+#[cfg(feature = "metrics")]
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+// Kind of lookup being recorded.
+#[derive(Clone, Copy)]
+pub(crate) enum Lookup {
+    // Lookup by name.
+    Name,
+    // Lookup by id.
+    Id,
+}
+
+/// Upper bounds of the buckets of the lookup duration histogram. An extra
+/// bucket holds the lookups slower than the last bound.
+#[cfg(feature = "metrics")]
+pub const LOOKUP_TIME_BUCKETS: [Duration; 4] = [
+    Duration::from_micros(1),
+    Duration::from_micros(10),
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+];
+
+/// Snapshot of the lookup statistics of a [`crate::Btf`] object.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Metrics {
+    /// Lookups by name returning at least one result.
+    pub name_hits: u64,
+    /// Lookups by name returning no result.
+    pub name_misses: u64,
+    /// Lookups by id returning a type.
+    pub id_hits: u64,
+    /// Lookups by id returning no type.
+    pub id_misses: u64,
+    /// Histogram of the lookup durations, see [`LOOKUP_TIME_BUCKETS`].
+    pub lookup_time: [u64; LOOKUP_TIME_BUCKETS.len() + 1],
+}
+
+// Counters backing the lookup statistics, updated concurrently.
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+pub(crate) struct Counters {
+    name_hits: AtomicU64,
+    name_misses: AtomicU64,
+    id_hits: AtomicU64,
+    id_misses: AtomicU64,
+    lookup_time: [AtomicU64; LOOKUP_TIME_BUCKETS.len() + 1],
+}
+
+#[cfg(feature = "metrics")]
+impl Counters {
+    // Record a single lookup.
+    pub(crate) fn record(&self, kind: Lookup, hit: bool, duration: Duration) {
+        match (kind, hit) {
+            (Lookup::Name, true) => &self.name_hits,
+            (Lookup::Name, false) => &self.name_misses,
+            (Lookup::Id, true) => &self.id_hits,
+            (Lookup::Id, false) => &self.id_misses,
+        }
+        .fetch_add(1, Ordering::Relaxed);
+
+        let bucket = LOOKUP_TIME_BUCKETS
+            .iter()
+            .position(|bound| duration < *bound)
+            .unwrap_or(LOOKUP_TIME_BUCKETS.len());
+        self.lookup_time[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Get a snapshot of the counters.
+    pub(crate) fn snapshot(&self) -> Metrics {
+        Metrics {
+            name_hits: self.name_hits.load(Ordering::Relaxed),
+            name_misses: self.name_misses.load(Ordering::Relaxed),
+            id_hits: self.id_hits.load(Ordering::Relaxed),
+            id_misses: self.id_misses.load(Ordering::Relaxed),
+            lookup_time: std::array::from_fn(|i| self.lookup_time[i].load(Ordering::Relaxed)),
+        }
+    }
+}
@@ -1,81 +1,210 @@
 #![allow(dead_code)]
 
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
     ffi::CStr,
-    io::{BufRead, Seek, SeekFrom},
-    sync::Arc,
+    hash::{BuildHasherDefault, Hasher},
+    io::{BufRead, Read, Seek, SeekFrom},
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 
 use crate::btf::*;
 use crate::cbtf;
 
+// Next identifier to assign to a parsed BTF object.
+static NEXT_FINGERPRINT: AtomicU64 = AtomicU64::new(0);
+
+// Map keyed by type ids or string offsets.
+type IdTable<V> = HashMap<u32, V, BuildHasherDefault<IdHasher>>;
+
+// Hasher for small integer keys, for which the default one (SipHash) is
+// needlessly slow. This is the Fx hash function used in rustc, which is not
+// resistant to collision attacks but keys here come from the parsed data
+// layout (incremental ids, increasing offsets), not from user input.
+#[derive(Default)]
+struct IdHasher(u64);
+
+impl IdHasher {
+    fn add(&mut self, word: u64) {
+        self.0 = (self.0.rotate_left(5) ^ word).wrapping_mul(0x517cc1b727220a95);
+    }
+}
+
+impl Hasher for IdHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        bytes.iter().for_each(|b| self.add(*b as u64));
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// Original bytes of the type section, and offsets in it of the record of each
+// type defined in an object (skipped ones included), indexed by their id minus
+// the id of the first type of the object.
+struct RawRecords {
+    types: Vec<u8>,
+    offsets: Vec<u32>,
+}
+
+impl RawRecords {
+    fn record(&self, index: usize) -> Option<&[u8]> {
+        let start = *self.offsets.get(index)? as usize;
+        let end = match self.offsets.get(index + 1) {
+            Some(end) => *end as usize,
+            None => self.types.len(),
+        };
+        self.types.get(start..end)
+    }
+}
+
 /// Main representation of a parsed BTF object. Provides helpers to resolve
 /// types and their associated names and maintains a symbol to type map for
 /// symbol resolution.
 pub(super) struct BtfObj {
     endianness: cbtf::Endianness,
+    // Flags of the BTF header.
+    flags: u8,
     // Map from str offsets to the strings. For internal use (name resolution)
     // only.
-    str_cache: HashMap<u32, String>,
+    str_cache: IdTable<Arc<str>>,
+    // Original bytes of the strings which were not valid UTF-8 and were
+    // decoded in a lossy way, keyed by their offset.
+    raw_strings: IdTable<Vec<u8>>,
     // Map from symbol names to their type id, used for retrieving a type by its
-    // name.
-    strings: HashMap<String, Vec<u32>>,
+    // name. Keys are the bytes of the names to allow lookups using byte slices;
+    // names decoded in a lossy way are indexed using both their decoded and
+    // their original bytes.
+    strings: HashMap<Vec<u8>, Vec<u32>>,
     // Vector of all the types parsed from the BTF info. The vector makes the
     // retrieval by their id implicit as the id is incremental in the BTF file;
     // but that is really the goal here.
-    types: HashMap<u32, Type>,
+    types: IdTable<Type>,
     // Length of the string section. Used to calculate the next string offset
     // of split BTFs.
     str_len: u32,
+    // Id of the first type defined in this object.
+    start_id: u32,
+    // Id following the last type defined in this object. Ids in between can
+    // be unused if type records were skipped.
+    end_id: u32,
+    // Type records skipped in tolerant mode.
+    skipped: Vec<SkippedType>,
+    // Original type records, if requested (see
+    // `BtfOptions::keep_raw_records()`).
+    raw_records: Option<RawRecords>,
+    // Identifier of the object, unique in the process.
+    fingerprint: u64,
 }
 
 impl BtfObj {
-    /// Parse a BTF object from a Reader.
+    /// Parse a BTF object from a Reader, starting at its current position.
     pub(super) fn from_reader<R: Seek + BufRead>(
         reader: &mut R,
         base: Option<Arc<BtfObj>>,
+        options: &BtfOptions,
     ) -> Result<BtfObj> {
+        // Offsets are relative to the start of the object.
+        let start = reader.stream_position()?;
+
         // First parse the BTF header, retrieve the endianness & perform sanity
         // checks.
-        let (header, endianness) = cbtf::btf_header::from_reader(reader)?;
+        let (header, endianness) =
+            cbtf::btf_header::from_reader(reader).context("Could not parse BTF header")?;
         if header.version != 1 {
             bail!("Unsupported BTF version: {}", header.version);
         }
 
+        // The header can be extended in future versions, which is reflected in
+        // its length. Unknown fields can be ignored as long as they are all
+        // zero, which is what the kernel enforces too.
+        let hdr_len = header.hdr_len;
+        if hdr_len < cbtf::btf_header::KNOWN_LEN {
+            bail!("Invalid BTF header length: {hdr_len}");
+        }
+        // The header length is not trusted, read its tail by chunks.
+        let mut tail = reader
+            .by_ref()
+            .take((hdr_len - cbtf::btf_header::KNOWN_LEN) as u64);
+        let mut chunk = [0; 64];
+        loop {
+            let len = tail.read(&mut chunk)?;
+            if len == 0 {
+                break;
+            }
+            if chunk[..len].iter().any(|b| *b != 0) {
+                bail!("Unsupported BTF header extension (length {hdr_len})");
+            }
+        }
+        if tail.limit() != 0 {
+            bail!("Truncated BTF header (length {hdr_len})");
+        }
+
+        let (est_types, est_strings) = match options.capacity {
+            Some(capacity) => capacity,
+            None => {
+                let end = reader.seek(SeekFrom::End(0))?;
+                header.estimates(end.saturating_sub(start))
+            }
+        };
+
         // Cache the str section for later use (name resolution).
         let offset = header.hdr_len + header.str_off;
-        reader.seek(SeekFrom::Start(offset as u64))?;
+        reader.seek(SeekFrom::Start(start + offset as u64))?;
 
-        let mut str_cache = HashMap::new();
+        let mut str_cache = IdTable::with_capacity_and_hasher(est_strings, Default::default());
+        let mut raw_strings = IdTable::default();
         let mut offset: u32 = 0;
 
         // For split BTFs both ids and string offsets are logically consecutive.
         let (mut id, start_str_off) = match base {
             None => (1, 0),
-            Some(ref base) => (base.types.len() as u32, base.str_len),
+            Some(ref base) => (base.end_id, base.str_len),
         };
+        let start_id = if base.is_none() { 0 } else { id };
 
         while offset < header.str_len {
             let mut raw = Vec::new();
-            let bytes = reader.read_until(b'\0', &mut raw)? as u32;
+            let bytes = reader.read_until(b'\0', &mut raw).with_context(|| {
+                format!("Could not read string at offset {}", start_str_off + offset)
+            })? as u32;
 
             let s = CStr::from_bytes_with_nul(&raw)
-                .map_err(|e| anyhow!("Could not parse string: {}", e))?
-                .to_str()?;
-            str_cache.insert(start_str_off + offset, String::from(s));
+                .map_err(|e| anyhow!("Could not parse string: {}", e))?;
+            let s = match (s.to_str(), options.strings) {
+                (Ok(s), _) => String::from(s),
+                (Err(e), StringDecoding::Strict) => {
+                    bail!("Invalid string at offset {}: {e}", start_str_off + offset)
+                }
+                (Err(_), StringDecoding::Lossy) => {
+                    raw_strings.insert(start_str_off + offset, s.to_bytes().to_vec());
+                    s.to_string_lossy().into_owned()
+                }
+            };
+            str_cache.insert(start_str_off + offset, Arc::from(s));
 
             offset += bytes;
         }
 
         // Finally build our representation of the BTF types.
         let offset = header.hdr_len + header.type_off;
-        reader.seek(SeekFrom::Start(offset as u64))?;
+        reader.seek(SeekFrom::Start(start + offset as u64))?;
 
-        let mut strings: HashMap<String, Vec<u32>> = HashMap::new();
-        let mut types = HashMap::new();
+        // Names are shared between types, but most types are named.
+        let mut strings: HashMap<Vec<u8>, Vec<u32>> = HashMap::with_capacity(est_types);
+        let mut types = IdTable::with_capacity_and_hasher(est_types + 1, Default::default());
 
         if base.is_none() {
             // Add special type Void with ID 0 (not described in type section)
@@ -83,39 +212,58 @@ impl BtfObj {
             types.insert(0, Type::Void);
         }
 
-        let end_type_section = offset as u64 + header.type_len as u64;
+        let mut skipped = Vec::new();
+        let mut record_offsets = options.keep_raw_records.then(|| {
+            let mut offsets = Vec::with_capacity(est_types + 1);
+            if base.is_none() {
+                // Void has no record, use an empty one.
+                offsets.push(0);
+            }
+            offsets
+        });
+        let type_section = start + offset as u64;
+        let end_type_section = type_section + header.type_len as u64;
         while reader.stream_position()? < end_type_section {
-            let bt = cbtf::btf_type::from_reader(reader, &endianness)?;
-
-            // Each BTF type needs specific handling to parse its type-specific
-            // header.
-            types.insert(
-                id,
-                match bt.kind() {
-                    1 => Type::Int(Int::from_reader(reader, &endianness, bt)?),
-                    2 => Type::Ptr(Ptr::new(bt)),
-                    3 => Type::Array(Array::from_reader(reader, &endianness, bt)?),
-                    4 => Type::Struct(Struct::from_reader(reader, &endianness, bt)?),
-                    5 => Type::Union(Struct::from_reader(reader, &endianness, bt)?),
-                    6 => Type::Enum(Enum::from_reader(reader, &endianness, bt)?),
-                    7 => Type::Fwd(Fwd::new(bt)),
-                    8 => Type::Typedef(Typedef::new(bt)),
-                    9 => Type::Volatile(Volatile::new(bt)),
-                    10 => Type::Const(Volatile::new(bt)),
-                    11 => Type::Restrict(Volatile::new(bt)),
-                    12 => Type::Func(Func::new(bt)),
-                    13 => Type::FuncProto(FuncProto::from_reader(reader, &endianness, bt)?),
-                    14 => Type::Var(Var::from_reader(reader, &endianness, bt)?),
-                    15 => Type::Datasec(Datasec::from_reader(reader, &endianness, bt)?),
-                    16 => Type::Float(Float::new(bt)),
-                    17 => Type::DeclTag(DeclTag::from_reader(reader, &endianness, bt)?),
-                    18 => Type::TypeTag(Typedef::new(bt)),
-                    19 => Type::Enum64(Enum64::from_reader(reader, &endianness, bt)?),
-                    // We can't ignore unsupported types as we can't guess their
-                    // size and thus how much to skip to the next type.
-                    x => bail!("Unsupported BTF type '{}'", x),
-                },
-            );
+            let pos = reader.stream_position()? - start;
+            if let Some(offsets) = record_offsets.as_mut() {
+                offsets.push((start + pos - type_section) as u32);
+            }
+            let bt = cbtf::btf_type::from_reader(reader, &endianness)
+                .with_context(|| format!("Could not parse type {id} at offset {pos}"))?;
+            let r#type = match Self::parse_type(reader, &endianness, bt, id) {
+                Ok(r#type) => r#type,
+                Err(e) if options.tolerant => {
+                    let layout = options
+                        .kind_layouts
+                        .iter()
+                        .rev()
+                        .find(|(kind, ..)| *kind == bt.kind())
+                        .map(|(_, info, elem)| (*info, *elem))
+                        .or_else(|| cbtf::btf_type::kind_layout(bt.kind()));
+                    let Some((info, elem)) = layout else {
+                        return Err(e.context(format!(
+                            "Could not parse type {id} at offset {pos}: unknown layout of kind {}",
+                            bt.kind()
+                        )));
+                    };
+
+                    let len = cbtf::btf_type::LEN + info + elem * bt.vlen() as usize;
+                    reader.seek(SeekFrom::Start(start + pos + len as u64))?;
+                    skipped.push(SkippedType {
+                        id: TypeId::from(id),
+                        kind: bt.kind(),
+                        offset: (start + pos - type_section) as usize,
+                        reason: format!("{e:#}"),
+                    });
+                    id += 1;
+                    continue;
+                }
+                Err(e) => {
+                    return Err(e.context(format!("Could not parse type {id} at offset {pos}")))
+                }
+            };
+
+            types.insert(id, r#type);
 
             if bt.name_off > 0 {
                 let name_off = bt.name_off;
@@ -125,11 +273,23 @@ impl BtfObj {
                     .get(&name_off)
                     .or_else(|| base.as_ref().and_then(|base| base.str_cache.get(&name_off)));
 
+                let raw = raw_strings.get(&name_off).or_else(|| {
+                    base.as_ref()
+                        .and_then(|base| base.raw_strings.get(&name_off))
+                });
+
                 match name {
-                    Some(name) => match strings.get_mut(name) {
-                        Some(entry) => entry.push(id),
-                        None => _ = strings.insert(name.clone(), vec![id]),
-                    },
+                    Some(name) => {
+                        for key in [Some(name.as_bytes()), raw.map(|r| r.as_slice())]
+                            .into_iter()
+                            .flatten()
+                        {
+                            match strings.get_mut(key) {
+                                Some(entry) => entry.push(id),
+                                None => _ = strings.insert(key.to_vec(), vec![id]),
+                            }
+                        }
+                    }
                     None => bail!(
                         "Couldn't get string at offset {} defined in kind {}",
                         name_off,
@@ -146,21 +306,287 @@ impl BtfObj {
             bail!("Invalid type section");
         }
 
-        Ok(BtfObj {
+        let raw_records = match record_offsets {
+            Some(offsets) => {
+                let mut types = vec![0; header.type_len as usize];
+                reader.seek(SeekFrom::Start(type_section))?;
+                reader.read_exact(&mut types)?;
+                Some(RawRecords { types, offsets })
+            }
+            None => None,
+        };
+
+        let obj = BtfObj {
             endianness,
+            flags: header.flags,
             str_cache,
+            raw_strings,
             strings,
             types,
             str_len: header.str_len,
-        })
+            start_id,
+            end_id: id,
+            skipped,
+            raw_records,
+            fingerprint: NEXT_FINGERPRINT.fetch_add(1, Ordering::Relaxed),
+        };
+        if options.check_references {
+            if let Some((from, to)) = obj.dangling_references(base.as_deref()).first() {
+                bail!("Type {from} references non-existing type {to}");
+            }
+        }
+
+        Ok(obj)
     }
 
-    /// Find a list of BTF ids using their name as a key.
-    pub(super) fn resolve_ids_by_name(&self, name: &str) -> Result<Vec<u32>> {
-        match self.strings.get(name) {
-            Some(ids) => Ok(ids.clone()),
-            None => bail!("No id linked to name {name}"),
+    /// List the references to types not defined in this object nor in its
+    /// base, as (referencing id, referenced id) pairs sorted by the former.
+    pub(super) fn dangling_references(&self, base: Option<&BtfObj>) -> Vec<(u32, u32)> {
+        let exists = |id| {
+            self.types.contains_key(&id) || base.is_some_and(|base| base.types.contains_key(&id))
+        };
+
+        let mut dangling = Vec::new();
+        for (id, r#type) in self.types.iter() {
+            for r#ref in r#type.references() {
+                if !exists(r#ref) {
+                    dangling.push((*id, r#ref));
+                }
+            }
         }
+        dangling.sort_unstable();
+        dangling
+    }
+
+    /// Serialize the object back to a raw BTF object, keeping its original
+    /// endianness and flags. For split objects only the split part is written,
+    /// string offsets and type ids being relative to the base.
+    pub(super) fn to_bytes(&self, base: Option<&BtfObj>) -> Result<Vec<u8>> {
+        self.encode(self.ordered_types()?, base)
+    }
+
+    // Get the types defined in this object, in id order.
+    fn ordered_types(&self) -> Result<Vec<&Type>> {
+        let mut types = Vec::with_capacity(self.types.len());
+        for id in self.ids() {
+            match self.types.get(&id) {
+                Some(r#type) => types.push(r#type),
+                None => bail!("No type with id {id}"),
+            }
+        }
+        Ok(types)
+    }
+
+    // Build a raw BTF object made of the given types, in order, and of the
+    // string section of the object.
+    fn encode<'a>(
+        &self,
+        types: impl IntoIterator<Item = &'a Type>,
+        base: Option<&BtfObj>,
+    ) -> Result<Vec<u8>> {
+        let mut type_section = Vec::new();
+        for r#type in types {
+            r#type.write(&mut type_section, &self.endianness)?;
+        }
+
+        // Strings were read consecutively, writing them back in the order of
+        // their offsets keeps those unchanged.
+        let start_str_off = base.map(|base| base.str_len).unwrap_or_default();
+        let mut offsets: Vec<&u32> = self.str_cache.keys().collect();
+        offsets.sort_unstable();
+
+        let mut strings = Vec::with_capacity(self.str_len as usize);
+        for offset in offsets {
+            if *offset != start_str_off + strings.len() as u32 {
+                bail!("Non-contiguous string at offset {offset}");
+            }
+            match self.raw_strings.get(offset) {
+                Some(raw) => strings.extend_from_slice(raw),
+                None => strings.extend_from_slice(self.str_cache[offset].as_bytes()),
+            }
+            strings.push(0);
+        }
+
+        let header = cbtf::btf_header {
+            magic: 0xeb9f,
+            version: 1,
+            flags: self.flags,
+            hdr_len: cbtf::btf_header::KNOWN_LEN,
+            type_off: 0,
+            type_len: type_section.len() as u32,
+            str_off: type_section.len() as u32,
+            str_len: strings.len() as u32,
+        };
+
+        let mut bytes = Vec::with_capacity(
+            cbtf::btf_header::KNOWN_LEN as usize + type_section.len() + strings.len(),
+        );
+        header.write(&mut bytes, &self.endianness)?;
+        bytes.extend_from_slice(&type_section);
+        bytes.extend_from_slice(&strings);
+        Ok(bytes)
+    }
+
+    /// Compute the new ids of the types of the object once deduplicated, see
+    /// `Btf::dedup()`. Only the ids which change are part of the map.
+    pub(super) fn dedup_map(&self) -> Result<HashMap<u32, u32>> {
+        // Identical strings can be found at different offsets, use the first
+        // one. Base strings are left untouched.
+        let mut canonical: HashMap<&[u8], u32> = HashMap::new();
+        for (offset, s) in self.str_cache.iter() {
+            let raw = self.raw_strings.get(offset).map_or(s.as_bytes(), |r| r);
+            let entry = canonical.entry(raw).or_insert(*offset);
+            *entry = (*entry).min(*offset);
+        }
+        let strs = |offset: u32| {
+            self.str_cache
+                .get(&offset)
+                .map(|s| self.raw_strings.get(&offset).map_or(s.as_bytes(), |r| r))
+                .and_then(|raw| canonical.get(raw).copied())
+                .unwrap_or(offset)
+        };
+
+        let types = self.ordered_types()?;
+        let start = self.start_id;
+        let index = |id: u32| {
+            id.checked_sub(start)
+                .map(|i| i as usize)
+                .filter(|i| *i < types.len())
+        };
+
+        // Types are first grouped by their raw representation, without
+        // references and with canonical strings. Types not defined in this
+        // object (e.g. base ones) are their own group.
+        let mut keys = Vec::with_capacity(types.len());
+        let mut refs = Vec::with_capacity(types.len());
+        for r#type in types.iter() {
+            let mut local = (*r#type).clone();
+            local.remap(|_| 0, strs);
+            let mut raw = Vec::new();
+            local.write(&mut raw, &self.endianness)?;
+            keys.push(raw);
+
+            refs.push(
+                r#type
+                    .references()
+                    .into_iter()
+                    .map(|r| index(r).ok_or(r))
+                    .collect(),
+            );
+        }
+        let groups = group_types(keys, refs);
+
+        // Keep the first type of each group and compute the new ids.
+        let mut new_ids: HashMap<u64, u32> = HashMap::new();
+        let mut map = HashMap::new();
+        for (id, group) in self.ids().zip(groups.iter()) {
+            let next = start + new_ids.len() as u32;
+            let new = *new_ids.entry(*group).or_insert(next);
+            if new != id {
+                map.insert(id, new);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Move the types of the object to new ids and serialize the result as a
+    /// raw BTF object, see `Btf::remap_ids()`.
+    pub(super) fn remap(&self, base: Option<&BtfObj>, map: &HashMap<u32, u32>) -> Result<Vec<u8>> {
+        let apply = |id: u32| map.get(&id).copied().unwrap_or(id);
+        if self.start_id == 0 && apply(0) != 0 {
+            bail!("Void must keep id 0");
+        }
+
+        // Keep the first type mapped to each id in the range of the object,
+        // which must not leave holes.
+        let range = self.ids();
+        let mut kept = BTreeMap::new();
+        for (id, r#type) in range.clone().zip(self.ordered_types()?) {
+            let new = apply(id);
+            if range.contains(&new) {
+                kept.entry(new).or_insert(r#type);
+            }
+        }
+        if let Some(missing) = range.clone().zip(kept.keys()).find(|(id, new)| id != *new) {
+            bail!("No type mapped to id {}", missing.0);
+        }
+
+        let kept = kept
+            .into_values()
+            .map(|r#type| {
+                let mut r#type = r#type.clone();
+                r#type.remap(apply, |offset| offset);
+                r#type
+            })
+            .collect::<Vec<_>>();
+        self.encode(&kept, base)
+    }
+
+    /// Get the identifier of the object, unique in the process.
+    pub(super) fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Get the byte order of the object.
+    pub(super) fn endianness(&self) -> cbtf::Endianness {
+        self.endianness
+    }
+
+    /// Get the length of the string section.
+    pub(super) fn str_len(&self) -> u32 {
+        self.str_len
+    }
+
+    /// Get the flags of the BTF header.
+    pub(super) fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    // Parse the type-specific part of a BTF type, given its common header and
+    // its id.
+    fn parse_type<R: Seek + BufRead>(
+        reader: &mut R,
+        endianness: &cbtf::Endianness,
+        bt: cbtf::btf_type,
+        id: u32,
+    ) -> Result<Type> {
+        // Each BTF type needs specific handling to parse its type-specific
+        // header.
+        Ok(match bt.kind() {
+            cbtf::BTF_KIND_INT => Type::Int(Int::from_reader(reader, endianness, bt)?),
+            cbtf::BTF_KIND_PTR => Type::Ptr(Ptr::new(bt)),
+            cbtf::BTF_KIND_ARRAY => Type::Array(Array::from_reader(reader, endianness, bt)?),
+            cbtf::BTF_KIND_STRUCT => Type::Struct(Struct::from_reader(reader, endianness, bt, id)?),
+            cbtf::BTF_KIND_UNION => Type::Union(Struct::from_reader(reader, endianness, bt, id)?),
+            cbtf::BTF_KIND_ENUM => Type::Enum(Enum::from_reader(reader, endianness, bt)?),
+            cbtf::BTF_KIND_FWD => Type::Fwd(Fwd::new(bt)),
+            cbtf::BTF_KIND_TYPEDEF => Type::Typedef(Typedef::new(bt)),
+            cbtf::BTF_KIND_VOLATILE => Type::Volatile(Volatile::new(bt)),
+            cbtf::BTF_KIND_CONST => Type::Const(Volatile::new(bt)),
+            cbtf::BTF_KIND_RESTRICT => Type::Restrict(Volatile::new(bt)),
+            cbtf::BTF_KIND_FUNC => Type::Func(Func::new(bt)),
+            cbtf::BTF_KIND_FUNC_PROTO => {
+                Type::FuncProto(FuncProto::from_reader(reader, endianness, bt)?)
+            }
+            cbtf::BTF_KIND_VAR => Type::Var(Var::from_reader(reader, endianness, bt)?),
+            cbtf::BTF_KIND_DATASEC => Type::Datasec(Datasec::from_reader(reader, endianness, bt)?),
+            cbtf::BTF_KIND_FLOAT => Type::Float(Float::new(bt)),
+            cbtf::BTF_KIND_DECL_TAG => Type::DeclTag(DeclTag::from_reader(reader, endianness, bt)?),
+            cbtf::BTF_KIND_TYPE_TAG => Type::TypeTag(Typedef::new(bt)),
+            cbtf::BTF_KIND_ENUM64 => Type::Enum64(Enum64::from_reader(reader, endianness, bt)?),
+            // We can't ignore unsupported types as we can't guess their
+            // size and thus how much to skip to the next type.
+            x => bail!("Unsupported BTF type '{}'", x),
+        })
+    }
+
+    /// Get the ids linked to a name, without copying them. Returns an empty
+    /// slice if the name is unknown.
+    pub(super) fn ids_by_name_bytes(&self, name: &[u8]) -> &[u32] {
+        self.strings
+            .get(name)
+            .map(|ids| ids.as_slice())
+            .unwrap_or_default()
     }
 
     /// Find a BTF type using its id as a key.
@@ -171,23 +597,67 @@ impl BtfObj {
         }
     }
 
-    /// Find a list of BTF types using their name as a key.
-    pub(super) fn resolve_types_by_name(&self, name: &str) -> Result<Vec<Type>> {
-        let mut types = Vec::new();
-        for id in self.resolve_ids_by_name(name)? {
-            types.push(self.resolve_type_by_id(id)?);
-        }
-        Ok(types)
+    /// Range of the ids of the types defined in this object.
+    pub(super) fn ids(&self) -> Range<u32> {
+        self.start_id..self.end_id
+    }
+
+    /// Type records skipped while parsing the object in tolerant mode.
+    pub(super) fn skipped(&self) -> &[SkippedType] {
+        &self.skipped
+    }
+
+    /// Iterate over the names linked to at least one type, in no particular
+    /// order. Original bytes of names decoded in a lossy way are not part of
+    /// the output.
+    pub(super) fn names(&self) -> impl Iterator<Item = &str> {
+        self.strings
+            .keys()
+            .filter_map(|s| std::str::from_utf8(s).ok())
+    }
+
+    /// Get a reference to a BTF type using its id as a key.
+    pub(super) fn type_by_id(&self, id: u32) -> Option<&Type> {
+        self.types.get(&id)
     }
 
-    /// Resolve a name referenced by a Type which is defined in the current BTF
-    /// object.
-    pub(super) fn resolve_name<T: BtfType + ?Sized>(&self, r#type: &T) -> Result<String> {
-        let offset = r#type.get_name_offset()?;
+    /// Get the bytes of the record of a type defined in this object, given its
+    /// id. Those are the original ones if kept while parsing, or the parsed
+    /// type encoded back otherwise.
+    pub(super) fn raw_record(&self, id: u32) -> Result<Option<Cow<'_, [u8]>>> {
+        let raw = match &self.raw_records {
+            Some(raw) => id
+                .checked_sub(self.start_id)
+                .and_then(|index| raw.record(index as usize))
+                .map(Cow::Borrowed),
+            None => match self.types.get(&id) {
+                Some(r#type) => {
+                    let mut raw = Vec::new();
+                    r#type.write(&mut raw, &self.endianness)?;
+                    Some(Cow::Owned(raw))
+                }
+                None => None,
+            },
+        };
+        Ok(raw.filter(|r| !r.is_empty()))
+    }
+
+    /// Get a reference to a string given its offset.
+    pub(super) fn str_by_offset(&self, offset: u32) -> Option<&str> {
+        self.str_cache.get(&offset).map(|s| s.as_ref())
+    }
+
+    /// Get a shared handle to a string given its offset.
+    pub(super) fn str_arc_by_offset(&self, offset: u32) -> Option<Arc<str>> {
+        self.str_cache.get(&offset).cloned()
+    }
 
-        match self.str_cache.get(&offset) {
-            Some(s) => Ok(s.clone()),
-            None => bail!("No string at offset {}", offset),
+    /// Get the raw bytes of a string given its offset. Those differ from the
+    /// ones of the decoded string only if it was decoded in a lossy way.
+    pub(super) fn str_bytes_by_offset(&self, offset: u32) -> Option<&[u8]> {
+        match self.raw_strings.get(&offset) {
+            Some(raw) => Some(raw),
+            None => self.str_cache.get(&offset).map(|s| s.as_bytes()),
         }
     }
 
@@ -195,6 +665,6 @@ impl BtfObj {
     /// helper resolve a Type referenced in an other one. It is the main helper
     /// to traverse the Type tree.
     pub(super) fn resolve_chained_type<T: BtfType + ?Sized>(&self, r#type: &T) -> Result<Type> {
-        self.resolve_type_by_id(r#type.get_type_id()?)
+        self.resolve_type_by_id(r#type.get_type_id()?.into())
     }
 }
@@ -0,0 +1,158 @@
+//! ### Symbol aliasing
+//!
+//! Some kernel symbols are mangled in an architecture or configuration
+//! specific way, e.g. syscall entry points being named `__x64_sys_openat` on
+//! x86_64 and `__arm64_sys_openat` on arm64. [`Aliases`] holds a set of user
+//! supplied prefix and suffix rewriting rules, which [`AliasedBtf`] uses to
+//! transparently try the mangled variants of a name on lookups.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::{alias::{AliasedBtf, Aliases}, arch::Arch}};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! let aliased = AliasedBtf::new(&btf, Aliases::syscalls(Arch::X86_64));
+//!
+//! // Resolves to __x64_sys_openat.
+//! let openat = aliased.resolve_types_by_name("sys_openat").unwrap();
+//! ```
+use std::ops::Deref;
+
+use anyhow::{bail, Result};
+
+use crate::{utils::arch::Arch, Btf, Type, TypeId};
+
+/// Rewriting rule used to generate an alias from a name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Rule {
+    /// Replace the `from` prefix of a name by `to`. An empty `from` prefix
+    /// matches all names.
+    Prefix { from: String, to: String },
+    /// Replace the `from` suffix of a name by `to`. An empty `from` suffix
+    /// matches all names.
+    Suffix { from: String, to: String },
+}
+
+impl Rule {
+    /// Apply the rule to a name, returning the generated alias if the rule
+    /// matched.
+    pub fn apply(&self, name: &str) -> Option<String> {
+        match self {
+            Rule::Prefix { from, to } => {
+                name.strip_prefix(from.as_str()).map(|s| format!("{to}{s}"))
+            }
+            Rule::Suffix { from, to } => {
+                name.strip_suffix(from.as_str()).map(|s| format!("{s}{to}"))
+            }
+        }
+    }
+}
+
+/// Ordered set of aliasing rules.
+#[derive(Clone, Debug, Default)]
+pub struct Aliases {
+    rules: Vec<Rule>,
+}
+
+impl Aliases {
+    /// Construct an empty set of rules.
+    pub fn new() -> Aliases {
+        Aliases::default()
+    }
+
+    /// Construct the set of rules mapping `sys_<name>` to the syscall entry
+    /// points of a given architecture.
+    pub fn syscalls(arch: Arch) -> Aliases {
+        Aliases::new().prefix("sys_", arch.syscall_prefix())
+    }
+
+    /// Add a prefix rewriting rule.
+    pub fn prefix(mut self, from: &str, to: &str) -> Aliases {
+        self.rules.push(Rule::Prefix {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        self
+    }
+
+    /// Add a suffix rewriting rule.
+    pub fn suffix(mut self, from: &str, to: &str) -> Aliases {
+        self.rules.push(Rule::Suffix {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        self
+    }
+
+    /// Get the rules, in the order they are applied.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// List the names to look for given an input name: the name itself first,
+    /// then all the aliases generated by the matching rules, in order.
+    pub fn candidates(&self, name: &str) -> Vec<String> {
+        let mut candidates = vec![name.to_string()];
+        self.rules
+            .iter()
+            .filter_map(|r| r.apply(name))
+            .for_each(|alias| {
+                if !candidates.contains(&alias) {
+                    candidates.push(alias);
+                }
+            });
+        candidates
+    }
+}
+
+/// Wrapper around a `Btf` object performing name lookups using a set of
+/// aliasing rules. Lookups return the results of the first candidate name (see
+/// [`Aliases::candidates`]) matching in the underlying `Btf` object. All other
+/// `Btf` helpers are directly available through dereferencing.
+pub struct AliasedBtf<'a> {
+    btf: &'a Btf,
+    aliases: Aliases,
+}
+
+impl<'a> AliasedBtf<'a> {
+    /// Construct an AliasedBtf object given a `Btf` object and a set of
+    /// aliasing rules.
+    pub fn new(btf: &'a Btf, aliases: Aliases) -> AliasedBtf<'a> {
+        AliasedBtf { btf, aliases }
+    }
+
+    /// Get the aliasing rules in use.
+    pub fn aliases(&self) -> &Aliases {
+        &self.aliases
+    }
+
+    /// Find a list of BTF ids using their name, or one of its aliases, as a
+    /// key.
+    pub fn resolve_ids_by_name(&self, name: &str) -> Result<Vec<TypeId>> {
+        for candidate in self.aliases.candidates(name) {
+            if let Ok(ids) = self.btf.resolve_ids_by_name(&candidate) {
+                return Ok(ids);
+            }
+        }
+        bail!("No id linked to name {name} or its aliases");
+    }
+
+    /// Find a list of BTF types using their name, or one of its aliases, as a
+    /// key.
+    pub fn resolve_types_by_name(&self, name: &str) -> Result<Vec<Type>> {
+        for candidate in self.aliases.candidates(name) {
+            if let Ok(types) = self.btf.resolve_types_by_name(&candidate) {
+                return Ok(types);
+            }
+        }
+        bail!("No id linked to name {name} or its aliases");
+    }
+}
+
+/// Let dereference AliasedBtf into Btf directly for ease of use.
+impl Deref for AliasedBtf<'_> {
+    type Target = Btf;
+
+    fn deref(&self) -> &Self::Target {
+        self.btf
+    }
+}
@@ -0,0 +1,171 @@
+//! ### Architecture specific definitions
+//!
+//! BTF is architecture independent but some of the kernel symbols and
+//! conventions it describes are not. The [`Arch`] type is used by helpers
+//! needing to handle those differences.
+//!
+//! [`param_locations`] maps the parameters of a function to the registers
+//! used to pass them according to the kernel calling convention, which is
+//! useful for kprobe based tools retrieving arguments from `struct pt_regs`.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, Type, utils::arch::{param_locations, Arch}};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! let func = match btf.resolve_types_by_name("kfree_skb_reason").unwrap().pop().unwrap() {
+//!     Type::Func(func) => func,
+//!     _ => panic!("Resolved type is not a function"),
+//! };
+//! let proto = match btf.resolve_chained_type(&func).unwrap() {
+//!     Type::FuncProto(proto) => proto,
+//!     _ => panic!("Resolved type is not a function proto"),
+//! };
+//!
+//! // The following prints "[Register("di"), Register("si")]".
+//! println!("{:?}", param_locations(&btf, &proto, Arch::X86_64).unwrap());
+//! ```
+//!
+//! The other way around, [`param_of_register`] finds which parameter (and its
+//! type) a `struct pt_regs` field holds when a function is entered.
+
+use anyhow::{bail, Result};
+
+use crate::{Btf, FuncProto, Type};
+
+/// Architectures supported by the arch specific helpers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Arch {
+    X86_64,
+    Arm64,
+}
+
+impl Arch {
+    /// Returns the architecture the library was built for, if supported.
+    pub fn host() -> Option<Arch> {
+        if cfg!(target_arch = "x86_64") {
+            Some(Arch::X86_64)
+        } else if cfg!(target_arch = "aarch64") {
+            Some(Arch::Arm64)
+        } else {
+            None
+        }
+    }
+
+    /// Prefix of the symbols implementing the syscall entry points.
+    pub fn syscall_prefix(&self) -> &'static str {
+        match self {
+            Arch::X86_64 => "__x64_sys_",
+            Arch::Arm64 => "__arm64_sys_",
+        }
+    }
+
+    /// Registers used to pass arguments, in order, as named in the kernel
+    /// `struct pt_regs` definition.
+    pub fn arg_registers(&self) -> &'static [&'static str] {
+        match self {
+            Arch::X86_64 => &["di", "si", "dx", "cx", "r8", "r9"],
+            Arch::Arm64 => &[
+                "regs[0]", "regs[1]", "regs[2]", "regs[3]", "regs[4]", "regs[5]", "regs[6]",
+                "regs[7]",
+            ],
+        }
+    }
+}
+
+/// Location of a function parameter when the function is called.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParamLocation {
+    /// Parameter is passed in a single register, given its `struct pt_regs`
+    /// field name.
+    Register(&'static str),
+    /// Parameter is passed in two consecutive registers, given their `struct
+    /// pt_regs` field names, lower bytes first.
+    RegisterPair(&'static str, &'static str),
+    /// Parameter is copied in memory and a pointer to that copy is passed in a
+    /// register, given its `struct pt_regs` field name.
+    Reference(&'static str),
+    /// Parameter is passed on the stack.
+    Stack,
+}
+
+/// Map each parameter of a function prototype to its location according to
+/// the kernel calling convention of the given architecture. This is only driven
+/// by the parameters types and arity, and does not handle floating point
+/// parameters nor variadic functions (which are not used in the kernel for the
+/// former and can't be described for the latter).
+pub fn param_locations(btf: &Btf, proto: &FuncProto, arch: Arch) -> Result<Vec<ParamLocation>> {
+    let regs = arch.arg_registers();
+    let mut next = 0;
+    let mut locations = Vec::new();
+
+    for param in proto.parameters.iter() {
+        if param.is_variadic() {
+            bail!("Can't compute the location of variadic parameters");
+        }
+
+        let r#type = btf.resolve_underlying_type(&btf.resolve_chained_type(param)?)?;
+        if let Type::Float(_) = r#type {
+            bail!("Floating point parameters are not supported");
+        }
+        let size = btf.resolve_type_size(&r#type)?;
+
+        let location = match size {
+            0..=8 if next < regs.len() => {
+                next += 1;
+                ParamLocation::Register(regs[next - 1])
+            }
+            9..=16 if next + 1 < regs.len() => {
+                next += 2;
+                ParamLocation::RegisterPair(regs[next - 2], regs[next - 1])
+            }
+            // Large composite types are copied in memory by the caller on
+            // arm64, and a pointer to the copy is used as the parameter.
+            17.. if arch == Arch::Arm64 && next < regs.len() => {
+                next += 1;
+                ParamLocation::Reference(regs[next - 1])
+            }
+            _ => {
+                // On arm64 once a parameter is put on the stack, all the
+                // following ones are too.
+                if arch == Arch::Arm64 {
+                    next = regs.len();
+                }
+                ParamLocation::Stack
+            }
+        };
+        locations.push(location);
+    }
+
+    Ok(locations)
+}
+
+/// Find the parameter of a function passed in a given register, as named in the
+/// `struct pt_regs` definition (e.g. "si" on x86_64). Returns the index of the
+/// parameter in the prototype and its type, or `None` if the register is not
+/// used to pass a parameter. Registers holding part of a parameter passed in a
+/// pair of registers are matched too, while for parameters passed by reference
+/// the register holds a pointer to a copy of a parameter of the returned type.
+pub fn param_of_register(
+    btf: &Btf,
+    proto: &FuncProto,
+    arch: Arch,
+    register: &str,
+) -> Result<Option<(usize, Type)>> {
+    if !arch.arg_registers().contains(&register) {
+        bail!("Register {register} is not used to pass arguments on {arch:?}");
+    }
+
+    for (index, location) in param_locations(btf, proto, arch)?.iter().enumerate() {
+        let found = match location {
+            ParamLocation::Register(reg) | ParamLocation::Reference(reg) => *reg == register,
+            ParamLocation::RegisterPair(lo, hi) => *lo == register || *hi == register,
+            ParamLocation::Stack => false,
+        };
+        if found {
+            let r#type = btf.resolve_chained_type(&proto.parameters[index])?;
+            return Ok(Some((index, r#type)));
+        }
+    }
+
+    Ok(None)
+}
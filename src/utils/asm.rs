@@ -0,0 +1,755 @@
+//! ### Textual definition of BTF objects
+//!
+//! Building BTF objects type by type using [`BtfBuilder`] is verbose and
+//! requires to compute member offsets by hand. [`assemble`] builds a BTF
+//! object out of C-like declarations instead, which keeps test fixtures
+//! compact and readable.
+//!
+//! ```
+//! use btf_rs::{utils::asm::assemble_btf, Type};
+//!
+//! let btf = assemble_btf(
+//!     "
+//!     struct bar;
+//!
+//!     struct foo {
+//!         int a;
+//!         struct bar *b;
+//!         unsigned long c[4];
+//!         union { char d; short e; };
+//!     };
+//!
+//!     enum state { STOPPED, RUNNING = 4 };
+//!     typedef struct foo foo_t;
+//!     int process(foo_t *foo, enum state state);
+//!     ",
+//! )
+//! .unwrap();
+//!
+//! match btf.resolve_types_by_name("foo").unwrap().pop().unwrap() {
+//!     Type::Struct(foo) => assert_eq!(foo.size(), 56),
+//!     _ => panic!("Resolved type is not a struct"),
+//! }
+//! ```
+//!
+//! Supported declarations are:
+//!
+//! - Structs, unions and enums: definitions and forward declarations. Structs
+//!   and unions declared but never defined are emitted as forward
+//!   declarations. Enums with values not fitting in an `int` are emitted as
+//!   64-bit enums.
+//! - Typedefs.
+//! - Functions, emitted along with their prototype. Variadic functions are
+//!   supported.
+//! - Global variables.
+//!
+//! Base types (e.g. `int`, `unsigned long`, `_Bool`, `double`) are added on
+//! first use and named as in the kernel BTF (e.g. `long unsigned int`). Types
+//! can be qualified (`const`, `volatile` and `restrict`) and declarators can
+//! be pointers and arrays. Member offsets follow the natural alignment of
+//! types on 64-bit architectures. Bitfields and function pointers are not
+//! supported. Comments start with `//` and span to the end of the line.
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{encode::BtfBuilder, Btf, Kind, TypeId};
+
+/// Build a raw BTF object from C-like declarations. See the module
+/// documentation for the supported syntax.
+pub fn assemble(src: &str) -> Result<Vec<u8>> {
+    let mut asm = Assembler {
+        tokens: tokenize(src)?,
+        pos: 0,
+        types: Vec::new(),
+        names: HashMap::new(),
+        derived: HashMap::new(),
+    };
+    while asm.peek().is_some() {
+        asm.external()?;
+    }
+    asm.emit()
+}
+
+/// Build a BTF object from C-like declarations. See [`assemble`].
+pub fn assemble_btf(src: &str) -> Result<Btf> {
+    Btf::from_bytes(&assemble(src)?)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(i128),
+    Punct(char),
+    Ellipsis,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "'{s}'"),
+            Token::Number(n) => write!(f, "'{n}'"),
+            Token::Punct(c) => write!(f, "'{c}'"),
+            Token::Ellipsis => write!(f, "'...'"),
+        }
+    }
+}
+
+// Split the source into tokens, along with their line number.
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    for (line, text) in src.lines().enumerate() {
+        let line = line + 1;
+        let text = text.split("//").next().unwrap_or_default();
+        let mut chars = text.char_indices().peekable();
+
+        while let Some((start, c)) = chars.next() {
+            let token = match c {
+                c if c.is_whitespace() => continue,
+                c if c.is_ascii_alphabetic() || c == '_' => {
+                    let mut end = start + 1;
+                    while let Some((i, c)) = chars.peek() {
+                        if !c.is_ascii_alphanumeric() && *c != '_' {
+                            break;
+                        }
+                        end = i + 1;
+                        chars.next();
+                    }
+                    Token::Ident(text[start..end].to_string())
+                }
+                c if c.is_ascii_digit() => {
+                    let mut end = start + 1;
+                    while let Some((i, c)) = chars.peek() {
+                        if !c.is_ascii_alphanumeric() {
+                            break;
+                        }
+                        end = i + 1;
+                        chars.next();
+                    }
+                    let num = &text[start..end];
+                    let val = match num.strip_prefix("0x") {
+                        Some(hex) => i128::from_str_radix(hex, 16),
+                        None => num.parse(),
+                    };
+                    Token::Number(val.map_err(|_| anyhow!("line {line}: invalid number '{num}'"))?)
+                }
+                '.' if text[start..].starts_with("...") => {
+                    chars.nth(1);
+                    Token::Ellipsis
+                }
+                '{' | '}' | '(' | ')' | '[' | ']' | ';' | ',' | '*' | '=' | '-' | ':' => {
+                    Token::Punct(c)
+                }
+                c => bail!("line {line}: unexpected character '{c}'"),
+            };
+            tokens.push((token, line));
+        }
+    }
+    Ok(tokens)
+}
+
+// Keywords, which can't be used as identifiers.
+const BASE_WORDS: &[&str] = &[
+    "void", "char", "short", "int", "long", "signed", "unsigned", "_Bool", "float", "double",
+];
+const QUALIFIERS: &[&str] = &["const", "volatile", "restrict"];
+const KEYWORDS: &[&str] = &["struct", "union", "enum", "typedef"];
+
+// Type to be emitted. Types are stored in emission order, their id being
+// their index + 1.
+enum Decl {
+    Int(String, usize, bool),
+    Float(String, usize),
+    Ptr(TypeId),
+    Array(TypeId, TypeId, u32),
+    Qualifier(Kind, TypeId),
+    Fwd(String, bool),
+    Composite {
+        union: bool,
+        name: String,
+        size: usize,
+        align: usize,
+        members: Vec<(String, TypeId, u32)>,
+    },
+    Enum(String, Vec<(String, i128)>),
+    Typedef(String, TypeId),
+    FuncProto(TypeId, Vec<(String, TypeId)>),
+    Func(String, TypeId),
+    Var(String, TypeId),
+}
+
+struct Assembler {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    types: Vec<Decl>,
+    // Named types: base types and typedefs by name, structs, unions and
+    // enums prefixed by their keyword (e.g. `struct foo`).
+    names: HashMap<String, TypeId>,
+    // Pointers, qualifiers and arrays already added, to avoid duplicates.
+    derived: HashMap<(Kind, TypeId, u32), TypeId>,
+}
+
+impl Assembler {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map(|(_, line)| *line)
+            .unwrap_or(1)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|(t, _)| t.clone())
+            .ok_or_else(|| anyhow!("line {}: unexpected end of input", self.line()))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        match self.peek() {
+            Some(t) => bail!("line {}: expected {expected}, found {t}", self.line()),
+            None => bail!(
+                "line {}: expected {expected}, found end of input",
+                self.line()
+            ),
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => self.unexpected(&format!("'{c}'")),
+        }
+    }
+
+    fn peek_ident(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Ident(s)) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> bool {
+        if self.peek_ident() == Some(ident) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    // Get the next token if it is an identifier, and not a keyword.
+    fn name(&mut self) -> Option<String> {
+        match self.peek_ident() {
+            Some(s)
+                if !BASE_WORDS.contains(&s)
+                    && !QUALIFIERS.contains(&s)
+                    && !KEYWORDS.contains(&s) =>
+            {
+                let s = s.to_string();
+                self.pos += 1;
+                Some(s)
+            }
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> Result<i128> {
+        let neg = self.eat('-');
+        match self.next()? {
+            Token::Number(n) if neg => Ok(-n),
+            Token::Number(n) => Ok(n),
+            _ => {
+                self.pos -= 1;
+                self.unexpected("a number")
+            }
+        }
+    }
+
+    fn push(&mut self, decl: Decl) -> TypeId {
+        self.types.push(decl);
+        TypeId::from(self.types.len() as u32)
+    }
+
+    fn decl(&self, id: TypeId) -> Option<&Decl> {
+        match u32::from(id) {
+            0 => None,
+            id => self.types.get(id as usize - 1),
+        }
+    }
+
+    fn derived(&mut self, kind: Kind, r#type: TypeId, nelems: u32) -> Result<TypeId> {
+        if let Some(id) = self.derived.get(&(kind, r#type, nelems)) {
+            return Ok(*id);
+        }
+        let decl = match kind {
+            Kind::Ptr => Decl::Ptr(r#type),
+            Kind::Array => Decl::Array(r#type, self.base("int", 4, true), nelems),
+            kind => Decl::Qualifier(kind, r#type),
+        };
+        let id = self.push(decl);
+        self.derived.insert((kind, r#type, nelems), id);
+        Ok(id)
+    }
+
+    // Get a base type, adding it on first use.
+    fn base(&mut self, name: &str, size: usize, signed: bool) -> TypeId {
+        if let Some(id) = self.names.get(name) {
+            return *id;
+        }
+        let id = self.push(Decl::Int(name.to_string(), size, signed));
+        self.names.insert(name.to_string(), id);
+        id
+    }
+
+    // Size and alignment, in bytes, of a type.
+    fn layout(&self, id: TypeId) -> Result<(usize, usize)> {
+        Ok(match self.decl(id) {
+            None => bail!("line {}: void has no size", self.line()),
+            Some(Decl::Int(_, size, _)) | Some(Decl::Float(_, size)) => (*size, *size),
+            Some(Decl::Ptr(_)) => (8, 8),
+            Some(Decl::Array(r#type, _, nelems)) => {
+                let (size, align) = self.layout(*r#type)?;
+                let size = size
+                    .checked_mul(*nelems as usize)
+                    .ok_or_else(|| anyhow!("line {}: type too large", self.line()))?;
+                (size, align)
+            }
+            Some(Decl::Qualifier(_, r#type)) | Some(Decl::Typedef(_, r#type)) => {
+                self.layout(*r#type)?
+            }
+            Some(Decl::Composite { size, align, .. }) => (*size, *align),
+            Some(Decl::Enum(_, values)) => match enum_size(values) {
+                4 => (4, 4),
+                _ => (8, 8),
+            },
+            Some(Decl::Fwd(name, _)) => {
+                bail!("line {}: incomplete type '{name}'", self.line())
+            }
+            Some(_) => bail!("line {}: type has no size", self.line()),
+        })
+    }
+
+    // Top level declaration.
+    fn external(&mut self) -> Result<()> {
+        if self.eat_ident("typedef") {
+            let base = self.type_spec()?;
+            let (name, r#type) = self.declarator(base)?;
+            let Some(name) = name else {
+                return self.unexpected("a typedef name");
+            };
+            self.expect(';')?;
+            let id = self.push(Decl::Typedef(name.clone(), r#type));
+            self.names.insert(name, id);
+            return Ok(());
+        }
+
+        let base = self.type_spec()?;
+        // Struct, union and enum definitions and declarations.
+        if self.eat(';') {
+            return Ok(());
+        }
+
+        let (name, r#type) = self.declarator(base)?;
+        let Some(name) = name else {
+            return self.unexpected("a name");
+        };
+        if self.eat('(') {
+            let params = self.params()?;
+            let proto = self.push(Decl::FuncProto(r#type, params));
+            self.push(Decl::Func(name, proto));
+        } else {
+            // Variables must have a complete type.
+            self.layout(r#type)?;
+            self.push(Decl::Var(name, r#type));
+        }
+        self.expect(';')
+    }
+
+    // Function parameters, after the opening parenthesis. Variadic functions
+    // have a last parameter with no name and a void type.
+    fn params(&mut self) -> Result<Vec<(String, TypeId)>> {
+        let mut params = Vec::new();
+        if self.peek_ident() == Some("void")
+            && self.tokens.get(self.pos + 1).map(|(t, _)| t) == Some(&Token::Punct(')'))
+        {
+            self.pos += 2;
+            return Ok(params);
+        }
+
+        while !self.eat(')') {
+            if !params.is_empty() {
+                self.expect(',')?;
+            }
+            if self.peek() == Some(&Token::Ellipsis) {
+                self.pos += 1;
+                params.push((String::new(), TypeId::default()));
+                self.expect(')')?;
+                break;
+            }
+            let base = self.type_spec()?;
+            let (name, r#type) = self.declarator(base)?;
+            params.push((name.unwrap_or_default(), r#type));
+        }
+        Ok(params)
+    }
+
+    // Type specifier, with its qualifiers.
+    fn type_spec(&mut self) -> Result<TypeId> {
+        let mut qualifiers = Vec::new();
+        self.qualifiers(&mut qualifiers);
+
+        let mut r#type = match self.peek_ident() {
+            Some("struct") => self.composite(false)?,
+            Some("union") => self.composite(true)?,
+            Some("enum") => self.r#enum()?,
+            Some(s) if BASE_WORDS.contains(&s) => self.base_type()?,
+            Some(s) => match self.names.get(s) {
+                Some(id) => {
+                    let id = *id;
+                    self.pos += 1;
+                    id
+                }
+                None => bail!("line {}: unknown type '{s}'", self.line()),
+            },
+            None => return self.unexpected("a type"),
+        };
+
+        self.qualifiers(&mut qualifiers);
+        for kind in qualifiers {
+            r#type = self.derived(kind, r#type, 0)?;
+        }
+        Ok(r#type)
+    }
+
+    fn qualifiers(&mut self, qualifiers: &mut Vec<Kind>) {
+        loop {
+            match self.peek_ident() {
+                Some("const") => qualifiers.push(Kind::Const),
+                Some("volatile") => qualifiers.push(Kind::Volatile),
+                Some("restrict") => qualifiers.push(Kind::Restrict),
+                _ => return,
+            }
+            self.pos += 1;
+        }
+    }
+
+    // Declarator: pointers, name (optional) and array dimensions.
+    fn declarator(&mut self, base: TypeId) -> Result<(Option<String>, TypeId)> {
+        let mut r#type = base;
+        while self.eat('*') {
+            r#type = self.derived(Kind::Ptr, r#type, 0)?;
+            let mut qualifiers = Vec::new();
+            self.qualifiers(&mut qualifiers);
+            for kind in qualifiers {
+                r#type = self.derived(kind, r#type, 0)?;
+            }
+        }
+
+        let name = self.name();
+        let mut dims = Vec::new();
+        while self.eat('[') {
+            let nelems = self.number()?;
+            dims.push(
+                u32::try_from(nelems)
+                    .map_err(|_| anyhow!("line {}: invalid array size {nelems}", self.line()))?,
+            );
+            self.expect(']')?;
+        }
+        // int a[2][3] is an array of 2 arrays of 3 ints.
+        for nelems in dims.into_iter().rev() {
+            r#type = self.derived(Kind::Array, r#type, nelems)?;
+        }
+        Ok((name, r#type))
+    }
+
+    fn base_type(&mut self) -> Result<TypeId> {
+        let line = self.line();
+        let mut words = Vec::new();
+        while let Some(word) = self.peek_ident().filter(|w| BASE_WORDS.contains(w)) {
+            words.push(word.to_string());
+            self.pos += 1;
+        }
+
+        let count = |w: &str| words.iter().filter(|x| *x == w).count();
+        let (signed, unsigned, longs) = (count("signed"), count("unsigned"), count("long"));
+        let others = words.len() - signed - unsigned - longs - count("int");
+        if signed + unsigned > 1 || longs > 2 || others > 1 {
+            bail!("line {line}: invalid type '{}'", words.join(" "));
+        }
+        let sign = |name: &str, size| {
+            let name = match unsigned {
+                0 => format!("{name} int"),
+                _ => format!("{name} unsigned int"),
+            };
+            (name.trim_start().to_string(), size, unsigned == 0)
+        };
+
+        let other = words
+            .iter()
+            .find(|w| !["signed", "unsigned", "long", "int"].contains(&w.as_str()))
+            .map(|w| w.as_str());
+        let (name, size, signed) = match (other, longs) {
+            (Some("void"), 0) if words.len() == 1 => return Ok(TypeId::default()),
+            (Some("_Bool"), 0) if words.len() == 1 => ("_Bool".to_string(), 1, false),
+            (Some("float"), 0) if words.len() == 1 => return Ok(self.float("float", 4)),
+            (Some("double"), 0) if words.len() == 1 => return Ok(self.float("double", 8)),
+            (Some("double"), 1) if words.len() == 2 => return Ok(self.float("long double", 16)),
+            (Some("char"), 0) if words.len() == 1 + signed + unsigned => match (signed, unsigned) {
+                (0, 0) => ("char".to_string(), 1, false),
+                (_, 0) => ("signed char".to_string(), 1, true),
+                _ => ("unsigned char".to_string(), 1, false),
+            },
+            (Some("short"), 0) => sign("short", 2),
+            (None, 2) => sign("long long", 8),
+            (None, 1) => sign("long", 8),
+            (None, 0) => sign("", 4),
+            _ => bail!("line {line}: invalid type '{}'", words.join(" ")),
+        };
+        Ok(self.base(&name, size, signed))
+    }
+
+    fn float(&mut self, name: &str, size: usize) -> TypeId {
+        if let Some(id) = self.names.get(name) {
+            return *id;
+        }
+        let id = self.push(Decl::Float(name.to_string(), size));
+        self.names.insert(name.to_string(), id);
+        id
+    }
+
+    fn composite(&mut self, union: bool) -> Result<TypeId> {
+        self.pos += 1;
+        let name = self.name();
+        let key = name.as_ref().map(|n| match union {
+            true => format!("union {n}"),
+            false => format!("struct {n}"),
+        });
+
+        if !self.eat('{') {
+            let (Some(name), Some(key)) = (name, key) else {
+                return self.unexpected("a name or '{'");
+            };
+            if let Some(id) = self.names.get(&key) {
+                return Ok(*id);
+            }
+            let id = self.push(Decl::Fwd(name, union));
+            self.names.insert(key, id);
+            return Ok(id);
+        }
+
+        let id = match key.as_ref().and_then(|k| self.names.get(k)) {
+            Some(id) => {
+                let id = *id;
+                if !matches!(self.decl(id), Some(Decl::Fwd(..))) {
+                    bail!("line {}: redefinition of '{}'", self.line(), key.unwrap());
+                }
+                id
+            }
+            None => {
+                let id = self.push(Decl::Fwd(name.clone().unwrap_or_default(), union));
+                if let Some(key) = key {
+                    self.names.insert(key, id);
+                }
+                id
+            }
+        };
+
+        let mut members = Vec::new();
+        while !self.eat('}') {
+            let base = self.type_spec()?;
+            // Anonymous struct or union member.
+            if self.eat(';') {
+                match self.decl(base) {
+                    Some(Decl::Composite { name, .. }) if name.is_empty() => {
+                        members.push((String::new(), base));
+                        continue;
+                    }
+                    _ => return self.unexpected("a member name"),
+                }
+            }
+            loop {
+                let (name, r#type) = self.declarator(base)?;
+                let Some(name) = name else {
+                    return self.unexpected("a member name");
+                };
+                if self.peek() == Some(&Token::Punct(':')) {
+                    bail!("line {}: bitfields are not supported", self.line());
+                }
+                members.push((name, r#type));
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect(';')?;
+        }
+
+        // Sizes and bit offsets must fit in the u32 fields of BTF records.
+        let too_large = || anyhow!("line {}: type too large", self.line());
+        let (mut size, mut align) = (0usize, 1);
+        let mut offsets = Vec::with_capacity(members.len());
+        for (name, r#type) in members {
+            let (msize, malign) = self.layout(r#type)?;
+            let offset = match union {
+                true => 0,
+                false => size
+                    .checked_next_multiple_of(malign)
+                    .ok_or_else(too_large)?,
+            };
+            let bit_offset = u32::try_from(offset)
+                .ok()
+                .and_then(|offset| offset.checked_mul(8))
+                .ok_or_else(too_large)?;
+            offsets.push((name, r#type, bit_offset));
+            size = size.max(offset.checked_add(msize).ok_or_else(too_large)?);
+            align = align.max(malign);
+        }
+        let size = size
+            .checked_next_multiple_of(align)
+            .filter(|size| u32::try_from(*size).is_ok())
+            .ok_or_else(too_large)?;
+
+        self.types[u32::from(id) as usize - 1] = Decl::Composite {
+            union,
+            name: name.unwrap_or_default(),
+            size,
+            align,
+            members: offsets,
+        };
+        Ok(id)
+    }
+
+    fn r#enum(&mut self) -> Result<TypeId> {
+        self.pos += 1;
+        let name = self.name();
+        let key = name.as_ref().map(|n| format!("enum {n}"));
+
+        if !self.eat('{') {
+            let Some(key) = key else {
+                return self.unexpected("a name or '{'");
+            };
+            return self
+                .names
+                .get(&key)
+                .copied()
+                .ok_or_else(|| anyhow!("line {}: unknown type '{key}'", self.line()));
+        }
+        if let Some(key) = key.as_ref().filter(|k| self.names.contains_key(*k)) {
+            bail!("line {}: redefinition of '{key}'", self.line());
+        }
+
+        let mut values = Vec::new();
+        let mut next = 0;
+        while !self.eat('}') {
+            let Some(name) = self.name() else {
+                return self.unexpected("an enumerator name");
+            };
+            if self.eat('=') {
+                next = self.number()?;
+            }
+            if next < i64::MIN as i128 || next > u64::MAX as i128 {
+                bail!("line {}: enum value {next} is out of range", self.line());
+            }
+            values.push((name, next));
+            next += 1;
+            if !self.eat(',') {
+                self.expect('}')?;
+                break;
+            }
+        }
+
+        let id = self.push(Decl::Enum(name.unwrap_or_default(), values));
+        if let Some(key) = key {
+            self.names.insert(key, id);
+        }
+        Ok(id)
+    }
+
+    // Write all the types using a builder.
+    fn emit(&self) -> Result<Vec<u8>> {
+        let mut builder = BtfBuilder::new();
+        for (i, decl) in self.types.iter().enumerate() {
+            let id = match decl {
+                Decl::Int(name, size, signed) => builder.add_int(name, *size, *signed)?,
+                Decl::Float(name, size) => builder.add_float(name, *size)?,
+                Decl::Ptr(r#type) => builder.add_ptr(*r#type)?,
+                Decl::Array(r#type, index, nelems) => {
+                    builder.add_array(*r#type, *index, *nelems)?
+                }
+                Decl::Qualifier(Kind::Const, r#type) => builder.add_const(*r#type)?,
+                Decl::Qualifier(Kind::Volatile, r#type) => builder.add_volatile(*r#type)?,
+                Decl::Qualifier(_, r#type) => builder.add_restrict(*r#type)?,
+                Decl::Fwd(name, union) => builder.add_fwd(name, *union)?,
+                Decl::Composite {
+                    union,
+                    name,
+                    size,
+                    members,
+                    ..
+                } => {
+                    let members: Vec<_> = members
+                        .iter()
+                        .map(|(name, r#type, offset)| (name.as_str(), *r#type, *offset))
+                        .collect();
+                    match union {
+                        true => builder.add_union(name, *size, &members)?,
+                        false => builder.add_struct(name, *size, &members)?,
+                    }
+                }
+                Decl::Enum(name, values) => match enum_size(values) {
+                    4 => {
+                        let values: Vec<_> = values
+                            .iter()
+                            .map(|(name, val)| (name.as_str(), *val as i32))
+                            .collect();
+                        builder.add_enum(name, 4, &values)?
+                    }
+                    _ => {
+                        let values: Vec<_> = values
+                            .iter()
+                            .map(|(name, val)| (name.as_str(), *val as i64))
+                            .collect();
+                        builder.add_enum64(name, 8, &values)?
+                    }
+                },
+                Decl::Typedef(name, r#type) => builder.add_typedef(name, *r#type)?,
+                Decl::FuncProto(ret, params) => {
+                    let params: Vec<_> = params
+                        .iter()
+                        .map(|(name, r#type)| (name.as_str(), *r#type))
+                        .collect();
+                    builder.add_func_proto(*ret, &params)?
+                }
+                Decl::Func(name, proto) => builder.add_func(name, *proto)?,
+                Decl::Var(name, r#type) => builder.add_var(name, *r#type)?,
+            };
+            if id != i as u32 + 1 {
+                bail!("Type {} was assigned id {id}", i + 1);
+            }
+        }
+        builder.to_bytes()
+    }
+}
+
+// Size of an enum given its values: 4 if they all fit in an `int`, 8
+// otherwise.
+fn enum_size(values: &[(String, i128)]) -> usize {
+    match values.iter().all(|(_, val)| i32::try_from(*val).is_ok()) {
+        true => 4,
+        false => 8,
+    }
+}
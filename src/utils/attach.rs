@@ -0,0 +1,99 @@
+//! ### Function attach compatibility checks
+//!
+//! Helpers checking whether a function can be traced using fentry/fexit BPF
+//! programs, based on its BTF description only. Those encode the rules the
+//! kernel verifier applies when distilling a function prototype for BPF
+//! trampolines, so tools can pre-filter attachable functions before trying to
+//! load anything.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::attach::can_fentry};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! assert!(can_fentry(&btf, "kfree_skb_reason"));
+//! ```
+//!
+//! Note that passing those checks does not guarantee a successful attachment
+//! as other restrictions apply at runtime (e.g. functions marked as notrace).
+use anyhow::{anyhow, bail, Result};
+
+use crate::{Btf, Func, Type};
+
+/// Maximum number of arguments a function can have to be traced using fentry
+/// or fexit programs, `MAX_BPF_FUNC_ARGS` in the kernel.
+pub const MAX_ARGS: usize = 12;
+
+/// Maximum size of a struct, or of any other argument, passed by value for a
+/// function to be traced using fentry or fexit programs.
+pub const MAX_STRUCT_BY_VALUE_SIZE: usize = 16;
+
+/// Check a function can be traced using fentry/fexit programs. The same rules
+/// apply to both program types. Returns an error describing the first
+/// incompatibility found.
+pub fn check_fentry(btf: &Btf, func: &Func) -> Result<()> {
+    let proto = match btf.resolve_chained_type(func)? {
+        Type::FuncProto(proto) => proto,
+        x => bail!("Function points to a {} instead of a func-proto", x.name()),
+    };
+
+    if proto.parameters.len() > MAX_ARGS {
+        bail!(
+            "Function has {} arguments, at most {MAX_ARGS} are supported",
+            proto.parameters.len()
+        );
+    }
+
+    for (i, param) in proto.parameters.iter().enumerate() {
+        if param.is_variadic() {
+            bail!("Variadic functions are not supported");
+        }
+        check_arg_type(btf, &btf.resolve_chained_type(param)?)
+            .map_err(|e| anyhow!("Argument {i}: {e}"))?;
+    }
+
+    match btf.resolve_underlying_type(&btf.resolve_type_by_id(proto.return_type_id())?)? {
+        Type::Void => (),
+        Type::Struct(_) | Type::Union(_) => bail!("Returning a struct or union is not supported"),
+        r#type => check_arg_type(btf, &r#type).map_err(|e| anyhow!("Return value: {e}"))?,
+    }
+
+    Ok(())
+}
+
+/// Check a function, given its name, can be traced using fentry/fexit programs.
+/// If multiple functions share the same name, returns true if any of them can
+/// be traced.
+pub fn can_fentry(btf: &Btf, name: &str) -> bool {
+    btf.resolve_types_by_name(name)
+        .unwrap_or_default()
+        .iter()
+        .any(|t| match t {
+            Type::Func(func) => check_fentry(btf, func).is_ok(),
+            _ => false,
+        })
+}
+
+// Check a single argument (or return value) type is supported.
+fn check_arg_type(btf: &Btf, r#type: &Type) -> Result<()> {
+    let r#type = btf.resolve_underlying_type(r#type)?;
+    let size = match &r#type {
+        Type::Ptr(_) => return Ok(()),
+        Type::Int(_) | Type::Enum(_) | Type::Enum64(_) => btf.resolve_type_size(&r#type)?,
+        Type::Struct(s) | Type::Union(s) => {
+            if s.size() > MAX_STRUCT_BY_VALUE_SIZE {
+                bail!(
+                    "{} of size {} passed by value, at most {MAX_STRUCT_BY_VALUE_SIZE} bytes are supported",
+                    r#type.name(),
+                    s.size()
+                );
+            }
+            return Ok(());
+        }
+        x => bail!("Unsupported type {}", x.name()),
+    };
+
+    if size > MAX_STRUCT_BY_VALUE_SIZE {
+        bail!("{} of size {size} is not supported", r#type.name());
+    }
+    Ok(())
+}
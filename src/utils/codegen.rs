@@ -0,0 +1,139 @@
+//! ### Rust code generation
+//!
+//! Helpers generating Rust code from BTF definitions, to be used in build
+//! scripts or to bootstrap code decoding kernel data (e.g. events reporting
+//! kernel enum values).
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::codegen::enum_to_rust};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! println!("{}", enum_to_rust(&btf, "skb_drop_reason").unwrap());
+//! ```
+use std::{collections::HashSet, fmt::Write};
+
+use anyhow::{bail, Result};
+
+use crate::{Btf, Type};
+
+/// Generate a Rust enum definition from a kernel enum (or enum64), given its
+/// name. The generated enum uses a `#[repr(i64)]` representation and comes
+/// with `TryFrom<i64>` implementation.
+///
+/// The name of the Rust enum is the kernel one converted to upper camel case.
+/// Variants names are the enum member ones, without their common prefix (if
+/// that leaves valid identifiers) and converted to upper camel case. Members
+/// sharing a value with a previous one can't be represented and are only
+/// mentioned in a comment.
+pub fn enum_to_rust(btf: &Btf, name: &str) -> Result<String> {
+    let mut values = None;
+    for r#type in btf.resolve_types_by_name(name)? {
+        values = match btf.resolve_underlying_type(&r#type)? {
+            Type::Enum(e) => Some(e.values(btf).collect::<Result<Vec<_>>>()?),
+            Type::Enum64(e) => Some(e.values(btf).collect::<Result<Vec<_>>>()?),
+            _ => continue,
+        };
+        break;
+    }
+    let values = match values {
+        Some(values) => values,
+        None => bail!("No enum named {name}"),
+    };
+
+    let prefix = common_prefix(values.iter().map(|(n, _)| n.as_str()));
+    let variant = |member: &str| camel_case(member.strip_prefix(prefix).unwrap_or(member));
+
+    let enum_name = camel_case(name);
+    let mut seen_values = HashSet::new();
+    let mut seen_names = HashSet::new();
+    let mut variants = Vec::new();
+    let mut out = String::new();
+
+    writeln!(out, "/// Generated from the kernel `enum {name}`.")?;
+    writeln!(out, "#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]")?;
+    writeln!(out, "#[repr(i64)]")?;
+    writeln!(out, "pub enum {enum_name} {{")?;
+    for (member, value) in values.iter() {
+        let variant = variant(member);
+        if !seen_values.insert(*value) || !seen_names.insert(variant.clone()) {
+            writeln!(
+                out,
+                "    // {member} = {value} can't be represented (duplicate)."
+            )?;
+            continue;
+        }
+        writeln!(out, "    {variant} = {value},")?;
+        variants.push((variant, *value));
+    }
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "impl TryFrom<i64> for {enum_name} {{")?;
+    writeln!(out, "    type Error = i64;")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "    fn try_from(value: i64) -> Result<Self, Self::Error> {{"
+    )?;
+    writeln!(out, "        match value {{")?;
+    for (variant, value) in variants.iter() {
+        writeln!(out, "            {value} => Ok({enum_name}::{variant}),")?;
+    }
+    writeln!(out, "            x => Err(x),")?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+
+    Ok(out)
+}
+
+// Find the longest prefix, ending with an underscore, common to all the
+// names. The prefix is only returned if stripping it leaves valid
+// identifiers, and if there is more than a single name.
+fn common_prefix<'a, I: Iterator<Item = &'a str> + Clone>(names: I) -> &'a str {
+    let mut iter = names.clone();
+    let first = match (iter.next(), iter.next()) {
+        (Some(first), Some(_)) => first,
+        _ => return "",
+    };
+
+    let mut len = first.len();
+    for name in names.clone() {
+        len = first
+            .bytes()
+            .zip(name.bytes())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count();
+    }
+    let prefix = match first[..len].rfind('_') {
+        Some(pos) => &first[..=pos],
+        None => return "",
+    };
+
+    let valid = names.map(|n| &n[prefix.len()..]).all(|n| {
+        n.chars().any(|c| c != '_')
+            && n.trim_start_matches('_')
+                .starts_with(|c: char| c.is_ascii_alphabetic())
+    });
+    match valid {
+        true => prefix,
+        false => "",
+    }
+}
+
+// Convert a snake case (lower or upper) name to upper camel case.
+fn camel_case(name: &str) -> String {
+    let mut out = String::new();
+    for part in name.split('_').filter(|p| !p.is_empty()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            out.push(first.to_ascii_uppercase());
+            out.extend(chars.map(|c| c.to_ascii_lowercase()));
+        }
+    }
+    // Make sure the result is a valid identifier.
+    if !out.starts_with(|c: char| c.is_ascii_alphabetic()) || out == "Self" {
+        out.insert(0, '_');
+    }
+    out
+}
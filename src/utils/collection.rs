@@ -14,7 +14,8 @@
 //! btfc.add_split_btf_from_file("/sys/kernel/btf/nf_tables").unwrap();
 //! ```
 //!
-//! [`BtfCollection`] also supports being constructed from byte slices.
+//! [`BtfCollection`] also supports being constructed from byte slices, or from
+//! streams of concatenated BTF objects (base first, followed by split ones).
 //!
 //! Due to how split BTF are constructed, [`BtfCollection`] does not provide
 //! helpers returning a single match but instead return lists of matches
@@ -24,11 +25,17 @@
 //! [`crate::Btf`] representation returned in the [`NamedBtf`] reference. See
 //! [`BtfCollection::resolve_ids_by_name`] and
 //! [`BtfCollection::resolve_types_by_name`].
-use std::{fs, ops::Deref, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 
-use crate::{Btf, Type};
+use crate::{cbtf, Btf, ResolutionOrder, SourceKind, Type, TypeId};
 
 /// BtfCollection provides a full system BTF view, by combining a base BTF
 /// information with multiple split BTFs.
@@ -39,16 +46,190 @@ use crate::{Btf, Type};
 /// resolution was done. This is important as further lookups for the returned
 /// value must be done using the `Btf` object returned.
 ///
-/// The base BTF lookups are prioritized over the split BTF ones.
+/// By default the results found in the split BTFs come first, followed by the
+/// ones found in the base BTF; note this is the opposite of the default of
+/// `Btf` objects. See [`BtfCollection::set_resolution_order`].
+///
+/// As for `Btf` objects, collections are cheap to clone: parsed BTF objects
+/// are shared between clones. Each clone has its own lookup cache, with the
+/// same settings.
+///
+/// Collections are `Send` and `Sync`, lookups being safe to perform
+/// concurrently from multiple threads.
 pub struct BtfCollection {
     /// Main BTF object for the kernel.
     base: NamedBtf,
     /// Split BTF information.
     split: Vec<NamedBtf>,
+    /// Index of the split BTFs in `split`, keyed by their name.
+    split_index: HashMap<String, usize>,
+    /// Order of the results of name lookups.
+    order: ResolutionOrder,
+    /// Cache of the results of recent name lookups.
+    cache: Mutex<LookupCache>,
+    /// Split BTF files skipped as they could not be read, see
+    /// `DirOptions::skip_permission_denied()`.
+    denied: Vec<String>,
+}
+
+// See the equivalent assertions for Btf.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<BtfCollection>();
+    assert_send_sync::<NamedBtf>();
+};
+
+/// Options for `BtfCollection::from_dir_with_options()`.
+#[derive(Clone, Debug, Default)]
+pub struct DirOptions {
+    skip_denied: bool,
+}
+
+impl DirOptions {
+    /// Construct the default set of options.
+    pub fn new() -> DirOptions {
+        DirOptions::default()
+    }
+
+    /// Skip the split BTF files which can't be read due to missing
+    /// permissions instead of failing, as found on some locked-down systems.
+    /// The base BTF file must always be readable. Skipped files can be
+    /// retrieved using `BtfCollection::denied_split_btfs()`. Defaults to
+    /// false.
+    pub fn skip_permission_denied(mut self, skip: bool) -> DirOptions {
+        self.skip_denied = skip;
+        self
+    }
+}
+
+/// Default number of name lookups cached by a [`BtfCollection`].
+pub const DEFAULT_LOOKUP_CACHE_CAPACITY: usize = 64;
+
+/// Statistics of the name lookup cache of a [`BtfCollection`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LookupCacheStats {
+    /// Number of lookups served from the cache.
+    pub hits: u64,
+    /// Number of lookups for names known not to exist, served from the
+    /// negative cache.
+    pub negative_hits: u64,
+    /// Number of lookups not found in the cache.
+    pub misses: u64,
+    /// Number of entries currently in the cache.
+    pub len: usize,
+    /// Number of entries currently in the negative cache.
+    pub negative_len: usize,
+}
+
+// Location of a match found during a name lookup: the index of the split BTF
+// (or None for the base one) and the id.
+type CachedMatch = (Option<usize>, TypeId);
+
+// LRU cache keyed by names. Entries are indexed by the tick of their last use
+// too, so the least recently used one is found without scanning all entries.
+struct Lru<V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (u64, V)>,
+    order: BTreeMap<u64, String>,
+}
+
+impl<V> Lru<V> {
+    fn new(capacity: usize) -> Lru<V> {
+        Lru {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, name: &str) -> Option<&V> {
+        let (tick, val) = self.entries.get_mut(name)?;
+        self.tick += 1;
+        if let Some(name) = self.order.remove(tick) {
+            self.order.insert(self.tick, name);
+        }
+        *tick = self.tick;
+        Some(val)
+    }
+
+    fn insert(&mut self, name: &str, val: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        match self.entries.remove(name) {
+            Some((tick, _)) => _ = self.order.remove(&tick),
+            None if self.entries.len() >= self.capacity => {
+                if let Some((_, lru)) = self.order.pop_first() {
+                    self.entries.remove(&lru);
+                }
+            }
+            None => (),
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, name.to_string());
+        self.entries.insert(name.to_string(), (self.tick, val));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+// Caches of name lookup results, for names with matches and for names
+// without.
+struct LookupCache {
+    matches: Lru<Vec<CachedMatch>>,
+    misses: Lru<()>,
+    stats: LookupCacheStats,
+}
+
+impl LookupCache {
+    fn new(capacity: usize, negative_capacity: usize) -> LookupCache {
+        LookupCache {
+            matches: Lru::new(capacity),
+            misses: Lru::new(negative_capacity),
+            stats: LookupCacheStats::default(),
+        }
+    }
+
+    fn get(&mut self, name: &str) -> Option<Vec<CachedMatch>> {
+        if self.matches.capacity == 0 && self.misses.capacity == 0 {
+            return None;
+        }
+
+        if let Some(matches) = self.matches.get(name) {
+            self.stats.hits += 1;
+            return Some(matches.clone());
+        }
+        if self.misses.get(name).is_some() {
+            self.stats.negative_hits += 1;
+            return Some(Vec::new());
+        }
+
+        self.stats.misses += 1;
+        None
+    }
+
+    fn insert(&mut self, name: &str, matches: Vec<CachedMatch>) {
+        match matches.is_empty() {
+            true => self.misses.insert(name, ()),
+            false => self.matches.insert(name, matches),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.matches.clear();
+        self.misses.clear();
+    }
 }
 
 /// Struct embedding a Btf object alongside a name to uniquely identify it. Used
 /// to manipulate Btf objects when there could be multiple matches.
+#[derive(Clone)]
 pub struct NamedBtf {
     /// Name of the BtfObject.
     pub name: String,
@@ -56,6 +237,57 @@ pub struct NamedBtf {
     pub btf: Btf,
 }
 
+/// Type found by a [`BtfCollection`] lookup.
+#[derive(Clone)]
+pub struct Match<'a> {
+    /// The `NamedBtf` the type was found in. Further lookups must be done
+    /// using it.
+    pub btf: &'a NamedBtf,
+    /// Id of the type in `btf`.
+    pub id: TypeId,
+    /// The type.
+    pub r#type: Type,
+}
+
+/// Type id found by a [`BtfCollection`] id lookup. Unlike [`Match`], the type
+/// itself is not resolved.
+#[derive(Clone, Copy)]
+pub struct IdMatch<'a> {
+    /// The `NamedBtf` the id was found in. Further lookups must be done using
+    /// it.
+    pub btf: &'a NamedBtf,
+    /// Id of the type in `btf`.
+    pub id: TypeId,
+}
+
+impl<'a> IdMatch<'a> {
+    /// Resolve the matching type.
+    pub fn resolve(&self) -> Result<Match<'a>> {
+        Ok(Match {
+            btf: self.btf,
+            id: self.id,
+            r#type: self.btf.resolve_type_by_id(self.id)?,
+        })
+    }
+}
+
+impl Clone for BtfCollection {
+    fn clone(&self) -> BtfCollection {
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        BtfCollection {
+            base: self.base.clone(),
+            split: self.split.clone(),
+            split_index: self.split_index.clone(),
+            order: self.order,
+            cache: Mutex::new(LookupCache::new(
+                cache.matches.capacity,
+                cache.misses.capacity,
+            )),
+            denied: self.denied.clone(),
+        }
+    }
+}
+
 /// Let dereference NamedBtf into Btf directly for ease of use.
 impl Deref for NamedBtf {
     type Target = Btf;
@@ -68,63 +300,225 @@ impl Deref for NamedBtf {
 impl BtfCollection {
     /// Construct a BtfCollection object from a base BTF file only.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<BtfCollection> {
-        Ok(BtfCollection {
-            base: NamedBtf {
-                name: Self::file_name(path.as_ref())?,
-                btf: Btf::from_file(path)?,
-            },
-            split: Vec::new(),
-        })
+        Ok(Self::from_btf(
+            &Self::file_name(path.as_ref())?,
+            Btf::from_file(path)?,
+        ))
     }
 
     /// Construct a BtfCollection object from a base BTF file only.
     pub fn from_bytes(name: &str, bytes: &[u8]) -> Result<BtfCollection> {
-        Ok(BtfCollection {
-            base: NamedBtf {
-                name: name.to_string(),
-                btf: Btf::from_bytes(bytes)?,
-            },
-            split: Vec::new(),
-        })
+        Ok(Self::from_btf(name, Btf::from_bytes(bytes)?))
     }
 
     /// Add a split BTF in the current BtfCollection representation, reading a file.
     pub fn add_split_btf_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
         let name = Self::file_name(path.as_ref())?;
+        self.check_split_name(&name)?;
 
-        if self.split.iter().any(|m| m.name == name) {
-            bail!("Split BTF with name {name} already present");
-        }
-
-        self.split.push(NamedBtf {
-            name,
-            btf: Btf::from_split_file(path, &self.base.btf)?,
-        });
-        Ok(self)
+        let btf = Btf::from_split_file(path, &self.base.btf)?;
+        self.add_split_btf(&name, btf)
     }
 
     /// Add a split BTF in the current BtfCollection representation, reading a byte slice.
     pub fn add_split_btf_from_bytes(&mut self, name: &str, bytes: &[u8]) -> Result<&mut Self> {
-        let name = name.to_string();
-        if self.split.iter().any(|m| m.name == name) {
-            bail!("Split BTF with name {name} already present");
+        self.check_split_name(name)?;
+
+        let btf = Btf::from_split_bytes(bytes, &self.base.btf)?;
+        self.add_split_btf(name, btf)
+    }
+
+    /// Replace a split BTF already part of the current BtfCollection, reading
+    /// a file. This is useful to refresh the definitions of a module which was
+    /// reloaded. Fails if no split BTF has the same name.
+    pub fn replace_split_btf_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        let name = Self::file_name(path.as_ref())?;
+        let btf = Btf::from_split_file(path, &self.base.btf)?;
+        self.replace_split_btf(&name, btf)
+    }
+
+    /// Replace a split BTF already part of the current BtfCollection, reading a
+    /// byte slice. Fails if no split BTF has the same name.
+    pub fn replace_split_btf_from_bytes(&mut self, name: &str, bytes: &[u8]) -> Result<&mut Self> {
+        let btf = Btf::from_split_bytes(bytes, &self.base.btf)?;
+        self.replace_split_btf(name, btf)
+    }
+
+    /// Add a split BTF in the current BtfCollection, or replace it if one with
+    /// the same name is already present, reading a file.
+    pub fn upsert_split_btf_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        let name = Self::file_name(path.as_ref())?;
+        let btf = Btf::from_split_file(path, &self.base.btf)?;
+        self.upsert_split_btf(&name, btf)
+    }
+
+    /// Add a split BTF in the current BtfCollection, or replace it if one with
+    /// the same name is already present, reading a byte slice.
+    pub fn upsert_split_btf_from_bytes(&mut self, name: &str, bytes: &[u8]) -> Result<&mut Self> {
+        let btf = Btf::from_split_bytes(bytes, &self.base.btf)?;
+        self.upsert_split_btf(name, btf)
+    }
+
+    /// Construct a BtfCollection object from a byte slice containing multiple
+    /// concatenated BTF objects: a base BTF followed by 0 or more split BTFs
+    /// built on top of it. The `name` hook is called for each object with its
+    /// index in the stream (the base one being 0) and returns the name it will
+    /// have in the collection.
+    pub fn from_concatenated_bytes<F>(bytes: &[u8], name: F) -> Result<BtfCollection>
+    where
+        F: FnMut(usize) -> String,
+    {
+        Self::from_concatenated(bytes, name, SourceKind::Bytes)
+    }
+
+    /// Construct a BtfCollection object from a file containing multiple
+    /// concatenated BTF objects. See
+    /// [`BtfCollection::from_concatenated_bytes`].
+    pub fn from_concatenated_file<P, F>(path: P, name: F) -> Result<BtfCollection>
+    where
+        P: AsRef<Path>,
+        F: FnMut(usize) -> String,
+    {
+        Self::from_concatenated(&fs::read(path)?, name, SourceKind::File)
+    }
+
+    // Split a stream of concatenated BTF objects and construct a BtfCollection
+    // out of it.
+    fn from_concatenated<F>(bytes: &[u8], mut name: F, source: SourceKind) -> Result<BtfCollection>
+    where
+        F: FnMut(usize) -> String,
+    {
+        let mut btfc: Option<BtfCollection> = None;
+        let mut offset = 0;
+        let mut index = 0;
+
+        while offset < bytes.len() {
+            let (header, _) = cbtf::btf_header::from_reader(&mut &bytes[offset..])
+                .map_err(|e| anyhow!("Invalid BTF object at offset {offset}: {e}"))?;
+            let end = offset as u64 + header.total_len();
+            if end > bytes.len() as u64 {
+                bail!("Truncated BTF object at offset {offset}");
+            }
+            let end = end as usize;
+
+            let segment = &bytes[offset..end];
+            btfc = Some(match btfc {
+                None => Self::from_btf(&name(index), Btf::from_bytes(segment)?.with_source(source)),
+                Some(mut btfc) => {
+                    let btf = Btf::from_split_bytes(segment, &btfc.base.btf)?.with_source(source);
+                    btfc.add_split_btf(&name(index), btf)?;
+                    btfc
+                }
+            });
+
+            offset = end;
+            index += 1;
         }
 
+        btfc.ok_or_else(|| anyhow!("No BTF object found"))
+    }
+
+    // Construct a BtfCollection object from an already parsed base BTF.
+    pub(crate) fn from_btf(name: &str, btf: Btf) -> BtfCollection {
+        let btf = btf.with_source_name(name);
+        BtfCollection {
+            base: NamedBtf {
+                name: name.to_string(),
+                btf,
+            },
+            split: Vec::new(),
+            split_index: HashMap::new(),
+            order: ResolutionOrder::SplitFirst,
+            cache: Mutex::new(LookupCache::new(DEFAULT_LOOKUP_CACHE_CAPACITY, 0)),
+            denied: Vec::new(),
+        }
+    }
+
+    // Add an already parsed split BTF, which must have been built on top of
+    // the collection base BTF, in the current BtfCollection representation.
+    pub(crate) fn add_split_btf(&mut self, name: &str, btf: Btf) -> Result<&mut Self> {
+        self.check_split_name(name)?;
+
+        let btf = btf.with_source_name(name);
+        self.split_index.insert(name.to_string(), self.split.len());
         self.split.push(NamedBtf {
-            name,
-            btf: Btf::from_split_bytes(bytes, &self.base.btf)?,
+            name: name.to_string(),
+            btf,
         });
+        // Cached lookups might now be missing matches.
+        self.cache_mut().clear();
         Ok(self)
     }
 
+    // Replace an already parsed split BTF, which must have been built on top of
+    // the collection base BTF, keeping its position in the collection.
+    fn replace_split_btf(&mut self, name: &str, btf: Btf) -> Result<&mut Self> {
+        let split = match self.split_index.get(name) {
+            Some(i) => &mut self.split[*i],
+            None => bail!("No split BTF with name {name}"),
+        };
+
+        let btf = btf.with_source_name(name);
+        split.btf = btf;
+        // Cached lookups might now be stale.
+        self.cache_mut().clear();
+        Ok(self)
+    }
+
+    // Add or replace an already parsed split BTF.
+    fn upsert_split_btf(&mut self, name: &str, btf: Btf) -> Result<&mut Self> {
+        match self.split_index.contains_key(name) {
+            true => self.replace_split_btf(name, btf),
+            false => self.add_split_btf(name, btf),
+        }
+    }
+
+    // Check a split BTF name is not already used in the collection.
+    fn check_split_name(&self, name: &str) -> Result<()> {
+        if self.split_index.contains_key(name) {
+            bail!("Split BTF with name {name} already present");
+        }
+        Ok(())
+    }
+
     /// Parse BTF objects stored in a directory and construct a BtfCollection
     /// object, given a path to the directory and the filename of the base BTF file.
     /// This is helpful for parsing /sys/kernel/btf for example.
     pub fn from_dir<P: AsRef<Path>>(dir: P, base: &str) -> Result<BtfCollection> {
+        Self::from_dir_with_options(dir, base, &DirOptions::default())
+    }
+
+    /// Same as `from_dir()` but with options, see [`DirOptions`].
+    pub fn from_dir_with_options<P: AsRef<Path>>(
+        dir: P,
+        base: &str,
+        options: &DirOptions,
+    ) -> Result<BtfCollection> {
+        let mut loader = Self::dir_loader(dir, base)?;
+        let mut denied = Vec::new();
+        for res in loader.by_ref() {
+            match res {
+                Err((name, e)) if options.skip_denied && is_permission_denied(&e) => {
+                    denied.push(name)
+                }
+                Err((_, e)) => return Err(e),
+                Ok(_) => (),
+            }
+        }
+
+        let mut btfc = loader.into_collection();
+        btfc.denied = denied;
+        Ok(btfc)
+    }
+
+    /// Same as `from_dir()` but returns a [`DirLoader`], parsing the split BTF
+    /// files one at a time. Only the base BTF file is parsed by this function.
+    pub fn dir_loader<P: AsRef<Path>>(dir: P, base: &str) -> Result<DirLoader> {
         // First parse the base BTF information.
-        let mut sys_btf = BtfCollection::from_file(format!("{}/{base}", dir.as_ref().display()))?;
+        let btfc = BtfCollection::from_file(format!("{}/{base}", dir.as_ref().display()))?;
 
-        // Then loop over all split BTF files and parse them.
+        // Then list all split BTF files, which will be parsed on demand.
+        let mut files = Vec::new();
         for file in fs::read_dir(dir.as_ref())? {
             match file {
                 Ok(file) => {
@@ -133,77 +527,170 @@ impl BtfCollection {
                     }
                     if let Ok(ft) = file.file_type() {
                         if !ft.is_dir() {
-                            sys_btf.add_split_btf_from_file(file.path())?;
+                            files.push(file.path());
                         }
                     }
                 }
                 Err(e) => bail!("Error reading file from {}: {e}", dir.as_ref().display()),
             }
         }
+        files.sort();
+
+        Ok(DirLoader {
+            btfc,
+            files: files.into_iter(),
+        })
+    }
+
+    /// Get the names of the split BTF files skipped when constructing the
+    /// collection, as they could not be read. See
+    /// `DirOptions::skip_permission_denied()`.
+    pub fn denied_split_btfs(&self) -> &[String] {
+        &self.denied
+    }
 
-        Ok(sys_btf)
+    /// Get a reference to the `NamedBtf` of the base BTF object.
+    pub fn base(&self) -> &NamedBtf {
+        &self.base
+    }
+
+    /// Get the name of the base BTF object.
+    pub fn base_name(&self) -> &str {
+        &self.base.name
     }
 
     /// Get a reference to a `NamedBtf` given a module name. This `NamedBtf` can
     /// then be used to perform scoped lookups.
     pub fn get_named_btf(&self, name: &str) -> Option<&NamedBtf> {
-        self.split.iter().find(|m| m.name == name)
+        self.split_index.get(name).map(|i| &self.split[*i])
+    }
+
+    /// Iterate over the split BTF objects of the collection, in the order they
+    /// were added.
+    pub fn split_btfs(&self) -> impl Iterator<Item = &NamedBtf> {
+        self.split.iter()
     }
 
     /// Find a list of BTF ids using their name as a key. Matching ids can be
     /// found in multiple underlying BTF, thus this function returns a list of
-    /// tuples containing each a reference to `NamedBtf` (representing the BTF
-    /// where a match was found) and the id. Further lookups must be done using
-    /// the `Btf` object contained in the linked `NamedBtf` one.
-    pub fn resolve_ids_by_name(&self, name: &str) -> Result<Vec<(&NamedBtf, u32)>> {
-        let mut ids = Vec::new();
-        let mut base_ids = self.base.btf.resolve_ids_by_name(name).unwrap_or_default();
-
-        for split in self.split.iter() {
-            if let Ok(mut mod_ids) = split.btf.resolve_split_ids_by_name(name) {
-                mod_ids.drain(..).for_each(|i| ids.push((split, i)));
+    /// [`IdMatch`] containing each a reference to `NamedBtf` (representing the
+    /// BTF where a match was found) and the id. Further lookups must be done
+    /// using the `Btf` object contained in the linked `NamedBtf` one.
+    ///
+    /// The results of recent lookups are cached, see
+    /// [`BtfCollection::set_lookup_cache_capacity`].
+    pub fn resolve_ids_by_name(&self, name: &str) -> Result<Vec<IdMatch<'_>>> {
+        let cached = self.cache().as_mut().and_then(|c| c.get(name));
+        let matches = match cached {
+            Some(matches) => matches,
+            None => {
+                let matches = self.lookup_ids_by_name(name);
+                if let Some(cache) = self.cache().as_mut() {
+                    cache.insert(name, matches.clone());
+                }
+                matches
             }
-        }
-
-        // Now add ids found in the base BTF.
-        base_ids.drain(..).for_each(|i| ids.push((&self.base, i)));
+        };
 
-        if ids.is_empty() {
+        if matches.is_empty() {
             bail!("No id linked to name {name}");
         }
 
-        Ok(ids)
+        Ok(matches
+            .into_iter()
+            .map(|(split, id)| IdMatch {
+                btf: match split {
+                    Some(i) => &self.split[i],
+                    None => &self.base,
+                },
+                id,
+            })
+            .collect())
     }
 
     /// Find a list of BTF types using their name as a key. Matching types can
     /// be found in multiple underlying BTF, thus this function returns a list
-    /// of tuples containing each a reference to `NamedBtf` (representing the
-    /// BTF where a match was found) and the type. Further lookups must be done
-    /// using the `Btf` object contained in the linked `NamedBtf` one.
-    pub fn resolve_types_by_name(&self, name: &str) -> Result<Vec<(&NamedBtf, Type)>> {
-        let mut types = Vec::new();
-        let mut base_types = self
-            .base
-            .btf
-            .resolve_types_by_name(name)
-            .unwrap_or_default();
+    /// of [`Match`] containing each a reference to `NamedBtf` (representing
+    /// the BTF where a match was found), the id and the type. Further lookups
+    /// must be done using the `Btf` object contained in the linked `NamedBtf`
+    /// one.
+    pub fn resolve_types_by_name(&self, name: &str) -> Result<Vec<Match<'_>>> {
+        self.resolve_ids_by_name(name)
+            .map_err(|_| anyhow!("No type linked to name {name}"))?
+            .iter()
+            .map(IdMatch::resolve)
+            .collect()
+    }
 
-        for split in self.split.iter() {
-            if let Ok(mut mod_types) = split.btf.resolve_split_types_by_name(name) {
-                mod_types.drain(..).for_each(|t| types.push((split, t)));
-            }
+    /// Set the order of the results of name lookups. Defaults to
+    /// [`ResolutionOrder::SplitFirst`].
+    pub fn set_resolution_order(&mut self, order: ResolutionOrder) {
+        self.order = order;
+        // Cached lookups follow the previous order.
+        self.cache_mut().clear();
+    }
+
+    /// Set the maximum number of name lookups whose results are cached. Setting
+    /// it to 0 disables the cache. Defaults to
+    /// [`DEFAULT_LOOKUP_CACHE_CAPACITY`].
+    pub fn set_lookup_cache_capacity(&mut self, capacity: usize) {
+        let cache = self.cache_mut();
+        *cache = LookupCache::new(capacity, cache.misses.capacity);
+    }
+
+    /// Set the maximum number of names without any match which are cached, so
+    /// repeated lookups for names not existing are cheap. Setting it to 0
+    /// disables the negative cache, which is the default.
+    pub fn set_negative_lookup_cache_capacity(&mut self, capacity: usize) {
+        let cache = self.cache_mut();
+        *cache = LookupCache::new(cache.matches.capacity, capacity);
+    }
+
+    /// Get statistics about the name lookup cache.
+    pub fn lookup_cache_stats(&self) -> LookupCacheStats {
+        match self.cache() {
+            Some(cache) => LookupCacheStats {
+                len: cache.matches.entries.len(),
+                negative_len: cache.misses.entries.len(),
+                ..cache.stats
+            },
+            None => LookupCacheStats::default(),
         }
+    }
 
-        // Now add types found in the base BTF.
-        base_types
-            .drain(..)
-            .for_each(|t| types.push((&self.base, t)));
+    // Look for ids matching a name in all the underlying BTF objects,
+    // following the resolution order.
+    fn lookup_ids_by_name(&self, name: &str) -> Vec<CachedMatch> {
+        let mut ids = Vec::new();
 
-        if types.is_empty() {
-            bail!("No type linked to name {name}");
+        if self.order == ResolutionOrder::BaseFirst {
+            ids.extend(self.base.btf.ids_for_exact_name(name).map(|id| (None, id)));
         }
 
-        Ok(types)
+        for (i, split) in self.split.iter().enumerate() {
+            split
+                .btf
+                .split_ids_by_name(name)
+                .iter()
+                .for_each(|id| ids.push((Some(i), TypeId::from(*id))));
+        }
+
+        if self.order == ResolutionOrder::SplitFirst {
+            ids.extend(self.base.btf.ids_for_exact_name(name).map(|id| (None, id)));
+        }
+
+        ids
+    }
+
+    // Get the lookup cache. Returns None if the lock was poisoned, in which
+    // case lookups are performed without using the cache.
+    fn cache(&self) -> Option<MutexGuard<'_, LookupCache>> {
+        self.cache.lock().ok()
+    }
+
+    // Get a mutable reference to the lookup cache.
+    fn cache_mut(&mut self) -> &mut LookupCache {
+        self.cache.get_mut().unwrap_or_else(|e| e.into_inner())
     }
 
     // Internal helper to extract a file name as a String from a Path.
@@ -217,3 +704,70 @@ impl BtfCollection {
         })
     }
 }
+
+/// Loader parsing the split BTF files of a directory one at a time, in file
+/// name order, and adding them to a [`BtfCollection`]. It is an iterator
+/// yielding the result of each file, allowing to report progress and to
+/// skip invalid files.
+///
+/// ```no_run
+/// use btf_rs::utils::collection::BtfCollection;
+///
+/// let mut loader = BtfCollection::dir_loader("/sys/kernel/btf", "vmlinux").unwrap();
+/// for res in loader.by_ref() {
+///     match res {
+///         Ok(name) => println!("loaded {name}"),
+///         Err((name, e)) => eprintln!("skipped {name}: {e}"),
+///     }
+/// }
+/// let btfc = loader.into_collection();
+/// ```
+pub struct DirLoader {
+    btfc: BtfCollection,
+    files: std::vec::IntoIter<PathBuf>,
+}
+
+impl DirLoader {
+    /// Get a reference to the collection being built.
+    pub fn collection(&self) -> &BtfCollection {
+        &self.btfc
+    }
+
+    /// Get the collection, holding the files successfully parsed so far.
+    pub fn into_collection(self) -> BtfCollection {
+        self.btfc
+    }
+
+    /// Number of files left to be parsed.
+    pub fn remaining(&self) -> usize {
+        self.files.len()
+    }
+}
+
+impl Iterator for DirLoader {
+    /// Name of the split BTF, or its name and the error encountered while
+    /// parsing it.
+    type Item = std::result::Result<String, (String, anyhow::Error)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = self.files.next()?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Some(match self.btfc.add_split_btf_from_file(&path) {
+            Ok(_) => Ok(name),
+            Err(e) => Err((name, e)),
+        })
+    }
+}
+
+// Was an error caused by missing permissions?
+fn is_permission_denied(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::PermissionDenied)
+    })
+}
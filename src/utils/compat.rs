@@ -0,0 +1,258 @@
+//! ### Type compatibility between BTF objects
+//!
+//! Programs compiled against a set of type definitions (e.g. an eBPF object
+//! and its own BTF) expect those to match the ones they are run against
+//! (e.g. the running kernel). [`check`] compares two types, possibly coming
+//! from different [`Btf`] objects, and lists their layout differences: kinds,
+//! sizes, member names and offsets, recursively.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::compat::check};
+//!
+//! let prog = Btf::from_file("prog.btf").unwrap();
+//! let kernel = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//!
+//! let a = prog.resolve_types_by_name("sk_buff").unwrap().pop().unwrap();
+//! let b = kernel.resolve_types_by_name("sk_buff").unwrap().pop().unwrap();
+//! for diff in check(&prog, &a, &kernel, &b).unwrap() {
+//!     eprintln!("{diff}");
+//! }
+//! ```
+//!
+//! Typedefs and qualifiers are skipped and pointers are compatible regardless
+//! of the type they point to. Type names are not compared, only member names.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use anyhow::Result;
+
+use crate::{Btf, Kind, Struct, Type};
+
+/// Difference between two types, see [`check`]. Paths are made of the name
+/// of the root type followed by member names, e.g. `sk_buff.len`; array
+/// elements are noted `[]`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Difference {
+    /// The kinds of the types differ.
+    Kind { path: String, a: Kind, b: Kind },
+    /// The sizes, in bytes, of the types differ.
+    Size { path: String, a: usize, b: usize },
+    /// Integer signedness differs.
+    Signedness { path: String, a: bool, b: bool },
+    /// The number of elements of arrays differ.
+    ArrayLen { path: String, a: usize, b: usize },
+    /// A member is only part of the first type.
+    OnlyInA(String),
+    /// A member is only part of the second type.
+    OnlyInB(String),
+    /// The offsets, in bits, of a member differ.
+    Offset { path: String, a: u32, b: u32 },
+    /// The bitfield sizes of a member differ, `None` for regular members.
+    Bitfield {
+        path: String,
+        a: Option<u32>,
+        b: Option<u32>,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Kind { path, a, b } => {
+                write!(f, "{path} is a {} vs a {}", a.name(), b.name())
+            }
+            Difference::Size { path, a, b } => write!(f, "{path} is {a} vs {b} bytes"),
+            Difference::Signedness { path, a, b } => {
+                let sign = |s: &bool| if *s { "signed" } else { "unsigned" };
+                write!(f, "{path} is {} vs {}", sign(a), sign(b))
+            }
+            Difference::ArrayLen { path, a, b } => {
+                write!(f, "{path} has {a} vs {b} elements")
+            }
+            Difference::OnlyInA(path) => write!(f, "{path} only found in the first type"),
+            Difference::OnlyInB(path) => write!(f, "{path} only found in the second type"),
+            Difference::Offset { path, a, b } => {
+                write!(f, "{path} is at bit offset {a} vs {b}")
+            }
+            Difference::Bitfield { path, a, b } => {
+                let size = |s: &Option<u32>| match s {
+                    Some(s) => format!("a {s}-bit bitfield"),
+                    None => "not a bitfield".to_string(),
+                };
+                write!(f, "{path} is {} vs {}", size(a), size(b))
+            }
+        }
+    }
+}
+
+/// Check two types, `type_a` defined in `btf_a` and `type_b` defined in
+/// `btf_b`, are compatible. Returns the list of differences found, empty if
+/// the types are compatible. Fails if one of the types definition can't be
+/// resolved.
+pub fn check(btf_a: &Btf, type_a: &Type, btf_b: &Btf, type_b: &Type) -> Result<Vec<Difference>> {
+    let path = match btf_a.resolve_name_ref(type_a) {
+        Ok(name) if !name.is_empty() => name.to_string(),
+        _ => "<root>".to_string(),
+    };
+
+    let mut diffs = Vec::new();
+    Checker { btf_a, btf_b }.compare(&path, type_a, type_b, &mut diffs)?;
+    Ok(diffs)
+}
+
+struct Checker<'a> {
+    btf_a: &'a Btf,
+    btf_b: &'a Btf,
+}
+
+impl Checker<'_> {
+    fn compare(&self, path: &str, a: &Type, b: &Type, diffs: &mut Vec<Difference>) -> Result<()> {
+        let a = self.btf_a.resolve_underlying_type(a)?;
+        let b = self.btf_b.resolve_underlying_type(b)?;
+
+        if a.kind() != b.kind() {
+            diffs.push(Difference::Kind {
+                path: path.to_string(),
+                a: a.kind(),
+                b: b.kind(),
+            });
+            return Ok(());
+        }
+
+        let size = |sa: usize, sb: usize, diffs: &mut Vec<Difference>| {
+            if sa != sb {
+                diffs.push(Difference::Size {
+                    path: path.to_string(),
+                    a: sa,
+                    b: sb,
+                });
+            }
+        };
+
+        match (&a, &b) {
+            (Type::Int(ia), Type::Int(ib)) => {
+                size(ia.size(), ib.size(), diffs);
+                if ia.is_signed() != ib.is_signed() {
+                    diffs.push(Difference::Signedness {
+                        path: path.to_string(),
+                        a: ia.is_signed(),
+                        b: ib.is_signed(),
+                    });
+                }
+            }
+            (Type::Enum(ea), Type::Enum(eb)) => size(ea.size(), eb.size(), diffs),
+            (Type::Enum64(ea), Type::Enum64(eb)) => size(ea.size(), eb.size(), diffs),
+            (Type::Float(fa), Type::Float(fb)) => size(fa.size(), fb.size(), diffs),
+            (Type::Array(aa), Type::Array(ab)) => {
+                if aa.len() != ab.len() {
+                    diffs.push(Difference::ArrayLen {
+                        path: path.to_string(),
+                        a: aa.len(),
+                        b: ab.len(),
+                    });
+                }
+                self.compare(
+                    &format!("{path}[]"),
+                    &self.btf_a.resolve_chained_type(aa)?,
+                    &self.btf_b.resolve_chained_type(ab)?,
+                    diffs,
+                )?;
+            }
+            (Type::Struct(sa), Type::Struct(sb)) | (Type::Union(sa), Type::Union(sb)) => {
+                size(sa.size(), sb.size(), diffs);
+                self.compare_members(path, sa, sb, diffs)?;
+            }
+            // Pointers are compatible whatever they point to, other kinds
+            // have no layout.
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn compare_members(
+        &self,
+        path: &str,
+        a: &Struct,
+        b: &Struct,
+        diffs: &mut Vec<Difference>,
+    ) -> Result<()> {
+        let members_a = members(self.btf_a, a, 0)?;
+        let members_b = members(self.btf_b, b, 0)?;
+        let index_b: HashMap<&str, usize> = members_b
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (m.name.as_str(), i))
+            .collect();
+
+        for ma in members_a.iter() {
+            let member_path = format!("{path}.{}", ma.name);
+            let Some(mb) = index_b.get(ma.name.as_str()).map(|i| &members_b[*i]) else {
+                diffs.push(Difference::OnlyInA(member_path));
+                continue;
+            };
+
+            if ma.bit_offset != mb.bit_offset {
+                diffs.push(Difference::Offset {
+                    path: member_path.clone(),
+                    a: ma.bit_offset,
+                    b: mb.bit_offset,
+                });
+            }
+            if ma.bitfield_size != mb.bitfield_size {
+                diffs.push(Difference::Bitfield {
+                    path: member_path.clone(),
+                    a: ma.bitfield_size,
+                    b: mb.bitfield_size,
+                });
+            }
+            self.compare(&member_path, &ma.r#type, &mb.r#type, diffs)?;
+        }
+
+        let names_a: HashSet<&str> = members_a.iter().map(|m| m.name.as_str()).collect();
+        for mb in members_b.iter() {
+            if !names_a.contains(&mb.name.as_str()) {
+                diffs.push(Difference::OnlyInB(format!("{path}.{}", mb.name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Named member of a struct or union, anonymous ones being inlined.
+struct NamedMember {
+    name: String,
+    bit_offset: u32,
+    bitfield_size: Option<u32>,
+    r#type: Type,
+}
+
+// Get the named members of a struct or union, inlining the members of
+// anonymous struct and union members. Offsets are relative to the outermost
+// struct, `base` being the offset in bits of the current one.
+fn members(btf: &Btf, r#struct: &Struct, base: u32) -> Result<Vec<NamedMember>> {
+    let mut named = Vec::with_capacity(r#struct.members.len());
+    for member in r#struct.members.iter() {
+        let r#type = btf.resolve_chained_type(member)?;
+        let bit_offset = base + member.bit_offset();
+        let name = btf.resolve_name(member)?;
+
+        if name.is_empty() {
+            if let Type::Struct(inner) | Type::Union(inner) =
+                btf.resolve_underlying_type(&r#type)?
+            {
+                named.extend(members(btf, &inner, bit_offset)?);
+                continue;
+            }
+        }
+
+        named.push(NamedMember {
+            name,
+            bit_offset,
+            bitfield_size: member.bitfield_size().filter(|size| *size != 0),
+            r#type,
+        });
+    }
+    Ok(named)
+}
@@ -0,0 +1,116 @@
+//! ### Debugging raw type records
+//!
+//! [`dump_record`] prints the raw bytes of a type record alongside its decoded
+//! header fields and its interpretation (in the format of
+//! [`dump`](crate::utils::dump)), which helps reporting parser issues on
+//! exotic kernels.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::debug::dump_record};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! let id = btf.resolve_ids_by_name("sk_buff").unwrap()[0];
+//! dump_record(&btf, id, &mut std::io::stdout()).unwrap();
+//! ```
+//!
+//! Which prints something like:
+//!
+//! ```text
+//! [4984] STRUCT 'sk_buff' size=232 vlen=28
+//!   header 0000: fe f0 01 00 1c 00 00 84 e8 00 00 00  name_off=127230 info=0x8400001c (kind=4 kind_flag=1 vlen=28) size_type=232
+//!   0      000c: 00 00 00 00 a0 14 00 00 00 00 00 00  '(anon)' type_id=5280 bits_offset=0
+//!   1      0018: 00 00 00 00 a1 14 00 00 c0 00 00 00  '(anon)' type_id=5281 bits_offset=192
+//!   ...
+//! ```
+//!
+//! By default records are the parsed types encoded back. Objects parsed with
+//! `BtfOptions::keep_raw_records()` keep the original bytes of their type
+//! section, which are then dumped as-is.
+use std::io::Write;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{cbtf, utils::dump::dump_type, utils::raw::CBtfType, Btf, Type, TypeId};
+
+/// Dump the raw record of a type, given its id. The raw bytes of its header,
+/// kind specific data and elements (e.g. struct members) are printed next to
+/// their interpretation.
+pub fn dump_record<I, W>(btf: &Btf, id: I, writer: &mut W) -> Result<()>
+where
+    I: Into<TypeId>,
+    W: Write,
+{
+    let id = id.into();
+    let r#type = btf.resolve_type_by_id(id)?;
+    if let Type::Void = r#type {
+        bail!("Type {id} (void) has no record");
+    }
+
+    let raw = btf
+        .raw_record(id.into())?
+        .ok_or_else(|| anyhow!("No record for type {id}"))?;
+
+    let mut text = Vec::new();
+    dump_type(btf, id, &r#type, &mut text)?;
+    let text = String::from_utf8(text)?;
+    let mut lines = text.lines();
+    writeln!(writer, "{}", lines.next().unwrap_or_default())?;
+
+    let endianness = btf.endianness();
+    let field = |off: usize| u32::from_bytes(&raw[off..], endianness).map(|(v, _)| v);
+    let (name_off, info, size_type) = (field(0)?, field(4)?, field(8)?);
+    let (kind, vlen) = ((info >> 24) & 0x1f, info & 0xffff);
+
+    let mut offset = 0;
+    let mut chunk = |label: &str, len: usize, comment: &str| -> Result<()> {
+        let bytes = raw
+            .get(offset..offset + len)
+            .ok_or_else(|| anyhow!("Record of type {id} is truncated"))?;
+        let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02x}")).collect();
+        let line = format!(
+            "  {label:<6} {offset:04x}: {:<35}  {comment}",
+            hex.join(" ")
+        );
+        writeln!(writer, "{}", line.trim_end())?;
+        offset += len;
+        Ok(())
+    };
+
+    chunk(
+        "header",
+        cbtf::btf_type::LEN,
+        &format!(
+            "name_off={name_off} info={info:#010x} (kind={kind} kind_flag={} vlen={vlen}) size_type={size_type}",
+            info >> 31
+        ),
+    )?;
+
+    let Some((info_len, elem_len)) = cbtf::btf_type::kind_layout(kind) else {
+        bail!("Unknown layout of kind {kind}");
+    };
+    if info_len > 0 {
+        chunk("data", info_len, "")?;
+    }
+    for i in 0..vlen as usize {
+        chunk(
+            &i.to_string(),
+            elem_len,
+            lines.next().unwrap_or_default().trim(),
+        )?;
+    }
+
+    if offset != raw.len() {
+        bail!(
+            "Record of type {id} is {} bytes, {offset} were described",
+            raw.len()
+        );
+    }
+    Ok(())
+}
+
+/// Dump the raw record of a type to a String. See [`dump_record`].
+pub fn dump_record_to_string<I: Into<TypeId>>(btf: &Btf, id: I) -> Result<String> {
+    let mut out = Vec::new();
+    dump_record(btf, id, &mut out)?;
+    Ok(String::from_utf8(out)?)
+}
@@ -0,0 +1,231 @@
+//! ### Differences between BTF objects
+//!
+//! [`diff`] compares two [`Btf`] objects, e.g. the vmlinux BTF of two kernel
+//! releases, and reports the named types which were added, removed or
+//! changed. This allows auditing API drift using only BTF files.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::diff::diff};
+//!
+//! let old = Btf::from_file("vmlinux-6.1").unwrap();
+//! let new = Btf::from_file("vmlinux-6.6").unwrap();
+//!
+//! let diff = diff(&old, &new).unwrap();
+//! for (kind, name) in diff.removed.iter() {
+//!     println!("removed {} {name}", kind.name());
+//! }
+//! for changed in diff.changed.iter() {
+//!     for change in changed.changes.iter() {
+//!         println!("{} {}: {change}", changed.kind.name(), changed.name);
+//!     }
+//! }
+//! ```
+//!
+//! Types are matched by kind and name, anonymous types are only compared as
+//! part of the named types they are used in. Structs and unions are compared
+//! using [`compat::check`], which reports members that were added, removed or
+//! moved. Enum values are compared by name, and functions, typedefs and
+//! variables by their C declaration.
+use std::{collections::HashMap, fmt};
+
+use anyhow::Result;
+
+use crate::{
+    utils::{
+        compat::{self, Difference},
+        printer::definition_to_c,
+    },
+    Btf, EnumValue, Kind, Type,
+};
+
+/// Differences between two BTF objects, see [`diff`]. Types are sorted by
+/// name.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BtfDiff {
+    /// Types only found in the second object.
+    pub added: Vec<(Kind, String)>,
+    /// Types only found in the first object.
+    pub removed: Vec<(Kind, String)>,
+    /// Types found in both objects, with differences.
+    pub changed: Vec<TypeDiff>,
+}
+
+impl BtfDiff {
+    /// Are the objects equivalent?
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Differences of a type found in both objects.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TypeDiff {
+    pub kind: Kind,
+    pub name: String,
+    pub changes: Vec<Change>,
+}
+
+/// Single difference of a type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// Layout difference of a struct, union, enum or base type.
+    Layout(Difference),
+    /// An enum value was added.
+    EnumValueAdded(String, EnumValue),
+    /// An enum value was removed.
+    EnumValueRemoved(String, EnumValue),
+    /// The value of an enum member changed.
+    EnumValueChanged {
+        name: String,
+        a: EnumValue,
+        b: EnumValue,
+    },
+    /// The C declaration of a function, typedef or variable changed.
+    Declaration { a: String, b: String },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |v: &EnumValue| match v {
+            EnumValue::Signed(v) => v.to_string(),
+            EnumValue::Unsigned(v) => v.to_string(),
+        };
+        match self {
+            Change::Layout(diff) => write!(f, "{diff}"),
+            Change::EnumValueAdded(name, v) => write!(f, "added {name} = {}", value(v)),
+            Change::EnumValueRemoved(name, v) => write!(f, "removed {name} = {}", value(v)),
+            Change::EnumValueChanged { name, a, b } => {
+                write!(f, "{name} changed from {} to {}", value(a), value(b))
+            }
+            Change::Declaration { a, b } => write!(f, "changed from `{a}` to `{b}`"),
+        }
+    }
+}
+
+/// Compare two BTF objects, returning the named types added, removed and
+/// changed in `b` compared to `a`. For split BTF objects, types of their base
+/// are compared too.
+pub fn diff(a: &Btf, b: &Btf) -> Result<BtfDiff> {
+    let types_a = named_types(a)?;
+    let types_b = named_types(b)?;
+
+    let mut diff = BtfDiff::default();
+    for ((kind, name), type_a) in types_a.iter() {
+        let Some(type_b) = types_b.get(&(*kind, name.clone())) else {
+            diff.removed.push((*kind, name.clone()));
+            continue;
+        };
+
+        let changes = changes(a, type_a, b, type_b)?;
+        if !changes.is_empty() {
+            diff.changed.push(TypeDiff {
+                kind: *kind,
+                name: name.clone(),
+                changes,
+            });
+        }
+    }
+    diff.added = types_b
+        .into_keys()
+        .filter(|key| !types_a.contains_key(key))
+        .collect();
+
+    diff.added
+        .sort_by(|x, y| (&x.1, x.0.name()).cmp(&(&y.1, y.0.name())));
+    diff.removed
+        .sort_by(|x, y| (&x.1, x.0.name()).cmp(&(&y.1, y.0.name())));
+    diff.changed
+        .sort_by(|x, y| (&x.name, x.kind.name()).cmp(&(&y.name, y.kind.name())));
+    Ok(diff)
+}
+
+// Get the named types of an object, keyed by their kind and name. The first
+// definition is used when multiple types share the same kind and name.
+fn named_types(btf: &Btf) -> Result<HashMap<(Kind, String), Type>> {
+    let mut types = HashMap::new();
+    for (_, r#type) in btf.iter() {
+        if !matches!(
+            r#type.kind(),
+            Kind::Int
+                | Kind::Float
+                | Kind::Struct
+                | Kind::Union
+                | Kind::Enum
+                | Kind::Enum64
+                | Kind::Typedef
+                | Kind::Func
+                | Kind::Var
+        ) {
+            continue;
+        }
+
+        let name = btf.resolve_name_ref(&r#type)?;
+        if !name.is_empty() {
+            types
+                .entry((r#type.kind(), name.to_string()))
+                .or_insert(r#type);
+        }
+    }
+    Ok(types)
+}
+
+// Compare two types of the same kind and name.
+fn changes(btf_a: &Btf, a: &Type, btf_b: &Btf, b: &Type) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    match a.kind() {
+        Kind::Typedef | Kind::Func | Kind::Var => {
+            let (a, b) = (definition_to_c(btf_a, a)?, definition_to_c(btf_b, b)?);
+            if a != b {
+                changes.push(Change::Declaration { a, b });
+            }
+        }
+        _ => {
+            changes.extend(
+                compat::check(btf_a, a, btf_b, b)?
+                    .into_iter()
+                    .map(Change::Layout),
+            );
+
+            let (values_a, values_b) = (enum_values(btf_a, a)?, enum_values(btf_b, b)?);
+            let index_a: HashMap<&str, EnumValue> =
+                values_a.iter().map(|(n, v)| (n.as_str(), *v)).collect();
+            let index_b: HashMap<&str, EnumValue> =
+                values_b.iter().map(|(n, v)| (n.as_str(), *v)).collect();
+
+            for (name, va) in values_a.iter() {
+                match index_b.get(name.as_str()) {
+                    Some(vb) if va != vb => changes.push(Change::EnumValueChanged {
+                        name: name.clone(),
+                        a: *va,
+                        b: *vb,
+                    }),
+                    Some(_) => (),
+                    None => changes.push(Change::EnumValueRemoved(name.clone(), *va)),
+                }
+            }
+            for (name, vb) in values_b.iter() {
+                if !index_a.contains_key(name.as_str()) {
+                    changes.push(Change::EnumValueAdded(name.clone(), *vb));
+                }
+            }
+        }
+    }
+    Ok(changes)
+}
+
+// Get the values of an enum, empty for other types.
+fn enum_values(btf: &Btf, r#type: &Type) -> Result<Vec<(String, EnumValue)>> {
+    Ok(match r#type {
+        Type::Enum(e) => e
+            .members
+            .iter()
+            .map(|m| Ok((btf.resolve_name(m)?, m.value(e))))
+            .collect::<Result<_>>()?,
+        Type::Enum64(e) => e
+            .members
+            .iter()
+            .map(|m| Ok((btf.resolve_name(m)?, m.value(e))))
+            .collect::<Result<_>>()?,
+        _ => Vec::new(),
+    })
+}
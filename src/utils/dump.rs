@@ -0,0 +1,264 @@
+//! ### Deterministic dump of BTF objects
+//!
+//! Helpers writing a textual representation of BTF types, one type per entry
+//! in increasing id order, so the output is stable across runs and can be
+//! used in golden-file tests. The format is the one used by `bpftool btf dump
+//! file <file> format raw`.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::dump::dump};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! dump(&btf, &mut std::io::stdout()).unwrap();
+//! ```
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::{Btf, BtfType, EnumValue, IntEncoding, Type, TypeId};
+
+/// Dump all the types defined in a BTF object, in increasing id order. For
+/// split BTF only the types defined in the split object are dumped, as
+/// `bpftool` does.
+pub fn dump<W: Write>(btf: &Btf, writer: &mut W) -> Result<()> {
+    for id in btf.split_type_ids() {
+        let r#type = btf.resolve_type_by_id(id)?;
+        if let Type::Void = r#type {
+            continue;
+        }
+        dump_type(btf, id, &r#type, writer)?;
+    }
+    Ok(())
+}
+
+/// Dump all the types defined in a BTF object to a String. See [`dump`].
+pub fn dump_to_string(btf: &Btf) -> Result<String> {
+    let mut out = Vec::new();
+    dump(btf, &mut out)?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Dump a single type, given its id. The type is described by a first line
+/// containing its id, kind, name and common information, followed by one line
+/// per member, parameter, etc. (if any) starting with a tab.
+pub fn dump_type<I, W>(btf: &Btf, id: I, r#type: &Type, writer: &mut W) -> Result<()>
+where
+    I: Into<TypeId>,
+    W: Write,
+{
+    let id = id.into();
+    let name = |t: &dyn BtfType| -> Result<String> {
+        Ok(match t.get_name_offset() {
+            Ok(_) => btf.resolve_name(t)?,
+            Err(_) => String::new(),
+        })
+    };
+    let anon = |name: String| -> String {
+        match name.is_empty() {
+            true => "(anon)".to_string(),
+            false => name,
+        }
+    };
+    let type_id = |t: &dyn BtfType| t.get_type_id().unwrap_or_default();
+
+    write!(writer, "[{id}] {} ", kind_str(r#type))?;
+    match r#type {
+        Type::Void => write!(writer, "'(anon)'")?,
+        Type::Int(i) => write!(
+            writer,
+            "'{}' size={} bits_offset={} nr_bits={} encoding={}",
+            anon(name(i)?),
+            i.size(),
+            i.offset(),
+            i.bits(),
+            match i.encoding() {
+                IntEncoding::None => "(none)",
+                IntEncoding::Signed => "SIGNED",
+                IntEncoding::Char => "CHAR",
+                IntEncoding::Bool => "BOOL",
+            }
+        )?,
+        Type::Ptr(p) => write!(writer, "'(anon)' type_id={}", type_id(p))?,
+        Type::Volatile(v) | Type::Const(v) | Type::Restrict(v) => {
+            write!(writer, "'(anon)' type_id={}", type_id(v))?
+        }
+        Type::Typedef(td) | Type::TypeTag(td) => {
+            write!(writer, "'{}' type_id={}", anon(name(td)?), type_id(td))?
+        }
+        Type::Array(a) => write!(
+            writer,
+            "'(anon)' type_id={} index_type_id={} nr_elems={}",
+            type_id(a),
+            a.index_type_id(),
+            a.len()
+        )?,
+        Type::Struct(s) | Type::Union(s) => {
+            write!(
+                writer,
+                "'{}' size={} vlen={}",
+                anon(name(s)?),
+                s.size(),
+                s.members.len()
+            )?;
+            for m in s.members.iter() {
+                write!(
+                    writer,
+                    "\n\t'{}' type_id={} bits_offset={}",
+                    anon(name(m)?),
+                    type_id(m),
+                    m.bit_offset()
+                )?;
+                if let Some(size) = m.bitfield_size() {
+                    if size != 0 {
+                        write!(writer, " bitfield_size={size}")?;
+                    }
+                }
+            }
+        }
+        Type::Enum(e) => {
+            write!(
+                writer,
+                "'{}' encoding={} size={} vlen={}",
+                anon(name(e)?),
+                signedness(e.is_signed()),
+                e.size(),
+                e.members.len()
+            )?;
+            for m in e.members.iter() {
+                write_enum_value(writer, &anon(name(m)?), m.value(e))?;
+            }
+        }
+        Type::Enum64(e) => {
+            write!(
+                writer,
+                "'{}' encoding={} size={} vlen={}",
+                anon(name(e)?),
+                signedness(e.is_signed()),
+                e.size(),
+                e.members.len()
+            )?;
+            for m in e.members.iter() {
+                write_enum_value(writer, &anon(name(m)?), m.value(e))?;
+            }
+        }
+        Type::Fwd(f) => write!(
+            writer,
+            "'{}' fwd_kind={}",
+            anon(name(f)?),
+            match f.is_union() {
+                true => "union",
+                false => "struct",
+            }
+        )?,
+        Type::Func(f) => write!(
+            writer,
+            "'{}' type_id={} linkage={}",
+            anon(name(f)?),
+            type_id(f),
+            match (f.is_static(), f.is_global(), f.is_extern()) {
+                (true, _, _) => "static",
+                (_, true, _) => "global",
+                (_, _, true) => "extern",
+                _ => "(unknown)",
+            }
+        )?,
+        Type::FuncProto(fp) => {
+            write!(
+                writer,
+                "'(anon)' ret_type_id={} vlen={}",
+                fp.return_type_id(),
+                fp.parameters.len()
+            )?;
+            for p in fp.parameters.iter() {
+                write!(writer, "\n\t'{}' type_id={}", anon(name(p)?), type_id(p))?;
+            }
+        }
+        Type::Var(v) => write!(
+            writer,
+            "'{}' type_id={}, linkage={}",
+            anon(name(v)?),
+            type_id(v),
+            match v.linkage() {
+                0 => "static",
+                1 => "global",
+                2 => "extern",
+                _ => "(unknown)",
+            }
+        )?,
+        Type::Datasec(ds) => {
+            write!(
+                writer,
+                "'{}' size={} vlen={}",
+                anon(name(ds)?),
+                ds.size(),
+                ds.variables.len()
+            )?;
+            for v in ds.variables.iter() {
+                write!(
+                    writer,
+                    "\n\ttype_id={} offset={} size={}",
+                    type_id(v),
+                    v.offset(),
+                    v.size()
+                )?;
+                // As bpftool, name the variables when their type is valid.
+                if let Ok(var) = btf.resolve_chained_type(v) {
+                    let var_name = anon(name(var.as_btf_type())?);
+                    write!(writer, " ({} '{var_name}')", kind_str(&var))?;
+                }
+            }
+        }
+        Type::Float(f) => write!(writer, "'{}' size={}", anon(name(f)?), f.size())?,
+        Type::DeclTag(dt) => write!(
+            writer,
+            "'{}' type_id={} component_idx={}",
+            anon(name(dt)?),
+            type_id(dt),
+            dt.component_index().map(|i| i as i64).unwrap_or(-1)
+        )?,
+    }
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+fn write_enum_value<W: Write>(writer: &mut W, name: &str, value: EnumValue) -> Result<()> {
+    match value {
+        EnumValue::Signed(val) => write!(writer, "\n\t'{name}' val={val}")?,
+        EnumValue::Unsigned(val) => write!(writer, "\n\t'{name}' val={val}")?,
+    }
+    Ok(())
+}
+
+// Get the kind string of a type, as used by bpftool.
+pub(crate) fn kind_str(r#type: &Type) -> &'static str {
+    match r#type {
+        Type::Void => "VOID",
+        Type::Int(_) => "INT",
+        Type::Ptr(_) => "PTR",
+        Type::Array(_) => "ARRAY",
+        Type::Struct(_) => "STRUCT",
+        Type::Union(_) => "UNION",
+        Type::Enum(_) => "ENUM",
+        Type::Fwd(_) => "FWD",
+        Type::Typedef(_) => "TYPEDEF",
+        Type::Volatile(_) => "VOLATILE",
+        Type::Const(_) => "CONST",
+        Type::Restrict(_) => "RESTRICT",
+        Type::Func(_) => "FUNC",
+        Type::FuncProto(_) => "FUNC_PROTO",
+        Type::Var(_) => "VAR",
+        Type::Datasec(_) => "DATASEC",
+        Type::Float(_) => "FLOAT",
+        Type::DeclTag(_) => "DECL_TAG",
+        Type::TypeTag(_) => "TYPE_TAG",
+        Type::Enum64(_) => "ENUM64",
+    }
+}
+
+fn signedness(signed: bool) -> &'static str {
+    match signed {
+        true => "SIGNED",
+        false => "UNSIGNED",
+    }
+}
@@ -1,79 +1,396 @@
 use std::{
+    collections::{BTreeMap, HashSet},
     fs::{self, File},
-    path::Path,
+    io,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, bail, Result};
-use elf::{endian::AnyEndian, ElfStream};
+use elf::{
+    endian::AnyEndian,
+    note::{Note, NoteGnuBuildId},
+    ElfStream,
+};
 
-use crate::utils::collection::BtfCollection;
+use crate::{utils::collection::BtfCollection, Btf, SourceKind};
 
 /// Extract raw BTF data from the .BTF elf section of the given file. Output can
 /// be used to fed `from_bytes` constructors in this library.
 pub fn extract_btf_from_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+    let mut elf = open_elf(&path)?;
+    extract_btf(&mut elf, path.as_ref())
+}
+
+/// Extract raw BTF.ext data from the .BTF.ext elf section of the given file,
+/// usually an eBPF object. Output can be parsed using `ext::BtfExt`.
+pub fn extract_btf_ext_from_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+    let mut elf = open_elf(&path)?;
+    extract_section(&mut elf, path.as_ref(), ".BTF.ext")
+}
+
+/// Same as `extract_btf_from_file()` but also extracts the GNU build-id of
+/// the file, if any. This is useful to correlate the BTF data with debuginfo
+/// files or to check split and base objects do match.
+pub fn extract_btf_and_build_id<P: AsRef<Path>>(path: P) -> Result<(Vec<u8>, Option<Vec<u8>>)> {
+    let mut elf = open_elf(&path)?;
+    let btf = extract_btf(&mut elf, path.as_ref())?;
+    Ok((btf, extract_build_id(&mut elf)?))
+}
+
+// Open an ELF file.
+fn open_elf<P: AsRef<Path>>(path: P) -> Result<ElfStream<AnyEndian, File>> {
     let file = File::open(&path)
         .map_err(|e| anyhow!("Could not open {}: {e}", path.as_ref().display()))?;
-    let mut elf = ElfStream::<AnyEndian, _>::open_stream(file)?;
+    Ok(ElfStream::<AnyEndian, _>::open_stream(file)?)
+}
 
-    let btf_hdr = match elf.section_header_by_name(".BTF")? {
+// Extract the raw BTF data from an ELF file.
+fn extract_btf(elf: &mut ElfStream<AnyEndian, File>, path: &Path) -> Result<Vec<u8>> {
+    extract_section(elf, path, ".BTF")
+}
+
+// Extract the raw BTF data from an ELF file, if it has a .BTF section.
+fn extract_btf_if_any(
+    elf: &mut ElfStream<AnyEndian, File>,
+    path: &Path,
+) -> Result<Option<Vec<u8>>> {
+    Ok(match elf.section_header_by_name(".BTF")?.is_some() {
+        true => Some(extract_btf(elf, path)?),
+        false => None,
+    })
+}
+
+// Extract the raw data of a BTF related section from an ELF file.
+fn extract_section(
+    elf: &mut ElfStream<AnyEndian, File>,
+    path: &Path,
+    name: &str,
+) -> Result<Vec<u8>> {
+    let hdr = match elf.section_header_by_name(name)? {
         Some(hdr) => *hdr,
-        None => bail!("No BTF section in {}", path.as_ref().display()),
+        None => bail!("No {} section in {}", &name[1..], path.display()),
     };
 
-    let (btf, chdr) = elf.section_data(&btf_hdr)?;
+    let (data, chdr) = elf.section_data(&hdr)?;
     if chdr.is_some() {
         bail!(
-            "Compressed BTF sections are not supported ({})",
-            path.as_ref().display()
+            "Compressed {} sections are not supported ({})",
+            &name[1..],
+            path.display()
         );
     }
 
-    Ok(btf.to_vec())
+    Ok(data.to_vec())
+}
+
+// Extract the GNU build-id of an ELF file, if any.
+fn extract_build_id(elf: &mut ElfStream<AnyEndian, File>) -> Result<Option<Vec<u8>>> {
+    let hdr = match elf.section_header_by_name(".note.gnu.build-id")? {
+        Some(hdr) => *hdr,
+        None => return Ok(None),
+    };
+
+    for note in elf.section_data_as_notes(&hdr)? {
+        if let Note::GnuBuildId(NoteGnuBuildId(id)) = note {
+            return Ok(Some(id.to_vec()));
+        }
+    }
+    Ok(None)
 }
 
+// Check if an ELF file has a .BTF section.
+pub(crate) fn has_btf_section<P: AsRef<Path>>(path: P) -> Result<bool> {
+    let mut elf = open_elf(path)?;
+    Ok(elf.section_header_by_name(".BTF")?.is_some())
+}
+
+/// Construct a `Btf` object from the .BTF elf section of the given file.
+pub fn btf_from_file<P: AsRef<Path>>(path: P) -> Result<Btf> {
+    Ok(Btf::from_bytes(&extract_btf_from_file(path)?)?.with_source(SourceKind::Elf))
+}
+
+/// Construct a split `Btf` object from the .BTF elf section of the given
+/// file. A base Btf object must be provided.
+pub fn split_btf_from_file<P: AsRef<Path>>(path: P, base: &Btf) -> Result<Btf> {
+    Ok(Btf::from_split_bytes(&extract_btf_from_file(path)?, base)?.with_source(SourceKind::Elf))
+}
+
+/// Maximum directory depth explored below the root directory when looking for
+/// modules in `collection_from_kernel_dir`.
+pub const MAX_KERNEL_DIR_DEPTH: usize = 16;
+
+// Extensions of compressed modules. Those are detected so they do not shadow
+// their uncompressed counterparts but are not loaded, as compressed modules
+// are not supported.
+const COMPRESSED_MODULE_EXTS: &[&str] = &[".ko.xz", ".ko.gz", ".ko.zst"];
+
 /// Given a directory containing a 'vmlinux' ELF file in its root and optional
 /// '*.ko' ELF modules in the root or any sub-directory (this maps well to a
 /// Linux build directory or /usr/lib/modules/), initialize a BtfCollection
 /// extracting BTF data from the .BTF section of those files.
+///
+/// Directories are visited once, even when reachable through multiple paths
+/// (symlinks), and at most `MAX_KERNEL_DIR_DEPTH` levels deep. When multiple
+/// files describe the same module, the uncompressed one is used and otherwise
+/// the first one found is, entries being visited in file name order.
+/// Compressed modules are not supported and are not loaded.
+///
+/// If no 'vmlinux' file is found, the base file is detected. See
+/// [`KernelDirLoader`] for more control over the loading, and
+/// [`KernelDirLoader::load_with_report`] to get the list of modules ignored.
 pub fn collection_from_kernel_dir<P: AsRef<Path>>(path: P) -> Result<BtfCollection> {
-    let path = path.as_ref();
-    if !path.is_dir() {
-        bail!(
-            "Can't initialize a BTF collection from {}: not a directory",
-            path.display()
-        );
+    KernelDirLoader::new(path).load()
+}
+
+/// Builder initializing a BtfCollection from a kernel directory, see
+/// [`collection_from_kernel_dir`].
+///
+/// ```no_run
+/// use btf_rs::utils::elf::KernelDirLoader;
+///
+/// let btfc = KernelDirLoader::new("/path/to/linux")
+///     .base("vmlinux.o")
+///     .load()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct KernelDirLoader {
+    path: PathBuf,
+    base: Option<PathBuf>,
+    skip_missing_btf: bool,
+    fallback_dir: Option<PathBuf>,
+}
+
+/// Report of a kernel directory loading, see
+/// [`KernelDirLoader::load_with_report`].
+#[derive(Clone, Debug, Default)]
+pub struct LoadReport {
+    /// Modules loaded from the .BTF section of their ELF file.
+    pub loaded: Vec<String>,
+    /// Modules loaded from the fallback directory, as their ELF file has no
+    /// .BTF section.
+    pub fallback: Vec<String>,
+    /// Modules skipped as their ELF file has no .BTF section.
+    pub skipped: Vec<String>,
+    /// Modules skipped as they are only available compressed.
+    pub compressed: Vec<String>,
+    /// Modules described by multiple files (e.g. found in two directories, or
+    /// both compressed and uncompressed), once per ignored file. Only one of
+    /// those is used, see [`collection_from_kernel_dir`].
+    pub duplicates: Vec<String>,
+}
+
+impl KernelDirLoader {
+    /// Construct a new loader for the given kernel directory.
+    pub fn new<P: AsRef<Path>>(path: P) -> KernelDirLoader {
+        KernelDirLoader {
+            path: path.as_ref().to_path_buf(),
+            base: None,
+            skip_missing_btf: false,
+            fallback_dir: None,
+        }
+    }
+
+    /// Set the path of the base ELF file, relative to the kernel directory.
+    /// If not set 'vmlinux' is used if it exists; otherwise the first ELF file
+    /// in the directory root containing a standalone kernel BTF (with a
+    /// `.data..percpu` Datasec) is used.
+    pub fn base<P: AsRef<Path>>(mut self, base: P) -> KernelDirLoader {
+        self.base = Some(base.as_ref().to_path_buf());
+        self
+    }
+
+    /// Skip modules whose ELF file has no .BTF section (and which could not
+    /// be found in the fallback directory, if any) instead of failing. Those
+    /// are recorded in the [`LoadReport`].
+    pub fn skip_missing_btf(mut self, skip: bool) -> KernelDirLoader {
+        self.skip_missing_btf = skip;
+        self
+    }
+
+    /// Set a directory holding raw split BTF files named after modules (e.g.
+    /// `/sys/kernel/btf`), used for modules whose ELF file has no .BTF
+    /// section. The files must have been generated on top of the same base
+    /// BTF.
+    pub fn fallback_dir<P: AsRef<Path>>(mut self, dir: P) -> KernelDirLoader {
+        self.fallback_dir = Some(dir.as_ref().to_path_buf());
+        self
     }
 
-    // Find the base BTF file and initialize the collection.
-    let vmlinux = path.join("vmlinux");
-    let mut collection = BtfCollection::from_bytes("vmlinux", &extract_btf_from_file(vmlinux)?)?;
-
-    // Traverse the directory looking for modules.
-    fn visit_dir<P: AsRef<Path>>(dir: P, collection: &mut BtfCollection) -> Result<()> {
-        for entry in fs::read_dir(dir)? {
-            let path = entry?.path();
-            let filename = path
-                .file_name()
-                .ok_or_else(|| anyhow!("Could not get module file name"))?
-                .to_str()
-                .ok_or_else(|| anyhow!("Could not convert module name to str"))?;
-
-            if path.is_dir() {
-                visit_dir(path, collection)?;
-            } else if filename.ends_with(".ko") {
-                collection.add_split_btf_from_bytes(
-                    match filename.split_once('.') {
-                        Some((name, _)) => name,
-                        // Should not happen as we already filtered on extensions.
-                        None => bail!("Invalid module file name"),
-                    },
-                    &extract_btf_from_file(&path)?,
-                )?;
+    /// Load the base BTF and the modules found in the kernel directory.
+    pub fn load(self) -> Result<BtfCollection> {
+        Ok(self.load_with_report()?.0)
+    }
+
+    /// Same as `load()`, but also returns a report of what was loaded and
+    /// skipped.
+    pub fn load_with_report(self) -> Result<(BtfCollection, LoadReport)> {
+        let path = self.path.as_path();
+        if !path.is_dir() {
+            bail!(
+                "Can't initialize a BTF collection from {}: not a directory",
+                path.display()
+            );
+        }
+
+        // Find the base BTF file and initialize the collection.
+        let (name, base) = match &self.base {
+            Some(base) => {
+                let base = path.join(base);
+                (module_name(&base)?, btf_from_file(&base)?)
+            }
+            None => Self::find_base(path)?,
+        };
+        let mut collection = BtfCollection::from_btf(&name, base);
+
+        // Traverse the directory looking for modules. Modules are first
+        // collected and deduplicated by name, then loaded.
+        let mut visited = HashSet::new();
+        let mut modules = BTreeMap::new();
+        let mut report = LoadReport::default();
+        visit_dir(path, 0, &mut visited, &mut modules, &mut report.duplicates)?;
+
+        for (name, module) in modules.iter() {
+            let path = match module {
+                Module::Uncompressed(path) => path,
+                Module::Compressed => {
+                    report.compressed.push(name.clone());
+                    continue;
+                }
+            };
+
+            if let Some(raw) = extract_btf_if_any(&mut open_elf(path)?, path)? {
+                let btf = Btf::from_split_bytes(&raw, &collection.base().btf)?
+                    .with_source(SourceKind::Elf);
+                collection.add_split_btf(name, btf)?;
+                report.loaded.push(name.clone());
+                continue;
+            }
+
+            match &self.fallback_dir {
+                Some(dir) if dir.join(name).is_file() => {
+                    let btf = Btf::from_split_file(dir.join(name), &collection.base().btf)?;
+                    collection.add_split_btf(name, btf)?;
+                    report.fallback.push(name.clone());
+                }
+                _ if self.skip_missing_btf => report.skipped.push(name.clone()),
+                _ => bail!("No BTF section in {}", path.display()),
             }
         }
-        Ok(())
+
+        Ok((collection, report))
     }
-    visit_dir(path, &mut collection)?;
 
-    Ok(collection)
+    // Find the base BTF file in the root of a kernel directory.
+    fn find_base(path: &Path) -> Result<(String, Btf)> {
+        let vmlinux = path.join("vmlinux");
+        if vmlinux.exists() {
+            return Ok(("vmlinux".to_string(), btf_from_file(vmlinux)?));
+        }
+
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+
+        for entry in entries.iter() {
+            if !entry.is_file() {
+                continue;
+            }
+            let Ok(Some(raw)) =
+                open_elf(entry).and_then(|mut elf| extract_btf_if_any(&mut elf, entry))
+            else {
+                continue;
+            };
+            // Module BTF is split and fails to be parsed as a standalone one.
+            if let Ok(btf) = Btf::from_bytes(&raw) {
+                if btf.resolve_ids_by_name(".data..percpu").is_ok() {
+                    return Ok((module_name(entry)?, btf.with_source(SourceKind::Elf)));
+                }
+            }
+        }
+
+        bail!("Could not find a base BTF file in {}", path.display())
+    }
+}
+
+// Module file found while traversing a kernel directory.
+enum Module {
+    Uncompressed(PathBuf),
+    Compressed,
+}
+
+// Recursively visit a directory, looking for modules. The names of modules
+// found in multiple files are added to `duplicates`, once per ignored file.
+fn visit_dir(
+    dir: &Path,
+    depth: usize,
+    visited: &mut HashSet<PathBuf>,
+    modules: &mut BTreeMap<String, Module>,
+    duplicates: &mut Vec<String>,
+) -> Result<()> {
+    // Do not visit the same directory twice, this protects against symlink
+    // loops.
+    if depth > MAX_KERNEL_DIR_DEPTH || !visited.insert(fs::canonicalize(dir)?) {
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            visit_dir(&path, depth + 1, visited, modules, duplicates)?;
+            continue;
+        }
+
+        let filename = file_name(&path)?;
+        let module = if filename.ends_with(".ko") {
+            Module::Uncompressed(path.clone())
+        } else if COMPRESSED_MODULE_EXTS
+            .iter()
+            .any(|ext| filename.ends_with(ext))
+        {
+            Module::Compressed
+        } else {
+            continue;
+        };
+
+        let name = module_name(&path)?;
+        match modules.get(&name) {
+            None => _ = modules.insert(name, module),
+            Some(existing) => {
+                // Uncompressed files are preferred, otherwise the first one
+                // found is used.
+                if let (Module::Compressed, Module::Uncompressed(_)) = (existing, &module) {
+                    modules.insert(name.clone(), module);
+                }
+                duplicates.push(name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Get the file name of a path as a str.
+fn file_name(path: &Path) -> Result<&str> {
+    path.file_name()
+        .ok_or_else(|| anyhow!("Could not get module file name"))?
+        .to_str()
+        .ok_or_else(|| anyhow!("Could not convert module name to str"))
+}
+
+// Get the name of a module (or of the base object) from its path, e.g.
+// `net/openvswitch/openvswitch.ko` gives `openvswitch`. As done by `modprobe`
+// and in `/sys/kernel/btf`, dashes are converted to underscores.
+fn module_name(path: &Path) -> Result<String> {
+    let filename = file_name(path)?;
+    Ok(match filename.split_once('.') {
+        Some((name, _)) => name,
+        None => filename,
+    }
+    .replace('-', "_"))
 }
@@ -0,0 +1,355 @@
+//! ### JSON export of BTF objects
+//!
+//! [`to_json`] dumps all the types of a BTF object as JSON, in the format of
+//! `bpftool btf dump file <file> --json`, so its output can be diffed against
+//! or consumed by existing, non-Rust, tooling.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::export::to_json};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! println!("{}", to_json(&btf).unwrap());
+//! ```
+//!
+//! Which prints something like:
+//!
+//! ```text
+//! {"schema_version":1,"types":[{"id":1,"kind":"INT","name":"long unsigned int","size":8,"bits_offset":0,"nr_bits":64,"encoding":"(none)"},...]}
+//! ```
+//!
+//! As `bpftool` does, only the types defined in split objects are exported.
+//!
+//! On top of the `bpftool` format, the output has a top-level
+//! `schema_version` field holding [`SCHEMA_VERSION`]. Within a version, fields
+//! are never renamed nor removed and their meaning and values do not change,
+//! so the output stays diffable across btf-rs releases; new fields can be
+//! added. Other changes bump the version.
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::{
+    utils::{
+        dump::kind_str,
+        resolved::{self, Linkage, ResolvedEnumValue, ResolvedType},
+    },
+    Btf, EnumValue, IntEncoding, Type, TypeId,
+};
+
+/// Version of the JSON output. See the module documentation for the
+/// guarantees it comes with.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Dump all the types defined in a BTF object as JSON, in increasing id
+/// order.
+pub fn to_json(btf: &Btf) -> Result<String> {
+    Ok(serde_json::to_string(&records(btf)?)?)
+}
+
+/// Dump all the types defined in a BTF object as pretty-printed JSON. See
+/// [`to_json`].
+pub fn to_json_pretty(btf: &Btf) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&records(btf)?)?)
+}
+
+#[derive(Serialize)]
+struct Dump {
+    schema_version: u32,
+    types: Vec<Record>,
+}
+
+// A type, as exported by bpftool. Fields are serialized in declaration order,
+// which matches the one of bpftool.
+#[derive(Serialize)]
+struct Record {
+    id: u32,
+    kind: &'static str,
+    name: String,
+    #[serde(flatten)]
+    data: Data,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Data {
+    Int {
+        size: usize,
+        bits_offset: u32,
+        nr_bits: u32,
+        encoding: &'static str,
+    },
+    Array {
+        type_id: TypeId,
+        index_type_id: TypeId,
+        nr_elems: usize,
+    },
+    Composite {
+        size: usize,
+        vlen: usize,
+        members: Vec<Member>,
+    },
+    Enum {
+        encoding: &'static str,
+        size: usize,
+        vlen: usize,
+        values: Vec<Value>,
+    },
+    Fwd {
+        fwd_kind: &'static str,
+    },
+    Linked {
+        type_id: TypeId,
+        linkage: Linkage,
+    },
+    FuncProto {
+        ret_type_id: TypeId,
+        vlen: usize,
+        params: Vec<Param>,
+    },
+    Datasec {
+        size: usize,
+        vlen: usize,
+        vars: Vec<SecVar>,
+    },
+    Float {
+        size: usize,
+    },
+    DeclTag {
+        type_id: TypeId,
+        component_idx: i64,
+    },
+    // Pointers, qualifiers, typedefs and type tags.
+    Ref {
+        type_id: TypeId,
+    },
+}
+
+#[derive(Serialize)]
+struct Member {
+    name: String,
+    type_id: TypeId,
+    bits_offset: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bitfield_size: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct Value {
+    name: String,
+    val: EnumValue,
+}
+
+#[derive(Serialize)]
+struct Param {
+    name: String,
+    type_id: TypeId,
+}
+
+#[derive(Serialize)]
+struct SecVar {
+    type_id: TypeId,
+    offset: u32,
+    size: usize,
+}
+
+// Build the records of all the types defined in a BTF object.
+fn records(btf: &Btf) -> Result<Dump> {
+    let mut types = Vec::new();
+    for id in btf.split_type_ids() {
+        let r#type = btf.resolve_type_by_id(id)?;
+        if let Type::Void = r#type {
+            continue;
+        }
+        let (name, data) = data(resolved::resolve(btf, &r#type)?)?;
+        types.push(Record {
+            id,
+            kind: kind_str(&r#type),
+            name: anon(name),
+            data,
+        });
+    }
+    Ok(Dump {
+        schema_version: SCHEMA_VERSION,
+        types,
+    })
+}
+
+// Convert a resolved type to its name and bpftool representation.
+fn data(r#type: ResolvedType) -> Result<(Option<String>, Data)> {
+    let values = |values: Vec<ResolvedEnumValue>| {
+        values
+            .into_iter()
+            .map(|v| Value {
+                name: v.name,
+                val: v.value,
+            })
+            .collect::<Vec<_>>()
+    };
+    let signedness = |signed| match signed {
+        true => "SIGNED",
+        false => "UNSIGNED",
+    };
+
+    Ok(match r#type {
+        ResolvedType::Void => bail!("Void has no representation"),
+        ResolvedType::Int {
+            name,
+            size,
+            encoding,
+            bit_offset,
+            bits,
+            ..
+        } => (
+            Some(name),
+            Data::Int {
+                size,
+                bits_offset: bit_offset,
+                nr_bits: bits,
+                encoding: match encoding {
+                    IntEncoding::None => "(none)",
+                    IntEncoding::Signed => "SIGNED",
+                    IntEncoding::Char => "CHAR",
+                    IntEncoding::Bool => "BOOL",
+                },
+            },
+        ),
+        ResolvedType::Ptr { type_id }
+        | ResolvedType::Volatile { type_id }
+        | ResolvedType::Const { type_id }
+        | ResolvedType::Restrict { type_id } => (None, Data::Ref { type_id }),
+        ResolvedType::Typedef { name, type_id } | ResolvedType::TypeTag { name, type_id } => {
+            (Some(name), Data::Ref { type_id })
+        }
+        ResolvedType::Array {
+            type_id,
+            index_type_id,
+            len,
+        } => (
+            None,
+            Data::Array {
+                type_id,
+                index_type_id,
+                nr_elems: len,
+            },
+        ),
+        ResolvedType::Struct {
+            name,
+            size,
+            members,
+        }
+        | ResolvedType::Union {
+            name,
+            size,
+            members,
+        } => (
+            name,
+            Data::Composite {
+                size,
+                vlen: members.len(),
+                members: members
+                    .into_iter()
+                    .map(|m| Member {
+                        name: anon(m.name),
+                        type_id: m.type_id,
+                        bits_offset: m.bit_offset,
+                        bitfield_size: m.bitfield_size,
+                    })
+                    .collect(),
+            },
+        ),
+        ResolvedType::Enum {
+            name,
+            size,
+            signed,
+            values: v,
+        }
+        | ResolvedType::Enum64 {
+            name,
+            size,
+            signed,
+            values: v,
+        } => (
+            name,
+            Data::Enum {
+                encoding: signedness(signed),
+                size,
+                vlen: v.len(),
+                values: values(v),
+            },
+        ),
+        ResolvedType::Fwd { name, union } => (
+            Some(name),
+            Data::Fwd {
+                fwd_kind: match union {
+                    true => "union",
+                    false => "struct",
+                },
+            },
+        ),
+        ResolvedType::Func {
+            name,
+            type_id,
+            linkage,
+        }
+        | ResolvedType::Var {
+            name,
+            type_id,
+            linkage,
+        } => (Some(name), Data::Linked { type_id, linkage }),
+        ResolvedType::FuncProto {
+            return_type_id,
+            parameters,
+        } => (
+            None,
+            Data::FuncProto {
+                ret_type_id: return_type_id,
+                vlen: parameters.len(),
+                params: parameters
+                    .into_iter()
+                    .map(|p| Param {
+                        name: anon(p.name),
+                        type_id: p.type_id,
+                    })
+                    .collect(),
+            },
+        ),
+        ResolvedType::Datasec {
+            name,
+            size,
+            variables,
+        } => (
+            Some(name),
+            Data::Datasec {
+                size,
+                vlen: variables.len(),
+                vars: variables
+                    .into_iter()
+                    .map(|v| SecVar {
+                        type_id: v.type_id,
+                        offset: v.offset,
+                        size: v.size,
+                    })
+                    .collect(),
+            },
+        ),
+        ResolvedType::Float { name, size } => (Some(name), Data::Float { size }),
+        ResolvedType::DeclTag {
+            name,
+            type_id,
+            component_index,
+        } => (
+            Some(name),
+            Data::DeclTag {
+                type_id,
+                component_idx: component_index.map(|i| i as i64).unwrap_or(-1),
+            },
+        ),
+    })
+}
+
+// Anonymous types are named "(anon)" by bpftool.
+fn anon(name: Option<String>) -> String {
+    match name {
+        Some(name) if !name.is_empty() => name,
+        _ => "(anon)".to_string(),
+    }
+}
@@ -0,0 +1,362 @@
+//! ### Struct layout validation
+//!
+//! Tools reading kernel memory directly (e.g. using a Rust mirror of a kernel
+//! struct) need the layout they expect to match the running kernel one. An
+//! [`ExpectedLayout`] describes the expected size and fields of a struct, and
+//! [`validate`] checks it against BTF, reporting all the mismatches found.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, Kind, utils::layout::{validate, ExpectedLayout}};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! let layout = ExpectedLayout::new("sk_buff")
+//!     .field("len", 112, 4)
+//!     .field_kind("data", 208, 8, Kind::Ptr);
+//!
+//! for mismatch in validate(&btf, &layout).unwrap() {
+//!     eprintln!("{mismatch}");
+//! }
+//! ```
+//!
+//! Rust types mirroring a kernel struct can also implement [`FromBtf`],
+//! usually using `#[derive(FromBtf)]` (requires the derive feature). The
+//! offsets of their fields are then resolved at runtime using [`BtfOffsets`],
+//! which also checks the fields sizes, and used to read the fields from raw
+//! kernel memory.
+//!
+//! ```ignore
+//! use btf_rs::{Btf, utils::layout::{BtfOffsets, FromBtf}};
+//!
+//! #[derive(FromBtf)]
+//! #[btf(name = "sk_buff")]
+//! struct Skb {
+//!     len: u32,
+//!     #[btf(name = "data_len")]
+//!     dlen: u32,
+//! }
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! let offsets = BtfOffsets::<Skb>::resolve(&btf).unwrap();
+//! println!("skb->data_len is at offset {}", offsets.offset(Skb::BTF_DLEN));
+//!
+//! let skb: Skb = offsets.read(&raw_skb).unwrap();
+//! ```
+use std::{fmt, marker::PhantomData};
+
+use anyhow::{anyhow, bail, Result};
+
+#[cfg(feature = "derive")]
+pub use btf_rs_derive::FromBtf;
+
+use crate::{Btf, Kind};
+
+/// Expected layout of a struct or union.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpectedLayout {
+    /// Name of the struct or union.
+    pub name: String,
+    /// Expected size in bytes, if it should be checked.
+    pub size: Option<usize>,
+    /// Expected fields. Fields not listed here are not checked.
+    pub fields: Vec<ExpectedField>,
+}
+
+/// Expected definition of a struct or union field.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpectedField {
+    /// Name of the field. Fields of anonymous struct and union members can be
+    /// used directly.
+    pub name: String,
+    /// Expected offset in bytes from the start of the struct.
+    pub offset: usize,
+    /// Expected size in bytes.
+    pub size: usize,
+    /// Expected kind of the field type, after skipping typedefs and
+    /// qualifiers, if it should be checked.
+    pub kind: Option<Kind>,
+}
+
+impl ExpectedLayout {
+    /// Start describing the layout of a struct or union, given its name.
+    pub fn new(name: &str) -> ExpectedLayout {
+        ExpectedLayout {
+            name: name.to_string(),
+            size: None,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Set the expected size of the struct, in bytes.
+    pub fn size(mut self, size: usize) -> ExpectedLayout {
+        self.size = Some(size);
+        self
+    }
+
+    /// Add an expected field, given its offset and size in bytes.
+    pub fn field(mut self, name: &str, offset: usize, size: usize) -> ExpectedLayout {
+        self.fields.push(ExpectedField {
+            name: name.to_string(),
+            offset,
+            size,
+            kind: None,
+        });
+        self
+    }
+
+    /// Same as `field()`, also checking the kind of the field type.
+    pub fn field_kind(
+        mut self,
+        name: &str,
+        offset: usize,
+        size: usize,
+        kind: Kind,
+    ) -> ExpectedLayout {
+        self.fields.push(ExpectedField {
+            name: name.to_string(),
+            offset,
+            size,
+            kind: Some(kind),
+        });
+        self
+    }
+}
+
+/// Difference between an expected layout and the BTF definition.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Mismatch {
+    /// The struct size differs.
+    Size { expected: usize, found: usize },
+    /// A field is not part of the struct.
+    MissingField(String),
+    /// A field is a bitfield, which can't be described by byte offsets and
+    /// sizes.
+    Bitfield(String),
+    /// A field offset differs.
+    Offset {
+        field: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A field size differs.
+    FieldSize {
+        field: String,
+        expected: usize,
+        found: usize,
+    },
+    /// The kind of a field type differs.
+    Kind {
+        field: String,
+        expected: Kind,
+        found: Kind,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Size { expected, found } => {
+                write!(f, "size is {found} bytes, expected {expected}")
+            }
+            Mismatch::MissingField(field) => write!(f, "field {field} not found"),
+            Mismatch::Bitfield(field) => write!(f, "field {field} is a bitfield"),
+            Mismatch::Offset {
+                field,
+                expected,
+                found,
+            } => write!(f, "field {field} is at offset {found}, expected {expected}"),
+            Mismatch::FieldSize {
+                field,
+                expected,
+                found,
+            } => write!(f, "field {field} is {found} bytes, expected {expected}"),
+            Mismatch::Kind {
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "field {field} is a {}, expected a {}",
+                found.name(),
+                expected.name()
+            ),
+        }
+    }
+}
+
+/// Validate an expected layout against its BTF definition, returning the list
+/// of mismatches found (empty if the layout matches). Fails if the struct or
+/// union can't be found or its definition can't be resolved.
+pub fn validate(btf: &Btf, layout: &ExpectedLayout) -> Result<Vec<Mismatch>> {
+    let r#struct = btf.resolve_struct_by_name(&layout.name)?;
+
+    let mut mismatches = Vec::new();
+
+    if let Some(expected) = layout.size {
+        if r#struct.size() != expected {
+            mismatches.push(Mismatch::Size {
+                expected,
+                found: r#struct.size(),
+            });
+        }
+    }
+
+    for field in layout.fields.iter() {
+        let (member, offset) = match r#struct.member_by_name(btf, &field.name)? {
+            Some(member) => member,
+            None => {
+                mismatches.push(Mismatch::MissingField(field.name.clone()));
+                continue;
+            }
+        };
+        if member.is_bitfield() {
+            mismatches.push(Mismatch::Bitfield(field.name.clone()));
+            continue;
+        }
+
+        let offset = offset as usize / 8;
+        if offset != field.offset {
+            mismatches.push(Mismatch::Offset {
+                field: field.name.clone(),
+                expected: field.offset,
+                found: offset,
+            });
+        }
+
+        let r#type = btf.resolve_chained_type(&member)?;
+        let size = btf.resolve_type_size(&r#type)?;
+        if size != field.size {
+            mismatches.push(Mismatch::FieldSize {
+                field: field.name.clone(),
+                expected: field.size,
+                found: size,
+            });
+        }
+
+        if let Some(expected) = field.kind {
+            let found = btf.resolve_underlying_type(&r#type)?.kind();
+            if found != expected {
+                mismatches.push(Mismatch::Kind {
+                    field: field.name.clone(),
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Rust type mirroring a kernel struct or union, whose fields are read from raw
+/// kernel memory at offsets resolved at runtime using [`BtfOffsets`]. Usually
+/// implemented using `#[derive(FromBtf)]`, which also generates `BTF_<FIELD>`
+/// constants holding the index of each field.
+pub trait FromBtf: Sized {
+    /// Name of the struct or union.
+    const BTF_NAME: &'static str;
+    /// Names of the fields and sizes in bytes of their Rust representation, in
+    /// declaration order.
+    const BTF_FIELDS: &'static [(&'static str, usize)];
+
+    /// Build the object from the raw memory of the struct, given the offsets
+    /// of the fields in the `BTF_FIELDS` order.
+    fn from_raw(raw: &[u8], offsets: &[usize]) -> Option<Self>;
+}
+
+/// Field types of [`FromBtf`] objects, which can be read from raw kernel memory
+/// using the native endianness.
+pub trait RawField: Sized {
+    /// Read the field from the start of `raw`, which can be longer than the
+    /// field. Returns `None` if it is too short.
+    fn from_raw(raw: &[u8]) -> Option<Self>;
+}
+
+macro_rules! raw_field_int {
+    ($($int:ty),*) => {
+        $(
+            impl RawField for $int {
+                fn from_raw(raw: &[u8]) -> Option<Self> {
+                    Some(<$int>::from_ne_bytes(
+                        raw.get(..std::mem::size_of::<$int>())?.try_into().ok()?,
+                    ))
+                }
+            }
+        )*
+    };
+}
+
+raw_field_int!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl<const N: usize> RawField for [u8; N] {
+    fn from_raw(raw: &[u8]) -> Option<Self> {
+        raw.get(..N)?.try_into().ok()
+    }
+}
+
+/// Offsets of the fields of a [`FromBtf`] object, resolved using BTF.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BtfOffsets<T> {
+    offsets: Vec<usize>,
+    size: usize,
+    r#type: PhantomData<T>,
+}
+
+impl<T: FromBtf> BtfOffsets<T> {
+    /// Resolve the offsets of the fields of `T`, checking they are not
+    /// bitfields and their size matches the size of their Rust
+    /// representation. Fields of anonymous struct and union members can be
+    /// used directly.
+    pub fn resolve(btf: &Btf) -> Result<BtfOffsets<T>> {
+        let r#struct = btf.resolve_struct_by_name(T::BTF_NAME)?;
+
+        let mut offsets = Vec::with_capacity(T::BTF_FIELDS.len());
+        for (name, size) in T::BTF_FIELDS {
+            let (member, offset) = r#struct
+                .member_by_name(btf, name)?
+                .ok_or_else(|| anyhow!("No field {name} in {}", T::BTF_NAME))?;
+            if member.is_bitfield() {
+                bail!("Field {name} of {} is a bitfield", T::BTF_NAME);
+            }
+
+            let found = btf.resolve_type_size(&btf.resolve_chained_type(&member)?)?;
+            if found != *size {
+                bail!(
+                    "Field {name} of {} is {found} bytes, expected {size}",
+                    T::BTF_NAME
+                );
+            }
+            offsets.push(offset as usize / 8);
+        }
+
+        Ok(BtfOffsets {
+            offsets,
+            size: r#struct.size(),
+            r#type: PhantomData,
+        })
+    }
+
+    /// Get the offset in bytes of a field, given its index in
+    /// `FromBtf::BTF_FIELDS` (see the generated `BTF_<FIELD>` constants).
+    pub fn offset(&self, field: usize) -> usize {
+        self.offsets[field]
+    }
+
+    /// Get the size of the struct or union in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Read an object from the raw memory of the struct, which must be at
+    /// least `size()` bytes long.
+    pub fn read(&self, raw: &[u8]) -> Result<T> {
+        if raw.len() < self.size {
+            bail!(
+                "Raw {} is {} bytes, expected at least {}",
+                T::BTF_NAME,
+                raw.len(),
+                self.size
+            );
+        }
+        T::from_raw(raw, &self.offsets).ok_or_else(|| anyhow!("Could not read {}", T::BTF_NAME))
+    }
+}
@@ -0,0 +1,102 @@
+//! ### Name lookups using regular expressions
+//!
+//! [`NameMatcher`] finds the types whose name matches any of a set of regular
+//! expressions, in a [`Btf`] object or in a whole [`BtfCollection`]. As
+//! scanning all the names of a collection is costly, the matcher caches the
+//! results for each BTF object it scanned. Those are reused by later scans as
+//! long as the BTF object is not changed (e.g. a split BTF replaced in the
+//! collection), which makes periodic scans of a collection cheap.
+//!
+//! ```no_run
+//! use btf_rs::utils::{collection::BtfCollection, matcher::NameMatcher};
+//!
+//! let btfc = BtfCollection::from_dir("/sys/kernel/btf", "vmlinux").unwrap();
+//! let matcher = NameMatcher::new(["^tcp_v4_", "^udp_rcv$"]).unwrap();
+//!
+//! for (nbtf, id) in matcher.scan_collection(&btfc) {
+//!     println!("{}: {:?}", nbtf.name, nbtf.resolve_type_by_id(id).unwrap());
+//! }
+//! ```
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+};
+
+use anyhow::Result;
+use regex::RegexSet;
+
+use crate::{
+    utils::collection::{BtfCollection, NamedBtf},
+    Btf, TypeId,
+};
+
+/// Set of regular expressions matched against type names, caching the results
+/// per BTF object.
+pub struct NameMatcher {
+    set: RegexSet,
+    /// Ids of the matching types, keyed by the fingerprint of the BTF object
+    /// they were found in.
+    cache: Mutex<HashMap<u64, Vec<TypeId>>>,
+}
+
+impl NameMatcher {
+    /// Build a matcher from a list of regular expressions. A name matches if
+    /// any of the expressions matches it.
+    pub fn new<I, S>(patterns: I) -> Result<NameMatcher>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Ok(NameMatcher {
+            set: RegexSet::new(patterns)?,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Find the ids of the types whose name matches in a BTF object, sorted.
+    /// For split BTF objects, types of the base BTF are not part of the
+    /// results.
+    pub fn scan(&self, btf: &Btf) -> Vec<TypeId> {
+        let key = btf.fingerprint();
+        if let Some(ids) = self.cache().get(&key) {
+            return ids.clone();
+        }
+
+        let mut ids: Vec<TypeId> = btf
+            .split_names()
+            .filter(|name| self.set.is_match(name))
+            .flat_map(|name| {
+                btf.split_ids_by_name(name)
+                    .iter()
+                    .map(|id| TypeId::from(*id))
+            })
+            .collect();
+        ids.sort_unstable();
+
+        self.cache().insert(key, ids.clone());
+        ids
+    }
+
+    /// Find the ids of the types whose name matches in all the BTF objects of
+    /// a collection, base first. Cached results of BTF objects no longer part
+    /// of the collection are dropped.
+    pub fn scan_collection<'a>(&self, btfc: &'a BtfCollection) -> Vec<(&'a NamedBtf, TypeId)> {
+        let nbtfs: Vec<&NamedBtf> = std::iter::once(btfc.base())
+            .chain(btfc.split_btfs())
+            .collect();
+
+        self.cache()
+            .retain(|key, _| nbtfs.iter().any(|nbtf| nbtf.fingerprint() == *key));
+
+        nbtfs
+            .into_iter()
+            .flat_map(|nbtf| self.scan(nbtf).into_iter().map(move |id| (nbtf, id)))
+            .collect()
+    }
+
+    // Get the results cache, recovering from poisoning as the cache is always
+    // left in a consistent state.
+    fn cache(&self) -> MutexGuard<'_, HashMap<u64, Vec<TypeId>>> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -1,6 +1,32 @@
 //! Utils built on top of the `btf_rs` library to ease the development in common
 //! use cases.
 
+pub mod alias;
+pub mod arch;
+pub mod asm;
+pub mod attach;
+pub mod codegen;
 pub mod collection;
+pub mod compat;
+pub mod debug;
+pub mod diff;
+pub mod dump;
 #[cfg(feature = "elf")]
 pub mod elf;
+#[cfg(feature = "json")]
+pub mod export;
+pub mod layout;
+#[cfg(feature = "regex")]
+pub mod matcher;
+pub mod moddep;
+pub mod net;
+pub mod printer;
+pub mod probe;
+pub mod raw;
+pub mod reloc;
+#[cfg(feature = "serde")]
+pub mod resolved;
+pub mod resolver;
+#[cfg(target_os = "linux")]
+pub mod sys;
+pub mod syscalls;
@@ -0,0 +1,181 @@
+//! ### Kernel module dependencies
+//!
+//! Kernel module directories (e.g. `/usr/lib/modules/$(uname -r)`) contain a
+//! `modules.dep` file listing the dependencies of each module. [`ModuleDeps`]
+//! parses it so split BTF objects can be processed in dependency order, and
+//! types defined in a module attributed to its dependency chain.
+//!
+//! ```no_run
+//! use btf_rs::utils::{collection::BtfCollection, moddep::ModuleDeps};
+//!
+//! let btfc = BtfCollection::from_dir("/sys/kernel/btf", "vmlinux").unwrap();
+//! let deps = ModuleDeps::from_kernel_dir("/usr/lib/modules/6.8.0").unwrap();
+//!
+//! for module in deps.sort_collection(&btfc).unwrap() {
+//!     println!("{}", module.name);
+//! }
+//! ```
+//!
+//! Modules are identified by their file name stripped from its extensions,
+//! with dashes converted to underscores as `modprobe` does (e.g.
+//! `vport-gre.ko` is `vport_gre`). This matches the names found in
+//! `/sys/kernel/btf` and the ones used by
+//! `utils::elf::collection_from_kernel_dir` (when the `elf` feature is enabled).
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::utils::collection::{BtfCollection, NamedBtf};
+
+/// Module dependency information, as found in a `modules.dep` file.
+#[derive(Clone, Debug, Default)]
+pub struct ModuleDeps {
+    // Map from module names to their direct dependencies, in the order they
+    // are listed.
+    deps: BTreeMap<String, Vec<String>>,
+}
+
+impl ModuleDeps {
+    /// Parse the `modules.dep` file found in the given kernel module directory.
+    pub fn from_kernel_dir<P: AsRef<Path>>(dir: P) -> Result<ModuleDeps> {
+        ModuleDeps::from_file(dir.as_ref().join("modules.dep"))
+    }
+
+    /// Parse a `modules.dep` file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ModuleDeps> {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Could not read {}: {e}", path.as_ref().display()))?;
+        ModuleDeps::parse(&content)
+    }
+
+    /// Parse the content of a `modules.dep` file. Each line is of the form
+    /// `path/to/module.ko: path/to/dep1.ko path/to/dep2.ko`.
+    pub fn parse(content: &str) -> Result<ModuleDeps> {
+        let mut deps = BTreeMap::new();
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (module, list) = line
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid modules.dep line {}: missing ':'", i + 1))?;
+
+            deps.insert(
+                module_name(module)?,
+                list.split_whitespace()
+                    .map(module_name)
+                    .collect::<Result<_>>()?,
+            );
+        }
+
+        Ok(ModuleDeps { deps })
+    }
+
+    /// Iterate over the known module names, in alphabetical order.
+    pub fn modules(&self) -> impl Iterator<Item = &str> {
+        self.deps.keys().map(|m| m.as_str())
+    }
+
+    /// Get the direct dependencies of a module. Returns `None` if the module is
+    /// unknown.
+    pub fn dependencies(&self, module: &str) -> Option<&[String]> {
+        self.deps.get(module).map(|d| d.as_slice())
+    }
+
+    /// Get the full dependency chain of a module, that is all the modules it
+    /// depends on directly or not, dependencies first. The module itself is
+    /// not part of the returned list.
+    pub fn dependency_chain(&self, module: &str) -> Result<Vec<&str>> {
+        let (module, _) = self
+            .deps
+            .get_key_value(module)
+            .ok_or_else(|| anyhow!("Unknown module {module}"))?;
+
+        let mut chain = Vec::new();
+        self.visit(module, &mut HashSet::new(), &mut HashSet::new(), &mut chain)?;
+        chain.pop();
+        Ok(chain)
+    }
+
+    /// Get all the known modules sorted so that each module comes after all
+    /// its dependencies. Ties are broken alphabetically.
+    pub fn load_order(&self) -> Result<Vec<&str>> {
+        let mut order = Vec::new();
+        let mut done = HashSet::new();
+
+        for module in self.deps.keys() {
+            self.visit(module, &mut done, &mut HashSet::new(), &mut order)?;
+        }
+        Ok(order)
+    }
+
+    /// Sort the split BTF objects of a collection in dependency order. Objects
+    /// not described in the dependency information come last, in the order
+    /// they are found in the collection.
+    pub fn sort_collection<'a>(&self, btfc: &'a BtfCollection) -> Result<Vec<&'a NamedBtf>> {
+        let mut sorted = Vec::new();
+        for module in self.load_order()? {
+            if let Some(nbtf) = btfc.get_named_btf(module) {
+                sorted.push(nbtf);
+            }
+        }
+
+        btfc.split_btfs()
+            .filter(|nbtf| !self.deps.contains_key(&nbtf.name))
+            .for_each(|nbtf| sorted.push(nbtf));
+
+        Ok(sorted)
+    }
+
+    // Depth-first visit of a module dependencies, adding the modules to `out`
+    // in post-order. `done` holds modules already added to `out` and `stack`
+    // the ones being visited, used to detect cycles.
+    fn visit<'a>(
+        &'a self,
+        module: &'a str,
+        done: &mut HashSet<&'a str>,
+        stack: &mut HashSet<&'a str>,
+        out: &mut Vec<&'a str>,
+    ) -> Result<()> {
+        if done.contains(module) {
+            return Ok(());
+        }
+        if !stack.insert(module) {
+            bail!("Dependency cycle involving module {module}");
+        }
+
+        // Dependencies not having their own entry are still reported.
+        if let Some(deps) = self.deps.get(module) {
+            for dep in deps.iter() {
+                self.visit(dep, done, stack, out)?;
+            }
+        }
+
+        stack.remove(module);
+        done.insert(module);
+        out.push(module);
+        Ok(())
+    }
+}
+
+// Extract a module name from its path, e.g. `kernel/net/openvswitch.ko.xz`
+// gives `openvswitch` and `net/openvswitch/vport-gre.ko` gives `vport_gre`.
+fn module_name(path: &str) -> Result<String> {
+    let filename = Path::new(path)
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| anyhow!("Could not get module name from {path}"))?;
+
+    Ok(match filename.split_once('.') {
+        Some((name, _)) => name,
+        None => filename,
+    }
+    .replace('-', "_"))
+}
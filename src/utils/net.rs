@@ -0,0 +1,85 @@
+//! ### Networking helpers
+//!
+//! Networking tracers often need the same handful of kernel definitions, e.g.
+//! the offsets of some `struct sk_buff` fields to read packet data or the
+//! names of the skb drop reasons. Those change across kernel versions and
+//! configurations, so [`NetInfo`] computes them from the loaded BTF instead of
+//! relying on hardcoded values.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::net::NetInfo};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! let info = NetInfo::from_btf(&btf).unwrap();
+//!
+//! println!("skb->len is at offset {}", info.skb_len);
+//! println!("drop reason 2 is {:?}", info.drop_reasons.get(&2));
+//! ```
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+
+use crate::{Btf, Struct, Type};
+
+/// Networking related definitions, computed from BTF. Offsets are in bytes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NetInfo {
+    /// Offset of `len` in `struct sk_buff`.
+    pub skb_len: usize,
+    /// Offset of `data_len` in `struct sk_buff`.
+    pub skb_data_len: usize,
+    /// Offset of `head` in `struct sk_buff`.
+    pub skb_head: usize,
+    /// Offset of `data` in `struct sk_buff`.
+    pub skb_data: usize,
+    /// Offset of `dev` in `struct sk_buff`.
+    pub skb_dev: usize,
+    /// Offset of `name` in `struct net_device`, i.e. `skb->dev->name` is at
+    /// this offset from the value of `skb->dev`.
+    pub dev_name: usize,
+    /// Values of `enum skb_drop_reason` and their names (e.g.
+    /// `SKB_DROP_REASON_NOT_SPECIFIED`). Empty on kernels not defining it.
+    pub drop_reasons: BTreeMap<i64, String>,
+}
+
+impl NetInfo {
+    /// Compute the networking definitions from a BTF object, which must
+    /// describe at least `struct sk_buff` and `struct net_device`.
+    pub fn from_btf(btf: &Btf) -> Result<NetInfo> {
+        let skb = btf.resolve_struct_by_name("sk_buff")?;
+        let dev = btf.resolve_struct_by_name("net_device")?;
+
+        let mut drop_reasons = BTreeMap::new();
+        if let Some(Type::Enum(reasons)) = btf
+            .resolve_types_by_name("skb_drop_reason")
+            .unwrap_or_default()
+            .into_iter()
+            .find(|t| matches!(t, Type::Enum(_)))
+        {
+            for value in reasons.values(btf) {
+                let (name, value) = value?;
+                drop_reasons.insert(value, name);
+            }
+        }
+
+        Ok(NetInfo {
+            skb_len: member_offset(btf, &skb, "sk_buff", "len")?,
+            skb_data_len: member_offset(btf, &skb, "sk_buff", "data_len")?,
+            skb_head: member_offset(btf, &skb, "sk_buff", "head")?,
+            skb_data: member_offset(btf, &skb, "sk_buff", "data")?,
+            skb_dev: member_offset(btf, &skb, "sk_buff", "dev")?,
+            dev_name: member_offset(btf, &dev, "net_device", "name")?,
+            drop_reasons,
+        })
+    }
+}
+
+// Get the offset in bytes of a struct member given its name, looking into
+// anonymous struct and union members.
+fn member_offset(btf: &Btf, r#struct: &Struct, struct_name: &str, name: &str) -> Result<usize> {
+    match r#struct.member_by_name(btf, name)? {
+        Some((_, offset)) if offset % 8 == 0 => Ok(offset as usize / 8),
+        Some(_) => bail!("Member {name} of struct {struct_name} is not byte aligned"),
+        None => bail!("No member {name} in struct {struct_name}"),
+    }
+}
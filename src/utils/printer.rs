@@ -0,0 +1,228 @@
+//! ### C declarations
+//!
+//! Helpers rendering types as C declarations, similar to `bpftool btf dump
+//! file <file> format c`. Those take care of walking the chain of types and of
+//! ordering qualifiers, pointers, arrays and function prototypes the way C
+//! expects them.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::printer::{decl_to_c, definition_to_c}};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//!
+//! // Prints "void kfree_skb_reason(struct sk_buff *skb, enum skb_drop_reason reason)".
+//! let func = btf.resolve_types_by_name("kfree_skb_reason").unwrap().pop().unwrap();
+//! println!("{}", decl_to_c(&btf, &func, "").unwrap());
+//!
+//! // Prints the full definition of struct sk_buff, with the member offsets.
+//! let skb = btf.resolve_types_by_name("sk_buff").unwrap().pop().unwrap();
+//! println!("{}", definition_to_c(&btf, &skb).unwrap());
+//! ```
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+
+use crate::{Btf, EnumValue, Struct, Type};
+
+/// Render a type as a C type name, e.g. `const struct sk_buff *`.
+pub fn type_to_c(btf: &Btf, r#type: &Type) -> Result<String> {
+    declaration(btf, r#type, String::new())
+}
+
+/// Render the declaration of an object of a given type and name, e.g. `const
+/// struct sk_buff *skb` or `char name[16]`. Functions and variables are
+/// declared using their own name if `name` is empty.
+pub fn decl_to_c(btf: &Btf, r#type: &Type, name: &str) -> Result<String> {
+    declaration(btf, r#type, name.to_string())
+}
+
+/// Render the full definition of a type, terminated by a semicolon. Structs
+/// and unions are rendered with all their members, anonymous ones being
+/// inlined, and the offset of each member in a comment (`/* byte */`, or
+/// `/* byte: bit */` for bitfields). Enums are rendered with all their values
+/// and typedefs, functions and variables as their declaration. Other types are
+/// rendered as in [`type_to_c`].
+pub fn definition_to_c(btf: &Btf, r#type: &Type) -> Result<String> {
+    Ok(match r#type {
+        Type::Struct(s) | Type::Union(s) => format!("{};", composite(btf, r#type, s, 0, 0)?),
+        Type::Enum(e) => {
+            let values = e
+                .members
+                .iter()
+                .map(|m| Ok((btf.resolve_name(m)?, m.value(e))))
+                .collect::<Result<Vec<_>>>()?;
+            enumeration(btf, r#type, values)?
+        }
+        Type::Enum64(e) => {
+            let values = e
+                .members
+                .iter()
+                .map(|m| Ok((btf.resolve_name(m)?, m.value(e))))
+                .collect::<Result<Vec<_>>>()?;
+            enumeration(btf, r#type, values)?
+        }
+        Type::Typedef(td) => format!(
+            "typedef {};",
+            declaration(btf, &btf.resolve_chained_type(td)?, btf.resolve_name(td)?)?
+        ),
+        Type::Fwd(_) | Type::Func(_) | Type::Var(_) => format!("{};", type_to_c(btf, r#type)?),
+        _ => type_to_c(btf, r#type)?,
+    })
+}
+
+// Render the declaration of `inner` (an identifier, possibly decorated with
+// pointers, arrays, etc.) having type `r#type`. C declarators are built
+// inside-out: each type of the chain wraps the declarator built so far.
+fn declaration(btf: &Btf, r#type: &Type, inner: String) -> Result<String> {
+    let named = |base: String| match inner.is_empty() || inner.starts_with('[') {
+        true => format!("{base}{inner}"),
+        false => format!("{base} {inner}"),
+    };
+
+    Ok(match r#type {
+        Type::Void => named("void".to_string()),
+        Type::Int(_) | Type::Float(_) | Type::Typedef(_) => named(btf.resolve_name(r#type)?),
+        Type::Struct(_) => named(tagged("struct", btf.resolve_name(r#type)?)),
+        Type::Union(_) => named(tagged("union", btf.resolve_name(r#type)?)),
+        Type::Enum(_) | Type::Enum64(_) => named(tagged("enum", btf.resolve_name(r#type)?)),
+        Type::Fwd(f) => named(tagged(
+            match f.is_union() {
+                true => "union",
+                false => "struct",
+            },
+            btf.resolve_name(f)?,
+        )),
+        Type::Ptr(p) => {
+            let target = btf.resolve_chained_type(p)?;
+            let inner = match target {
+                Type::Array(_) | Type::FuncProto(_) => format!("(*{inner})"),
+                // Pointers returned by function pointers, rendered as bpftool
+                // does: `struct sk_buff * (*fn)(void)`.
+                _ if inner.starts_with('(') => format!("* {inner}"),
+                _ => format!("*{inner}"),
+            };
+            declaration(btf, &target, inner)?
+        }
+        Type::Const(q) | Type::Volatile(q) | Type::Restrict(q) => {
+            let qualifier = match r#type {
+                Type::Const(_) => "const",
+                Type::Volatile(_) => "volatile",
+                _ => "restrict",
+            };
+            // Qualifiers of pointers are placed after the '*'.
+            match btf.resolve_chained_type(q)? {
+                target @ Type::Ptr(_) => match inner.is_empty() {
+                    true => declaration(btf, &target, qualifier.to_string())?,
+                    false => declaration(btf, &target, format!("{qualifier} {inner}"))?,
+                },
+                target => format!("{qualifier} {}", declaration(btf, &target, inner)?),
+            }
+        }
+        // Type tags have no C representation.
+        Type::TypeTag(tt) => declaration(btf, &btf.resolve_chained_type(tt)?, inner)?,
+        Type::Array(a) => declaration(
+            btf,
+            &btf.resolve_chained_type(a)?,
+            format!("{inner}[{}]", a.len()),
+        )?,
+        Type::FuncProto(fp) => {
+            let params = fp
+                .parameters
+                .iter()
+                .map(|p| match p.is_variadic() {
+                    true => Ok("...".to_string()),
+                    false => declaration(btf, &p.r#type(btf)?, btf.resolve_name(p)?),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let params = match params.is_empty() {
+                true => "void".to_string(),
+                false => params.join(", "),
+            };
+            declaration(btf, &fp.return_type(btf)?, format!("{inner}({params})"))?
+        }
+        Type::Func(_) | Type::Var(_) => {
+            let inner = match inner.is_empty() {
+                true => btf.resolve_name(r#type)?,
+                false => inner,
+            };
+            declaration(btf, &btf.resolve_chained_type(r#type)?, inner)?
+        }
+        Type::Datasec(_) | Type::DeclTag(_) => {
+            bail!("A {} has no C representation", r#type.name())
+        }
+    })
+}
+
+// Render the body of a struct or union, with the given indentation level.
+// Offsets are relative to the outermost struct, `base` being the offset in
+// bits of the current one.
+fn composite(
+    btf: &Btf,
+    r#type: &Type,
+    r#struct: &Struct,
+    indent: usize,
+    base: u32,
+) -> Result<String> {
+    let keyword = match r#type {
+        Type::Union(_) => "union",
+        _ => "struct",
+    };
+    let mut out = match btf.resolve_name_ref(r#struct)? {
+        "" => format!("{keyword} {{\n"),
+        name => format!("{keyword} {name} {{\n"),
+    };
+
+    for member in r#struct.members.iter() {
+        let offset = base + member.bit_offset();
+        let name = btf.resolve_name(member)?;
+
+        let target = btf.resolve_chained_type(member)?;
+        let mut decl = match &target {
+            // Anonymous structs and unions are defined inline.
+            Type::Struct(s) | Type::Union(s) if btf.resolve_name_ref(s)?.is_empty() => {
+                let body = composite(btf, &target, s, indent + 1, offset)?;
+                match name.is_empty() {
+                    true => body,
+                    false => format!("{body} {name}"),
+                }
+            }
+            _ => declaration(btf, &target, name)?,
+        };
+
+        let comment = match member.bitfield_size() {
+            Some(size) if size != 0 => {
+                write!(decl, ": {size}")?;
+                format!("{}: {}", offset / 8, offset % 8)
+            }
+            _ => format!("{}", offset / 8),
+        };
+        writeln!(out, "{}{decl}; /* {comment} */", "\t".repeat(indent + 1))?;
+    }
+
+    write!(out, "{}}}", "\t".repeat(indent))?;
+    Ok(out)
+}
+
+// Render the definition of an enum, given its values.
+fn enumeration(btf: &Btf, r#type: &Type, values: Vec<(String, EnumValue)>) -> Result<String> {
+    let mut out = match btf.resolve_name_ref(r#type)? {
+        "" => "enum {\n".to_string(),
+        name => format!("enum {name} {{\n"),
+    };
+    for (name, value) in values {
+        match value {
+            EnumValue::Signed(val) => writeln!(out, "\t{name} = {val},")?,
+            EnumValue::Unsigned(val) => writeln!(out, "\t{name} = {val},")?,
+        }
+    }
+    out.push_str("};");
+    Ok(out)
+}
+
+// Prefix a struct, union or enum name with its keyword.
+fn tagged(keyword: &str, name: String) -> String {
+    match name.is_empty() {
+        true => format!("{keyword} {{...}}"),
+        false => format!("{keyword} {name}"),
+    }
+}
@@ -0,0 +1,331 @@
+//! ### BTF availability diagnostics
+//!
+//! Finding BTF information on a system can fail for many reasons: the kernel
+//! being built without `CONFIG_DEBUG_INFO_BTF`, missing permissions, debuginfo
+//! files stripped from their .BTF section, etc. [`report`] checks the common
+//! locations and returns a structured [`Report`] which can be used to give
+//! end-users meaningful error messages.
+//!
+//! ```no_run
+//! use btf_rs::utils::probe;
+//!
+//! let report = probe::report();
+//! if let Some(reason) = report.reason() {
+//!     eprintln!("BTF is not available: {reason}");
+//! }
+//! ```
+//!
+//! Debuginfo files and modules are only inspected when the `elf` feature is
+//! enabled. The kernel configuration is read from `/boot/config-<release>`, or
+//! from `/proc/config.gz` when the `gzip` feature is enabled.
+use std::{
+    fmt, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::Btf;
+
+/// Status of a single probed location.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Status {
+    /// BTF information was found and is usable.
+    Available,
+    /// The location does not exist.
+    Missing,
+    /// The location exists but can't be read by the current user.
+    PermissionDenied,
+    /// The location exists but does not contain BTF information (e.g. a
+    /// stripped ELF file).
+    NoBtf,
+    /// The location exists but its content could not be parsed.
+    Invalid(String),
+}
+
+/// A probed location and its status.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Location {
+    /// Path of the location.
+    pub path: PathBuf,
+    /// Status of the location.
+    pub status: Status,
+}
+
+impl Location {
+    /// Is BTF information available at this location?
+    pub fn is_available(&self) -> bool {
+        self.status == Status::Available
+    }
+}
+
+/// Report describing BTF availability on a system.
+#[derive(Clone, Debug)]
+pub struct Report {
+    /// Release of the kernel the report applies to, if it could be retrieved.
+    pub release: Option<String>,
+    /// Whether the kernel was built with `CONFIG_DEBUG_INFO_BTF`, if its
+    /// configuration could be found.
+    pub config_btf: Option<bool>,
+    /// Kernel BTF exposed in sysfs (`/sys/kernel/btf/vmlinux`).
+    pub vmlinux: Location,
+    /// Number of split BTF objects exposed in sysfs for modules.
+    pub sysfs_modules: usize,
+    /// Kernel modules directory (`/usr/lib/modules/<release>`).
+    pub modules_dir: Location,
+    /// A module of the modules directory, probed for a .BTF section. `None` if
+    /// no module was found.
+    pub module: Option<Location>,
+    /// Candidate debuginfo ELF files for the kernel.
+    pub debuginfo: Vec<Location>,
+}
+
+impl Report {
+    /// Is kernel BTF information available in any of the probed locations?
+    pub fn is_available(&self) -> bool {
+        self.vmlinux.is_available() || self.debuginfo.iter().any(|l| l.is_available())
+    }
+
+    /// Give the most likely reason why kernel BTF information could not be
+    /// found. Returns `None` if it is available.
+    pub fn reason(&self) -> Option<String> {
+        if self.is_available() {
+            return None;
+        }
+
+        let path = self.vmlinux.path.display();
+        Some(match &self.vmlinux.status {
+            Status::PermissionDenied => {
+                format!("permission denied while reading {path}")
+            }
+            Status::Invalid(e) => format!("could not parse {path}: {e}"),
+            _ if self.config_btf == Some(false) => {
+                "the kernel was built without CONFIG_DEBUG_INFO_BTF".to_string()
+            }
+            _ if self.debuginfo.iter().any(|l| l.status == Status::NoBtf) => {
+                format!("{path} does not exist and debuginfo files do not contain a .BTF section")
+            }
+            _ => format!("{path} does not exist and no debuginfo file was found"),
+        })
+    }
+
+    /// Give the most likely reason why BTF information for modules could not
+    /// be found. Returns `None` if it is available, or if no module could be
+    /// inspected.
+    pub fn modules_reason(&self) -> Option<String> {
+        if self.sysfs_modules > 0 {
+            return None;
+        }
+
+        let module = self.module.as_ref()?;
+        let path = module.path.display();
+        match &module.status {
+            Status::NoBtf => Some(format!(
+                "{path} does not contain a .BTF section, modules were likely stripped"
+            )),
+            Status::PermissionDenied => Some(format!("permission denied while reading {path}")),
+            Status::Invalid(e) => Some(format!("could not parse {path}: {e}")),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "kernel release: {}",
+            self.release.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            f,
+            "CONFIG_DEBUG_INFO_BTF: {}",
+            match self.config_btf {
+                Some(true) => "y",
+                Some(false) => "n",
+                None => "unknown",
+            }
+        )?;
+        writeln!(
+            f,
+            "{}: {:?}",
+            self.vmlinux.path.display(),
+            self.vmlinux.status
+        )?;
+        writeln!(f, "split BTF in sysfs: {}", self.sysfs_modules)?;
+        writeln!(
+            f,
+            "{}: {:?}",
+            self.modules_dir.path.display(),
+            self.modules_dir.status
+        )?;
+        if let Some(l) = &self.module {
+            writeln!(f, "{}: {:?}", l.path.display(), l.status)?;
+        }
+        for l in self.debuginfo.iter() {
+            writeln!(f, "{}: {:?}", l.path.display(), l.status)?;
+        }
+        Ok(())
+    }
+}
+
+/// Probe the running system for BTF information.
+pub fn report() -> Report {
+    report_from("/")
+}
+
+/// Probe for BTF information using `root` as the root of the file system.
+/// This is useful when the host file system is mounted in a sub-directory,
+/// e.g. when running in a container.
+pub fn report_from<P: AsRef<Path>>(root: P) -> Report {
+    let root = root.as_ref();
+
+    let release = fs::read_to_string(root.join("proc/sys/kernel/osrelease"))
+        .ok()
+        .map(|r| r.trim().to_string());
+
+    let config_btf = release
+        .as_ref()
+        .and_then(|r| fs::read_to_string(root.join(format!("boot/config-{r}"))).ok())
+        .or_else(|| proc_config(root))
+        .map(|config| config.lines().any(|l| l == "CONFIG_DEBUG_INFO_BTF=y"));
+
+    let vmlinux = probe(root.join("sys/kernel/btf/vmlinux"), |p| {
+        Btf::from_file(p).map(|_| Status::Available)
+    });
+
+    let sysfs_modules = fs::read_dir(root.join("sys/kernel/btf"))
+        .map(|dir| {
+            dir.filter_map(|e| e.ok())
+                .filter(|e| e.file_name() != "vmlinux")
+                .count()
+        })
+        .unwrap_or(0);
+
+    let modules_dir = match &release {
+        Some(r) => probe(root.join(format!("usr/lib/modules/{r}")), |p| {
+            fs::read_dir(p)?;
+            Ok(Status::Available)
+        }),
+        None => Location {
+            path: root.join("usr/lib/modules"),
+            status: Status::Missing,
+        },
+    };
+
+    #[cfg(feature = "elf")]
+    let module = match modules_dir.status {
+        Status::Available => find_module(&modules_dir.path, 0).map(|path| {
+            probe(path, |p| {
+                Ok(match crate::utils::elf::has_btf_section(p)? {
+                    true => Status::Available,
+                    false => Status::NoBtf,
+                })
+            })
+        }),
+        _ => None,
+    };
+    #[cfg(not(feature = "elf"))]
+    let module = None;
+
+    #[cfg(feature = "elf")]
+    let debuginfo = match &release {
+        Some(r) => [
+            format!("usr/lib/debug/boot/vmlinux-{r}"),
+            format!("usr/lib/debug/lib/modules/{r}/vmlinux"),
+            format!("usr/lib/modules/{r}/build/vmlinux"),
+            format!("boot/vmlinux-{r}"),
+        ]
+        .iter()
+        .map(|p| {
+            probe(root.join(p), |p| {
+                Ok(match crate::utils::elf::has_btf_section(p)? {
+                    true => Status::Available,
+                    false => Status::NoBtf,
+                })
+            })
+        })
+        .filter(|l| l.status != Status::Missing)
+        .collect(),
+        None => Vec::new(),
+    };
+    #[cfg(not(feature = "elf"))]
+    let debuginfo = Vec::new();
+
+    Report {
+        release,
+        config_btf,
+        vmlinux,
+        sysfs_modules,
+        modules_dir,
+        module,
+        debuginfo,
+    }
+}
+
+// Read the kernel configuration exposed in /proc/config.gz.
+#[cfg(feature = "gzip")]
+fn proc_config(root: &Path) -> Option<String> {
+    use std::io::Read;
+
+    let file = fs::File::open(root.join("proc/config.gz")).ok()?;
+    let mut config = String::new();
+    flate2::read::GzDecoder::new(file)
+        .read_to_string(&mut config)
+        .ok()?;
+    Some(config)
+}
+
+#[cfg(not(feature = "gzip"))]
+fn proc_config(_: &Path) -> Option<String> {
+    None
+}
+
+// Find the first uncompressed module below a directory, visiting entries in
+// file name order.
+#[cfg(feature = "elf")]
+fn find_module(dir: &Path, depth: usize) -> Option<PathBuf> {
+    if depth > crate::utils::elf::MAX_KERNEL_DIR_DEPTH {
+        return None;
+    }
+
+    let mut entries: Vec<_> = fs::read_dir(dir).ok()?.filter_map(|e| e.ok()).collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        // Do not follow symlinks, e.g. the build and source links.
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => {
+                if let Some(module) = find_module(&path, depth + 1) {
+                    return Some(module);
+                }
+            }
+            Ok(t) if t.is_file() && entry.file_name().to_string_lossy().ends_with(".ko") => {
+                return Some(path)
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+// Probe a single location, using `check` to inspect its content once it is
+// known to exist and be readable.
+fn probe<F>(path: PathBuf, check: F) -> Location
+where
+    F: Fn(&Path) -> anyhow::Result<Status>,
+{
+    let status = match fs::metadata(&path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => Status::Missing,
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => Status::PermissionDenied,
+        Err(e) => Status::Invalid(e.to_string()),
+        Ok(_) => match check(&path) {
+            Ok(status) => status,
+            Err(e) => match e.downcast_ref::<std::io::Error>() {
+                Some(e) if e.kind() == ErrorKind::PermissionDenied => Status::PermissionDenied,
+                _ => Status::Invalid(e.to_string()),
+            },
+        },
+    };
+
+    Location { path, status }
+}
@@ -0,0 +1,113 @@
+//! ### Parsing raw binary records
+//!
+//! BTF related formats (e.g. the records of the `.BTF.ext` ELF section) are
+//! made of packed C structs, stored using the byte order of the BTF data they
+//! come with. [`CBtfType`] types can be read from such raw data given its
+//! [`Endianness`]. It is implemented for integer types and arrays of
+//! `CBtfType` types, and can be derived for structs made of such types using
+//! `#[derive(CBtfType)]` (requires the derive feature); fields are then read
+//! in declaration order, without padding. The size of the raw representation
+//! is known at compile time (`CBtfType::SIZE`), which allows to skip records
+//! without parsing them.
+//!
+//! Objects can also be read directly from a byte slice using
+//! `CBtfType::from_bytes()`, which returns the number of bytes consumed. This
+//! allows to sequentially parse a buffer (e.g. a memory mapped file) without
+//! wrapping it in a `Cursor` and seeking around.
+//!
+//! ```ignore
+//! use btf_rs::{utils::raw::CBtfType, Endianness};
+//!
+//! #[derive(CBtfType)]
+//! struct FuncInfo {
+//!     insn_off: u32,
+//!     type_id: u32,
+//! }
+//!
+//! let info = FuncInfo::from_reader(&mut raw.as_slice(), btf.endianness()).unwrap();
+//!
+//! // Or, keeping track of the position in the buffer.
+//! let (info, len) = FuncInfo::from_bytes(&raw[pos..], btf.endianness()).unwrap();
+//! pos += len;
+//! ```
+use std::io::{Error, Read, Result};
+
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+
+#[cfg(feature = "derive")]
+pub use btf_rs_derive::CBtfType;
+
+use crate::Endianness;
+
+/// Types which can be read from raw data in a given byte order.
+pub trait CBtfType: Sized {
+    /// Size in bytes of the raw representation.
+    const SIZE: usize;
+
+    /// Read an object from `reader`.
+    fn from_reader<R: Read>(reader: &mut R, endianness: Endianness) -> Result<Self>;
+
+    /// Read an object from the start of `bytes`. On success, returns the
+    /// object and the number of bytes consumed.
+    fn from_bytes(mut bytes: &[u8], endianness: Endianness) -> Result<(Self, usize)> {
+        let len = bytes.len();
+        let obj = Self::from_reader(&mut bytes, endianness)?;
+        Ok((obj, len - bytes.len()))
+    }
+}
+
+macro_rules! cbtf_type_int {
+    ($($int:ty => $read:ident),*) => {
+        $(
+            impl CBtfType for $int {
+                const SIZE: usize = std::mem::size_of::<$int>();
+
+                fn from_reader<R: Read>(reader: &mut R, endianness: Endianness) -> Result<Self> {
+                    match endianness {
+                        Endianness::Little => reader.$read::<LittleEndian>(),
+                        Endianness::Big => reader.$read::<BigEndian>(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+cbtf_type_int!(
+    u16 => read_u16,
+    u32 => read_u32,
+    u64 => read_u64,
+    i16 => read_i16,
+    i32 => read_i32,
+    i64 => read_i64
+);
+
+impl CBtfType for u8 {
+    const SIZE: usize = 1;
+
+    fn from_reader<R: Read>(reader: &mut R, _: Endianness) -> Result<Self> {
+        reader.read_u8()
+    }
+}
+
+impl CBtfType for i8 {
+    const SIZE: usize = 1;
+
+    fn from_reader<R: Read>(reader: &mut R, _: Endianness) -> Result<Self> {
+        reader.read_i8()
+    }
+}
+
+impl<T: CBtfType, const N: usize> CBtfType for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn from_reader<R: Read>(reader: &mut R, endianness: Endianness) -> Result<Self> {
+        let mut elems = Vec::with_capacity(N);
+        for _ in 0..N {
+            elems.push(T::from_reader(reader, endianness)?);
+        }
+        elems
+            .try_into()
+            .map_err(|_| Error::other("Could not build array"))
+    }
+}
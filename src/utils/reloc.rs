@@ -0,0 +1,228 @@
+//! ### CO-RE field relocations
+//!
+//! BPF programs compiled with CO-RE (Compile Once - Run Everywhere) support
+//! reference struct members using access strings, e.g. `0:2:1`, which are
+//! resolved against the BTF of the running kernel when the program is loaded.
+//! [`field_reloc`] resolves such an access string given the name of its root
+//! type and returns the values a loader needs to patch the instructions: byte
+//! offset and size, bitfield shifts, sign and existence of the field.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, ext::CoreReloKind, utils::reloc::field_reloc};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//!
+//! // Access strings, as found in CO-RE relocations.
+//! let reloc = field_reloc(&btf, "sk_buff", "0:1:2").unwrap();
+//! println!("{:?}", reloc.value(CoreReloKind::FieldByteOffset));
+//!
+//! // Or member names.
+//! let reloc = field_reloc(&btf, "task_struct", "pid").unwrap();
+//! assert!(reloc.exists);
+//! ```
+//!
+//! Access strings are made of indexes separated by colons. The first one is
+//! an array index on the root type, the following ones are member indexes in
+//! structs and unions (anonymous members included) or element indexes in
+//! arrays. Named accesses are member names separated by dots, anonymous
+//! members being looked through. Pointers are never followed, as for CO-RE.
+use anyhow::{anyhow, bail, Result};
+
+use crate::{ext::CoreReloKind, Btf, BtfType, Endianness, Type, TypeId};
+
+/// Relocation information of a field, see [`field_reloc`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldReloc {
+    /// Does the field exist? If not, all the other values are zeroed.
+    pub exists: bool,
+    /// Id of the type of the field.
+    pub type_id: TypeId,
+    /// Offset in bits of the field, from the start of the root object.
+    pub bit_offset: u32,
+    /// Size in bits of the field, for bitfields.
+    pub bitfield_size: Option<u32>,
+    /// Offset in bytes of the field or, for bitfields, of the smallest
+    /// aligned integer containing it.
+    pub byte_offset: usize,
+    /// Size in bytes of the field or, for bitfields, of the smallest aligned
+    /// integer containing it.
+    pub byte_size: usize,
+    /// Is the field a signed integer or enum?
+    pub signed: bool,
+    /// Left shift to apply to the loaded value, in a 64-bit register, to drop
+    /// the bits above the field.
+    pub lshift_u64: u32,
+    /// Right shift to apply to the loaded value once left shifted, to drop
+    /// the bits below the field.
+    pub rshift_u64: u32,
+}
+
+impl FieldReloc {
+    fn missing() -> FieldReloc {
+        FieldReloc {
+            exists: false,
+            type_id: TypeId::default(),
+            bit_offset: 0,
+            bitfield_size: None,
+            byte_offset: 0,
+            byte_size: 0,
+            signed: false,
+            lshift_u64: 0,
+            rshift_u64: 0,
+        }
+    }
+
+    /// Value to patch in an instruction for a given kind of field relocation.
+    /// Returns `None` for relocations not targeting fields.
+    pub fn value(&self, kind: CoreReloKind) -> Option<u64> {
+        Some(match kind {
+            CoreReloKind::FieldByteOffset => self.byte_offset as u64,
+            CoreReloKind::FieldByteSize => self.byte_size as u64,
+            CoreReloKind::FieldExists => self.exists as u64,
+            CoreReloKind::FieldSigned => self.signed as u64,
+            CoreReloKind::FieldLshiftU64 => self.lshift_u64 as u64,
+            CoreReloKind::FieldRshiftU64 => self.rshift_u64 as u64,
+            _ => return None,
+        })
+    }
+}
+
+/// Resolve a field relocation, given the name of its root type and an access
+/// string: either a CO-RE one (e.g. `0:2:1`) or member names (e.g. `pid` on
+/// `task_struct`). A missing root type or field is not an error
+/// but is reported using `FieldReloc::exists`, as CO-RE relocations can
+/// check for the existence of fields.
+pub fn field_reloc(btf: &Btf, type_name: &str, access: &str) -> Result<FieldReloc> {
+    let Some((mut type_id, mut current)) = root(btf, type_name)? else {
+        return Ok(FieldReloc::missing());
+    };
+
+    let mut bit_offset = 0;
+    let mut bitfield_size = None;
+    if access.starts_with(|c: char| c.is_ascii_digit()) {
+        let mut indexes = access.split(':').map(|i| {
+            i.parse::<usize>()
+                .map_err(|e| anyhow!("Invalid access string {access}: {e}"))
+        });
+
+        let first = indexes.next().unwrap_or(Ok(0))?;
+        if first != 0 {
+            bit_offset = (first * btf.resolve_type_size(&current)? * 8) as u32;
+        }
+
+        for index in indexes {
+            let index = index?;
+            match btf.resolve_underlying_type(&current)? {
+                Type::Struct(s) | Type::Union(s) => {
+                    let Some(member) = s.members.get(index) else {
+                        return Ok(FieldReloc::missing());
+                    };
+                    bit_offset += member.bit_offset();
+                    bitfield_size = member.bitfield_size().filter(|size| *size != 0);
+                    type_id = member.get_type_id()?;
+                    current = btf.resolve_chained_type(member)?;
+                }
+                Type::Array(a) => {
+                    type_id = a.get_type_id()?;
+                    current = btf.resolve_chained_type(&a)?;
+                    bit_offset += (index * btf.resolve_type_size(&current)? * 8) as u32;
+                    bitfield_size = None;
+                }
+                x => bail!("Can't access index {index} of a {} in {access}", x.name()),
+            }
+        }
+    } else {
+        for name in access.split('.') {
+            let r#struct = match btf.resolve_underlying_type(&current)? {
+                Type::Struct(s) | Type::Union(s) => s,
+                x => bail!("Can't access member {name} of a {} in {access}", x.name()),
+            };
+            let Some((member, offset)) = r#struct.member_by_name(btf, name)? else {
+                return Ok(FieldReloc::missing());
+            };
+            bit_offset += offset;
+            bitfield_size = member.bitfield_size().filter(|size| *size != 0);
+            type_id = member.get_type_id()?;
+            current = btf.resolve_chained_type(&member)?;
+        }
+    }
+
+    let underlying = btf.resolve_underlying_type(&current)?;
+    let signed = match &underlying {
+        Type::Int(i) => i.is_signed(),
+        Type::Enum(e) => e.is_signed(),
+        Type::Enum64(e) => e.is_signed(),
+        _ => false,
+    };
+
+    // Same logic as libbpf: bitfields are loaded using the smallest aligned
+    // integer (of at least the size of their type) containing them.
+    let (byte_offset, byte_size, bit_size) = match bitfield_size {
+        Some(bit_size) => {
+            let mut byte_size = btf.resolve_type_size(&underlying)?;
+            if byte_size == 0 {
+                bail!("Bitfield of a zero-sized type in {access}");
+            }
+            let mut byte_offset = bit_offset as usize / 8 / byte_size * byte_size;
+            while (bit_offset + bit_size) as usize - byte_offset * 8 > byte_size * 8 {
+                if byte_size >= 8 {
+                    bail!("Bitfield can't be loaded using a 64-bit integer in {access}");
+                }
+                byte_size *= 2;
+                byte_offset = bit_offset as usize / 8 / byte_size * byte_size;
+            }
+            (byte_offset, byte_size, bit_size)
+        }
+        None => {
+            let byte_size = btf.resolve_type_size(&underlying)?;
+            (bit_offset as usize / 8, byte_size, byte_size as u32 * 8)
+        }
+    };
+
+    // Shifts only make sense for fields fitting in 64 bits.
+    let (lshift_u64, rshift_u64) = match byte_size <= 8 {
+        true => {
+            let bit_in_load = bit_offset - byte_offset as u32 * 8;
+            let lshift = match btf.endianness() {
+                Endianness::Little => 64 - (bit_in_load + bit_size),
+                Endianness::Big => (8 - byte_size as u32) * 8 + bit_in_load,
+            };
+            (lshift, 64 - bit_size)
+        }
+        false => (0, 0),
+    };
+
+    Ok(FieldReloc {
+        exists: true,
+        type_id,
+        bit_offset,
+        bitfield_size,
+        byte_offset,
+        byte_size,
+        signed,
+        lshift_u64,
+        rshift_u64,
+    })
+}
+
+// Find the root type of a relocation given its name. Structs and unions are
+// preferred, declarations (forward ones, functions, etc.) are never used.
+fn root(btf: &Btf, name: &str) -> Result<Option<(TypeId, Type)>> {
+    let mut found = None;
+    for id in btf.ids_for_exact_name(name) {
+        let r#type = btf.resolve_type_by_id(id)?;
+        match btf.resolve_underlying_type(&r#type)? {
+            Type::Struct(_) | Type::Union(_) => return Ok(Some((id, r#type))),
+            Type::Fwd(_)
+            | Type::Func(_)
+            | Type::FuncProto(_)
+            | Type::Var(_)
+            | Type::Datasec(_)
+            | Type::DeclTag(_) => (),
+            _ => {
+                found.get_or_insert((id, r#type));
+            }
+        }
+    }
+    Ok(found)
+}
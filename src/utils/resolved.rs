@@ -0,0 +1,390 @@
+//! ### Serializable resolved types
+//!
+//! [`Type`] and its associated structs reference names using offsets in the
+//! string section of their [`Btf`] object, which makes them meaningless on
+//! their own. [`ResolvedType`] mirrors [`Type`] with names and values resolved
+//! and implements `Serialize` and `Deserialize`, so type information can be
+//! exported to JSON, YAML, etc. using any serde backend.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::resolved};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! let id = btf.resolve_ids_by_name("sk_buff").unwrap()[0];
+//!
+//! let sk_buff = resolved::resolve_by_id(&btf, id).unwrap();
+//! println!("{}", serde_json::to_string_pretty(&sk_buff).unwrap());
+//! ```
+//!
+//! Referenced types are not resolved recursively but are identified by their
+//! id, e.g. the type of struct members. Types are tagged by their kind:
+//!
+//! ```text
+//! {"kind":"Ptr","type_id":4984}
+//! ```
+//!
+//! The serialized representation is versioned by [`SCHEMA_VERSION`], which
+//! consumers storing it should record alongside. Within a version, kinds and
+//! fields are never renamed nor removed and their meaning does not change;
+//! new fields and kinds can be added. Other changes bump the version.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{Btf, BtfType, EnumValue, IntEncoding, Type, TypeId};
+
+/// Version of the serialized representation of [`ResolvedType`]. See the
+/// module documentation for the guarantees it comes with.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Type with its names and values resolved, see [`resolve`]. Names of types
+/// which can be anonymous are `None` when so.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "kind")]
+pub enum ResolvedType {
+    Void,
+    /// `signed`, `char` and `bool` are redundant with `encoding`, and kept for
+    /// compatibility. When deserializing data lacking `encoding`, it is derived
+    /// from them.
+    #[serde(deserialize_with = "deserialize_int")]
+    Int {
+        name: String,
+        size: usize,
+        signed: bool,
+        char: bool,
+        bool: bool,
+        encoding: IntEncoding,
+        bit_offset: u32,
+        bits: u32,
+    },
+    Ptr {
+        type_id: TypeId,
+    },
+    Array {
+        type_id: TypeId,
+        index_type_id: TypeId,
+        len: usize,
+    },
+    Struct {
+        name: Option<String>,
+        size: usize,
+        members: Vec<ResolvedMember>,
+    },
+    Union {
+        name: Option<String>,
+        size: usize,
+        members: Vec<ResolvedMember>,
+    },
+    Enum {
+        name: Option<String>,
+        size: usize,
+        signed: bool,
+        values: Vec<ResolvedEnumValue>,
+    },
+    Fwd {
+        name: String,
+        union: bool,
+    },
+    Typedef {
+        name: String,
+        type_id: TypeId,
+    },
+    Volatile {
+        type_id: TypeId,
+    },
+    Const {
+        type_id: TypeId,
+    },
+    Restrict {
+        type_id: TypeId,
+    },
+    Func {
+        name: String,
+        type_id: TypeId,
+        linkage: Linkage,
+    },
+    FuncProto {
+        return_type_id: TypeId,
+        parameters: Vec<ResolvedParameter>,
+    },
+    Var {
+        name: String,
+        type_id: TypeId,
+        linkage: Linkage,
+    },
+    Datasec {
+        name: String,
+        size: usize,
+        variables: Vec<ResolvedVariable>,
+    },
+    Float {
+        name: String,
+        size: usize,
+    },
+    DeclTag {
+        name: String,
+        type_id: TypeId,
+        component_index: Option<u32>,
+    },
+    TypeTag {
+        name: String,
+        type_id: TypeId,
+    },
+    Enum64 {
+        name: Option<String>,
+        size: usize,
+        signed: bool,
+        values: Vec<ResolvedEnumValue>,
+    },
+}
+
+// Fields of a serialized ResolvedType::Int, with the optional encoding.
+#[derive(Deserialize)]
+struct SerializedInt {
+    name: String,
+    size: usize,
+    signed: bool,
+    char: bool,
+    bool: bool,
+    encoding: Option<IntEncoding>,
+    bit_offset: u32,
+    bits: u32,
+}
+
+// Deserialize a ResolvedType::Int, deriving its encoding from the signed, char
+// and bool fields when missing.
+#[allow(clippy::type_complexity)]
+fn deserialize_int<'de, D>(
+    deserializer: D,
+) -> Result<(String, usize, bool, bool, bool, IntEncoding, u32, u32), D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let int = SerializedInt::deserialize(deserializer)?;
+    let encoding = int
+        .encoding
+        .unwrap_or(match (int.signed, int.char, int.bool) {
+            (true, _, _) => IntEncoding::Signed,
+            (_, true, _) => IntEncoding::Char,
+            (_, _, true) => IntEncoding::Bool,
+            _ => IntEncoding::None,
+        });
+    Ok((
+        int.name,
+        int.size,
+        int.signed,
+        int.char,
+        int.bool,
+        encoding,
+        int.bit_offset,
+        int.bits,
+    ))
+}
+
+/// Member of a [`ResolvedType::Struct`] or [`ResolvedType::Union`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResolvedMember {
+    pub name: Option<String>,
+    pub type_id: TypeId,
+    pub bit_offset: u32,
+    pub bitfield_size: Option<u32>,
+}
+
+/// Value of a [`ResolvedType::Enum`] or [`ResolvedType::Enum64`]. Values are
+/// serialized as plain numbers; when deserialized, values fitting in an `i64`
+/// are always [`EnumValue::Signed`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResolvedEnumValue {
+    pub name: String,
+    pub value: EnumValue,
+}
+
+/// Parameter of a [`ResolvedType::FuncProto`]. Variadic functions have a last
+/// parameter with no name and a `void` type.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResolvedParameter {
+    pub name: Option<String>,
+    pub type_id: TypeId,
+}
+
+/// Variable of a [`ResolvedType::Datasec`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResolvedVariable {
+    pub type_id: TypeId,
+    pub offset: u32,
+    pub size: usize,
+}
+
+/// Linkage of functions and variables.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Linkage {
+    Static,
+    Global,
+    Extern,
+}
+
+/// Resolve the names and values of a type defined in `btf`.
+pub fn resolve(btf: &Btf, r#type: &Type) -> Result<ResolvedType> {
+    let name = |t: &dyn BtfType| btf.resolve_name(t);
+    let opt_name = |t: &dyn BtfType| -> Result<Option<String>> {
+        let name = btf.resolve_name(t)?;
+        Ok((!name.is_empty()).then_some(name))
+    };
+    let type_id = |t: &dyn BtfType| t.get_type_id();
+    let linkage = |static_: bool, global: bool| match (static_, global) {
+        (true, _) => Linkage::Static,
+        (_, true) => Linkage::Global,
+        _ => Linkage::Extern,
+    };
+
+    Ok(match r#type {
+        Type::Void => ResolvedType::Void,
+        Type::Int(i) => ResolvedType::Int {
+            name: name(i)?,
+            size: i.size(),
+            signed: i.is_signed(),
+            char: i.is_char(),
+            bool: i.is_bool(),
+            encoding: i.encoding(),
+            bit_offset: i.offset(),
+            bits: i.bits(),
+        },
+        Type::Ptr(p) => ResolvedType::Ptr {
+            type_id: type_id(p)?,
+        },
+        Type::Array(a) => ResolvedType::Array {
+            type_id: type_id(a)?,
+            index_type_id: a.index_type_id().into(),
+            len: a.len(),
+        },
+        Type::Struct(s) | Type::Union(s) => {
+            let members = s
+                .members
+                .iter()
+                .map(|m| {
+                    Ok(ResolvedMember {
+                        name: opt_name(m)?,
+                        type_id: type_id(m)?,
+                        bit_offset: m.bit_offset(),
+                        bitfield_size: m.bitfield_size().filter(|size| *size != 0),
+                    })
+                })
+                .collect::<Result<_>>()?;
+            match r#type {
+                Type::Struct(_) => ResolvedType::Struct {
+                    name: opt_name(s)?,
+                    size: s.size(),
+                    members,
+                },
+                _ => ResolvedType::Union {
+                    name: opt_name(s)?,
+                    size: s.size(),
+                    members,
+                },
+            }
+        }
+        Type::Enum(e) => ResolvedType::Enum {
+            name: opt_name(e)?,
+            size: e.size(),
+            signed: e.is_signed(),
+            values: e
+                .members
+                .iter()
+                .map(|m| {
+                    Ok(ResolvedEnumValue {
+                        name: name(m)?,
+                        value: m.value(e),
+                    })
+                })
+                .collect::<Result<_>>()?,
+        },
+        Type::Enum64(e) => ResolvedType::Enum64 {
+            name: opt_name(e)?,
+            size: e.size(),
+            signed: e.is_signed(),
+            values: e
+                .members
+                .iter()
+                .map(|m| {
+                    Ok(ResolvedEnumValue {
+                        name: name(m)?,
+                        value: m.value(e),
+                    })
+                })
+                .collect::<Result<_>>()?,
+        },
+        Type::Fwd(f) => ResolvedType::Fwd {
+            name: name(f)?,
+            union: f.is_union(),
+        },
+        Type::Typedef(td) => ResolvedType::Typedef {
+            name: name(td)?,
+            type_id: type_id(td)?,
+        },
+        Type::TypeTag(tt) => ResolvedType::TypeTag {
+            name: name(tt)?,
+            type_id: type_id(tt)?,
+        },
+        Type::Volatile(v) => ResolvedType::Volatile {
+            type_id: type_id(v)?,
+        },
+        Type::Const(c) => ResolvedType::Const {
+            type_id: type_id(c)?,
+        },
+        Type::Restrict(r) => ResolvedType::Restrict {
+            type_id: type_id(r)?,
+        },
+        Type::Func(f) => ResolvedType::Func {
+            name: name(f)?,
+            type_id: type_id(f)?,
+            linkage: linkage(f.is_static(), f.is_global()),
+        },
+        Type::FuncProto(fp) => ResolvedType::FuncProto {
+            return_type_id: fp.return_type_id(),
+            parameters: fp
+                .parameters
+                .iter()
+                .map(|p| {
+                    Ok(ResolvedParameter {
+                        name: opt_name(p)?,
+                        type_id: type_id(p)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        },
+        Type::Var(v) => ResolvedType::Var {
+            name: name(v)?,
+            type_id: type_id(v)?,
+            linkage: linkage(v.is_static(), v.is_global()),
+        },
+        Type::Datasec(ds) => ResolvedType::Datasec {
+            name: name(ds)?,
+            size: ds.size(),
+            variables: ds
+                .variables
+                .iter()
+                .map(|v| {
+                    Ok(ResolvedVariable {
+                        type_id: type_id(v)?,
+                        offset: v.offset(),
+                        size: v.size(),
+                    })
+                })
+                .collect::<Result<_>>()?,
+        },
+        Type::Float(f) => ResolvedType::Float {
+            name: name(f)?,
+            size: f.size(),
+        },
+        Type::DeclTag(dt) => ResolvedType::DeclTag {
+            name: name(dt)?,
+            type_id: type_id(dt)?,
+            component_index: dt.component_index(),
+        },
+    })
+}
+
+/// Resolve the names and values of a type defined in `btf`, given its id.
+pub fn resolve_by_id<I: Into<TypeId>>(btf: &Btf, id: I) -> Result<ResolvedType> {
+    resolve(btf, &btf.resolve_type_by_id(id)?)
+}
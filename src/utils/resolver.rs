@@ -0,0 +1,89 @@
+//! ### Generic type resolution
+//!
+//! The [`TypeResolver`] trait abstracts name based lookups over [`Btf`],
+//! [`NamedBtf`] and [`BtfCollection`] objects, so code can be written once for
+//! "something resolving kernel types". As for collections, each result comes
+//! with the [`Btf`] object it was found in and which must be used for further
+//! lookups.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, Kind, utils::{collection::BtfCollection, resolver::TypeResolver}};
+//!
+//! fn skb_size<R: TypeResolver>(resolver: &R) -> usize {
+//!     match resolver.resolve_types("sk_buff").unwrap().pop().unwrap() {
+//!         (_, btf_rs::Type::Struct(skb)) => skb.size(),
+//!         _ => panic!("Resolved type is not a struct"),
+//!     }
+//! }
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! let btfc = BtfCollection::from_dir("/sys/kernel/btf", "vmlinux").unwrap();
+//! assert_eq!(skb_size(&btf), skb_size(&btfc));
+//! ```
+use anyhow::{bail, Result};
+
+use crate::{
+    utils::collection::{BtfCollection, NamedBtf},
+    Btf, Kind, Type, TypeId,
+};
+
+/// Name based type resolution, implemented by [`Btf`], [`NamedBtf`] and
+/// [`BtfCollection`].
+pub trait TypeResolver {
+    /// Find the ids of the types named `name`, alongside the `Btf` object
+    /// they are defined in.
+    fn resolve_ids(&self, name: &str) -> Result<Vec<(&Btf, TypeId)>>;
+
+    /// Find the types named `name`, alongside the `Btf` object they are
+    /// defined in.
+    fn resolve_types(&self, name: &str) -> Result<Vec<(&Btf, Type)>> {
+        let mut types = Vec::new();
+        for (btf, id) in self.resolve_ids(name)? {
+            types.push((btf, btf.resolve_type_by_id(id)?));
+        }
+        Ok(types)
+    }
+
+    /// Find the types named `name` and walk their chain of referenced types
+    /// following the expected kinds (see `Btf::expect_chain()`), returning the
+    /// last type of each matching chain. Types whose chain does not match are
+    /// ignored.
+    fn resolve_chains(&self, name: &str, kinds: &[Kind]) -> Result<Vec<(&Btf, Type)>> {
+        let chains: Vec<_> = self
+            .resolve_types(name)?
+            .into_iter()
+            .filter_map(|(btf, r#type)| Some((btf, btf.expect_chain(&r#type, kinds).ok()?)))
+            .collect();
+
+        if chains.is_empty() {
+            bail!("No type named {name} matching the expected chain");
+        }
+        Ok(chains)
+    }
+}
+
+impl TypeResolver for Btf {
+    fn resolve_ids(&self, name: &str) -> Result<Vec<(&Btf, TypeId)>> {
+        Ok(self
+            .resolve_ids_by_name(name)?
+            .into_iter()
+            .map(|id| (self, id))
+            .collect())
+    }
+}
+
+impl TypeResolver for NamedBtf {
+    fn resolve_ids(&self, name: &str) -> Result<Vec<(&Btf, TypeId)>> {
+        self.btf.resolve_ids(name)
+    }
+}
+
+impl TypeResolver for BtfCollection {
+    fn resolve_ids(&self, name: &str) -> Result<Vec<(&Btf, TypeId)>> {
+        Ok(self
+            .resolve_ids_by_name(name)?
+            .into_iter()
+            .map(|m| (&m.btf.btf, m.id))
+            .collect())
+    }
+}
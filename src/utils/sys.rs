@@ -0,0 +1,243 @@
+//! ### BTF objects loaded in the running kernel
+//!
+//! BTF objects known to the running kernel (vmlinux, modules and the ones
+//! loaded along with BPF programs) can be retrieved using the `bpf()` syscall,
+//! without relying on `/sys/kernel/btf` being available. This requires the
+//! `CAP_SYS_ADMIN` capability.
+//!
+//! ```no_run
+//! use btf_rs::utils::sys;
+//!
+//! // Base and module BTFs, as found in /sys/kernel/btf.
+//! let btfc = sys::collection().unwrap();
+//!
+//! // All the BTF objects loaded in the kernel.
+//! for id in sys::ids().unwrap() {
+//!     let obj = sys::get(id).unwrap();
+//!     println!("{id}: {} ({} bytes)", obj.name, obj.data.len());
+//! }
+//! ```
+//!
+//! This is only available on Linux.
+use std::{
+    ffi::{c_long, c_void},
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::{utils::collection::BtfCollection, Btf};
+
+/// BTF object loaded in the running kernel, see [`get`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LoadedBtf {
+    /// Kernel id of the object.
+    pub id: u32,
+    /// Name of the object: `vmlinux`, the module name or the name given when
+    /// loading it (can be empty).
+    pub name: String,
+    /// Is the object part of the kernel (vmlinux or module)?
+    pub kernel: bool,
+    /// Raw BTF data. Module BTF objects are split ones, on top of vmlinux.
+    pub data: Vec<u8>,
+}
+
+/// Get the ids of all the BTF objects loaded in the running kernel.
+pub fn ids() -> Result<Vec<u32>> {
+    let mut ids = Vec::new();
+    let mut attr = IdAttr::default();
+    loop {
+        match bpf(BPF_BTF_GET_NEXT_ID, &mut attr) {
+            Ok(_) => ids.push(attr.next_id),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(ids),
+            Err(e) => return Err(e).context("Could not get the next BTF id"),
+        }
+        attr.id = attr.next_id;
+    }
+}
+
+/// Retrieve a BTF object loaded in the running kernel given its id.
+pub fn get(id: u32) -> Result<LoadedBtf> {
+    let mut attr = IdAttr {
+        id,
+        ..Default::default()
+    };
+    let fd = bpf(BPF_BTF_GET_FD_BY_ID, &mut attr)
+        .with_context(|| format!("Could not get a file descriptor for BTF id {id}"))?;
+    // Safety: the syscall returned a new file descriptor we now own.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // First retrieve the size of the data and name, then their content.
+    let mut info = BtfInfo::default();
+    info_by_fd(&fd, &mut info).with_context(|| format!("Could not get BTF id {id} info"))?;
+
+    let mut data = vec![0u8; info.btf_size as usize];
+    let mut name = vec![0u8; info.name_len as usize + 1];
+    let mut info = BtfInfo {
+        btf: data.as_mut_ptr() as u64,
+        btf_size: data.len() as u32,
+        name: name.as_mut_ptr() as u64,
+        name_len: name.len() as u32,
+        ..Default::default()
+    };
+    info_by_fd(&fd, &mut info).with_context(|| format!("Could not get BTF id {id} data"))?;
+
+    // The object can't change, but better be safe.
+    if info.btf_size as usize != data.len() {
+        bail!("BTF id {id} changed size while being read");
+    }
+    name.truncate(info.name_len as usize);
+
+    Ok(LoadedBtf {
+        id: info.id,
+        name: String::from_utf8(name).map_err(|e| anyhow!("Invalid BTF id {id} name: {e}"))?,
+        kernel: info.kernel_btf != 0,
+        data,
+    })
+}
+
+/// Retrieve all the kernel BTF objects (vmlinux and modules) loaded in the
+/// running kernel.
+pub fn kernel_btfs() -> Result<Vec<LoadedBtf>> {
+    let mut btfs = Vec::new();
+    for id in ids()? {
+        match get(id) {
+            Ok(btf) if btf.kernel => btfs.push(btf),
+            Ok(_) => (),
+            // The object was unloaded after we got its id.
+            Err(e)
+                if e.downcast_ref::<io::Error>()
+                    .is_some_and(|e| e.kind() == io::ErrorKind::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(btfs)
+}
+
+/// Parse the vmlinux BTF object of the running kernel.
+pub fn vmlinux() -> Result<Btf> {
+    let vmlinux = kernel_btfs()?
+        .into_iter()
+        .find(|btf| btf.name == "vmlinux")
+        .ok_or_else(|| anyhow!("No vmlinux BTF object found"))?;
+    Btf::from_bytes(&vmlinux.data)
+}
+
+/// Build a [`BtfCollection`] from the kernel BTF objects of the running
+/// kernel, using vmlinux as the base and modules as split BTFs.
+pub fn collection() -> Result<BtfCollection> {
+    let (vmlinux, modules): (Vec<_>, Vec<_>) = kernel_btfs()?
+        .into_iter()
+        .partition(|btf| btf.name == "vmlinux");
+    let vmlinux = vmlinux
+        .first()
+        .ok_or_else(|| anyhow!("No vmlinux BTF object found"))?;
+
+    let mut btfc = BtfCollection::from_bytes(&vmlinux.name, &vmlinux.data)?;
+    for module in modules.iter() {
+        btfc.add_split_btf_from_bytes(&module.name, &module.data)
+            .with_context(|| format!("Could not parse BTF of module {}", module.name))?;
+    }
+    Ok(btfc)
+}
+
+// bpf() commands, from include/uapi/linux/bpf.h.
+const BPF_OBJ_GET_INFO_BY_FD: u32 = 15;
+const BPF_BTF_GET_FD_BY_ID: u32 = 19;
+const BPF_BTF_GET_NEXT_ID: u32 = 23;
+
+#[cfg(target_arch = "x86_64")]
+const SYS_BPF: Option<c_long> = Some(321);
+#[cfg(target_arch = "x86")]
+const SYS_BPF: Option<c_long> = Some(357);
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "loongarch64"
+))]
+const SYS_BPF: Option<c_long> = Some(280);
+#[cfg(target_arch = "arm")]
+const SYS_BPF: Option<c_long> = Some(386);
+#[cfg(target_arch = "s390x")]
+const SYS_BPF: Option<c_long> = Some(351);
+#[cfg(target_arch = "powerpc64")]
+const SYS_BPF: Option<c_long> = Some(361);
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "x86",
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "loongarch64",
+    target_arch = "arm",
+    target_arch = "s390x",
+    target_arch = "powerpc64"
+)))]
+const SYS_BPF: Option<c_long> = None;
+
+// Attribute of the BPF_BTF_GET_NEXT_ID and BPF_BTF_GET_FD_BY_ID commands.
+#[repr(C)]
+#[derive(Default)]
+struct IdAttr {
+    id: u32,
+    next_id: u32,
+    open_flags: u32,
+}
+
+// Attribute of the BPF_OBJ_GET_INFO_BY_FD command.
+#[repr(C)]
+#[derive(Default)]
+struct InfoAttr {
+    bpf_fd: u32,
+    info_len: u32,
+    info: u64,
+}
+
+// struct bpf_btf_info.
+#[repr(C)]
+#[derive(Default)]
+struct BtfInfo {
+    btf: u64,
+    btf_size: u32,
+    id: u32,
+    name: u64,
+    name_len: u32,
+    kernel_btf: u32,
+}
+
+extern "C" {
+    fn syscall(num: c_long, ...) -> c_long;
+}
+
+// Issue a bpf() command, returning its non-negative result.
+fn bpf<T>(cmd: u32, attr: &mut T) -> io::Result<i32> {
+    let Some(sys_bpf) = SYS_BPF else {
+        return Err(io::Error::from(io::ErrorKind::Unsupported));
+    };
+
+    // Safety: attr is a valid, exclusively borrowed, object of the size given
+    // to the kernel.
+    let ret = unsafe {
+        syscall(
+            sys_bpf,
+            cmd,
+            attr as *mut T as *mut c_void,
+            std::mem::size_of::<T>() as u32,
+        )
+    };
+    match ret {
+        ret if ret < 0 => Err(io::Error::last_os_error()),
+        ret => Ok(ret as i32),
+    }
+}
+
+// Retrieve the info of a BTF object given a file descriptor to it. Buffers
+// referenced in `info` must be valid for their given lengths.
+fn info_by_fd(fd: &OwnedFd, info: &mut BtfInfo) -> io::Result<()> {
+    let mut attr = InfoAttr {
+        bpf_fd: fd.as_raw_fd() as u32,
+        info_len: std::mem::size_of::<BtfInfo>() as u32,
+        info: info as *mut BtfInfo as u64,
+    };
+    bpf(BPF_OBJ_GET_INFO_BY_FD, &mut attr).map(|_| ())
+}
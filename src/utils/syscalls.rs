@@ -0,0 +1,146 @@
+//! ### Syscall prototype resolution
+//!
+//! Syscall entry points are defined using per-architecture symbol prefixes
+//! (e.g. `__x64_sys_openat` or `__arm64_sys_openat`) and, on recent kernels,
+//! take a single `struct pt_regs *` parameter holding the actual arguments.
+//! Older kernels expose the syscalls as `sys_<name>` with their full
+//! prototype. The helpers in this module hide those differences, using the
+//! aliasing rules from [`Aliases::syscalls`]. When the entry point takes a
+//! `struct pt_regs *`, the actual prototype is retrieved from the
+//! `__do_sys_<name>` or `__se_sys_<name>` functions it wraps, when those are
+//! described in BTF.
+//!
+//! ```no_run
+//! use btf_rs::{Btf, utils::{arch::Arch, syscalls::resolve_syscall}};
+//!
+//! let btf = Btf::from_file("/sys/kernel/btf/vmlinux").unwrap();
+//! let openat = resolve_syscall(&btf, "openat", Arch::X86_64).unwrap();
+//!
+//! // The following prints "__x64_sys_openat".
+//! println!("{}", openat.symbol);
+//! ```
+use anyhow::{bail, Result};
+
+use crate::{
+    utils::{alias::Aliases, arch::Arch},
+    Btf, Func, FuncProto, Type,
+};
+
+/// Description of a syscall entry point found in BTF.
+#[derive(Clone, Debug)]
+pub struct Syscall {
+    /// Name of the function implementing the syscall entry point, e.g.
+    /// `__x64_sys_openat`.
+    pub symbol: String,
+    /// The function implementing the syscall entry point.
+    pub func: Func,
+    /// Prototype of the function implementing the syscall entry point.
+    pub proto: FuncProto,
+    /// Whether the entry point takes a single `struct pt_regs *` parameter
+    /// holding the actual syscall arguments, instead of the arguments
+    /// themselves.
+    pub uses_pt_regs: bool,
+    /// Prototype of the syscall arguments. Same as `proto` if the entry point
+    /// does not use `struct pt_regs`; otherwise the one of the wrapped
+    /// `__do_sys_<name>` or `__se_sys_<name>` function, or `None` if none was
+    /// found.
+    pub args_proto: Option<FuncProto>,
+}
+
+/// List the symbols which can implement a syscall entry point on a given
+/// architecture, in the order they should be looked up. The syscall name can be
+/// given with or without its `sys_` prefix.
+pub fn syscall_symbols(name: &str, arch: Arch) -> Vec<String> {
+    let name = name.strip_prefix("sys_").unwrap_or(name);
+    let symbol = format!("sys_{name}");
+    // Arch specific entry points, given by the aliasing rules, come first;
+    // `sys_<name>` is only used by older kernels.
+    let mut symbols: Vec<String> = Aliases::syscalls(arch)
+        .rules()
+        .iter()
+        .filter_map(|rule| rule.apply(&symbol))
+        .collect();
+    symbols.push(symbol);
+    symbols
+}
+
+/// Resolve a syscall, given its name (e.g. `openat` or `sys_openat`), to the
+/// function implementing its entry point on the given architecture.
+pub fn resolve_syscall(btf: &Btf, name: &str, arch: Arch) -> Result<Syscall> {
+    for symbol in syscall_symbols(name, arch) {
+        let func = match btf
+            .resolve_types_by_name(&symbol)
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|t| match t {
+                Type::Func(func) => Some(func),
+                _ => None,
+            }) {
+            Some(func) => func,
+            None => continue,
+        };
+
+        let proto = func_proto(btf, &symbol, &func)?;
+        let uses_pt_regs = proto.parameters.len() == 1 && is_pt_regs_ptr(btf, &proto)?;
+        let args_proto = match uses_pt_regs {
+            true => wrapped_proto(btf, name)?,
+            false => Some(proto.clone()),
+        };
+        return Ok(Syscall {
+            symbol,
+            func,
+            proto,
+            uses_pt_regs,
+            args_proto,
+        });
+    }
+
+    bail!("No entry point found for syscall {name} on {arch:?}")
+}
+
+// Get the prototype of the function wrapped by a `struct pt_regs *` syscall
+// entry point, if described in BTF. `__do_sys_<name>` comes first as it uses the
+// actual argument types, while `__se_sys_<name>` uses `long` for all of them.
+fn wrapped_proto(btf: &Btf, name: &str) -> Result<Option<FuncProto>> {
+    let name = name.strip_prefix("sys_").unwrap_or(name);
+    for symbol in [format!("__do_sys_{name}"), format!("__se_sys_{name}")] {
+        let func = btf
+            .resolve_types_by_name(&symbol)
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|t| match t {
+                Type::Func(func) => Some(func),
+                _ => None,
+            });
+        if let Some(func) = func {
+            return Ok(Some(func_proto(btf, &symbol, &func)?));
+        }
+    }
+    Ok(None)
+}
+
+// Get the prototype of a function.
+fn func_proto(btf: &Btf, symbol: &str, func: &Func) -> Result<FuncProto> {
+    match btf.resolve_chained_type(func)? {
+        Type::FuncProto(proto) => Ok(proto),
+        x => bail!(
+            "Function {symbol} points to a {} instead of a func-proto",
+            x.name()
+        ),
+    }
+}
+
+// Checks if the first parameter of a FuncProto is a `struct pt_regs *`.
+fn is_pt_regs_ptr(btf: &Btf, proto: &FuncProto) -> Result<bool> {
+    let ptr = match btf.resolve_underlying_type(&btf.resolve_chained_type(&proto.parameters[0])?)? {
+        Type::Ptr(ptr) => ptr,
+        _ => return Ok(false),
+    };
+
+    Ok(
+        match btf.resolve_underlying_type(&btf.resolve_chained_type(&ptr)?)? {
+            Type::Struct(s) => btf.resolve_name_ref(&s)? == "pt_regs",
+            _ => false,
+        },
+    )
+}
@@ -0,0 +1,353 @@
+// SPDX-License-Identifier: (LGPL-2.1 OR BSD-2-Clause)
+/*
+ * Golden files generator, see README.md in this directory. This only relies on
+ * libbpf, not on btf-rs, so the golden files are independent of the crate
+ * output.
+ *
+ * Usage:
+ *   gen raw <base> [<split>] [<id>...]
+ *   gen c <base> [<split>] [<id>...]
+ *
+ * When a split BTF file is given all the types it defines are dumped, otherwise
+ * only the types with the given ids are.
+ *
+ * The raw format is the one of `bpftool btf dump file <file> format raw`, the
+ * printing logic being the one of dump_btf_type() in tools/bpf/bpftool/btf.c.
+ *
+ * The c format lists the C declarations emitted by libbpf's
+ * btf_dump__emit_type_decl(), the function backing the declarations found in
+ * `bpftool btf dump file <file> format c`, one per line:
+ *   <id>:-\t<decl>	functions, variables and typedefs, declared using their
+ *			own name (typedefs declare their target type).
+ *   <id>:<i>\t<decl>	named members of structs and unions, and named
+ *			parameters of functions prototypes.
+ * Declarations spanning multiple lines (e.g. of anonymous structs) are skipped.
+ */
+#include <errno.h>
+#include <stdarg.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <bpf/btf.h>
+
+static const char *btf_kind_str[NR_BTF_KINDS] = {
+	[BTF_KIND_UNKN]		= "UNKNOWN",
+	[BTF_KIND_INT]		= "INT",
+	[BTF_KIND_PTR]		= "PTR",
+	[BTF_KIND_ARRAY]	= "ARRAY",
+	[BTF_KIND_STRUCT]	= "STRUCT",
+	[BTF_KIND_UNION]	= "UNION",
+	[BTF_KIND_ENUM]		= "ENUM",
+	[BTF_KIND_FWD]		= "FWD",
+	[BTF_KIND_TYPEDEF]	= "TYPEDEF",
+	[BTF_KIND_VOLATILE]	= "VOLATILE",
+	[BTF_KIND_CONST]	= "CONST",
+	[BTF_KIND_RESTRICT]	= "RESTRICT",
+	[BTF_KIND_FUNC]		= "FUNC",
+	[BTF_KIND_FUNC_PROTO]	= "FUNC_PROTO",
+	[BTF_KIND_VAR]		= "VAR",
+	[BTF_KIND_DATASEC]	= "DATASEC",
+	[BTF_KIND_FLOAT]	= "FLOAT",
+	[BTF_KIND_DECL_TAG]	= "DECL_TAG",
+	[BTF_KIND_TYPE_TAG]	= "TYPE_TAG",
+	[BTF_KIND_ENUM64]	= "ENUM64",
+};
+
+static const char *btf_int_enc_str(__u8 encoding)
+{
+	switch (encoding) {
+	case 0:
+		return "(none)";
+	case BTF_INT_SIGNED:
+		return "SIGNED";
+	case BTF_INT_CHAR:
+		return "CHAR";
+	case BTF_INT_BOOL:
+		return "BOOL";
+	default:
+		return "UNKN";
+	}
+}
+
+static const char *btf_var_linkage_str(__u32 linkage)
+{
+	switch (linkage) {
+	case BTF_VAR_STATIC:
+		return "static";
+	case BTF_VAR_GLOBAL_ALLOCATED:
+		return "global";
+	case BTF_VAR_GLOBAL_EXTERN:
+		return "extern";
+	default:
+		return "(unknown)";
+	}
+}
+
+static const char *btf_func_linkage_str(const struct btf_type *t)
+{
+	switch (btf_vlen(t)) {
+	case BTF_FUNC_STATIC:
+		return "static";
+	case BTF_FUNC_GLOBAL:
+		return "global";
+	case BTF_FUNC_EXTERN:
+		return "extern";
+	default:
+		return "(unknown)";
+	}
+}
+
+static const char *btf_str(const struct btf *btf, __u32 off)
+{
+	if (!off)
+		return "(anon)";
+	return btf__name_by_offset(btf, off) ? : "(invalid)";
+}
+
+static void dump_raw(const struct btf *btf, __u32 id)
+{
+	const struct btf_type *t = btf__type_by_id(btf, id);
+	int kind = btf_kind(t), i;
+
+	printf("[%u] %s '%s'", id, btf_kind_str[kind], btf_str(btf, t->name_off));
+
+	switch (kind) {
+	case BTF_KIND_INT: {
+		__u32 v = *(__u32 *)(t + 1);
+
+		printf(" size=%u bits_offset=%u nr_bits=%u encoding=%s", t->size,
+		       BTF_INT_OFFSET(v), BTF_INT_BITS(v),
+		       btf_int_enc_str(BTF_INT_ENCODING(v)));
+		break;
+	}
+	case BTF_KIND_PTR:
+	case BTF_KIND_CONST:
+	case BTF_KIND_VOLATILE:
+	case BTF_KIND_RESTRICT:
+	case BTF_KIND_TYPEDEF:
+	case BTF_KIND_TYPE_TAG:
+		printf(" type_id=%u", t->type);
+		break;
+	case BTF_KIND_ARRAY: {
+		const struct btf_array *arr = btf_array(t);
+
+		printf(" type_id=%u index_type_id=%u nr_elems=%u", arr->type,
+		       arr->index_type, arr->nelems);
+		break;
+	}
+	case BTF_KIND_STRUCT:
+	case BTF_KIND_UNION: {
+		const struct btf_member *m = btf_members(t);
+
+		printf(" size=%u vlen=%u", t->size, btf_vlen(t));
+		for (i = 0; i < btf_vlen(t); i++, m++) {
+			__u32 bit_off = btf_member_bit_offset(t, i);
+			__u32 bit_sz = btf_member_bitfield_size(t, i);
+
+			printf("\n\t'%s' type_id=%u bits_offset=%u",
+			       btf_str(btf, m->name_off), m->type, bit_off);
+			if (bit_sz)
+				printf(" bitfield_size=%u", bit_sz);
+		}
+		break;
+	}
+	case BTF_KIND_ENUM: {
+		const struct btf_enum *v = btf_enum(t);
+
+		printf(" encoding=%s size=%u vlen=%u",
+		       btf_kflag(t) ? "SIGNED" : "UNSIGNED", t->size, btf_vlen(t));
+		for (i = 0; i < btf_vlen(t); i++, v++) {
+			if (btf_kflag(t))
+				printf("\n\t'%s' val=%d", btf_str(btf, v->name_off),
+				       v->val);
+			else
+				printf("\n\t'%s' val=%u", btf_str(btf, v->name_off),
+				       (__u32)v->val);
+		}
+		break;
+	}
+	case BTF_KIND_ENUM64: {
+		const struct btf_enum64 *v = btf_enum64(t);
+
+		printf(" encoding=%s size=%u vlen=%u",
+		       btf_kflag(t) ? "SIGNED" : "UNSIGNED", t->size, btf_vlen(t));
+		for (i = 0; i < btf_vlen(t); i++, v++) {
+			if (btf_kflag(t))
+				printf("\n\t'%s' val=%lld", btf_str(btf, v->name_off),
+				       (long long)btf_enum64_value(v));
+			else
+				printf("\n\t'%s' val=%llu", btf_str(btf, v->name_off),
+				       (unsigned long long)btf_enum64_value(v));
+		}
+		break;
+	}
+	case BTF_KIND_FWD:
+		printf(" fwd_kind=%s", btf_kflag(t) ? "union" : "struct");
+		break;
+	case BTF_KIND_FUNC:
+		printf(" type_id=%u linkage=%s", t->type, btf_func_linkage_str(t));
+		break;
+	case BTF_KIND_FUNC_PROTO: {
+		const struct btf_param *p = btf_params(t);
+
+		printf(" ret_type_id=%u vlen=%u", t->type, btf_vlen(t));
+		for (i = 0; i < btf_vlen(t); i++, p++)
+			printf("\n\t'%s' type_id=%u", btf_str(btf, p->name_off),
+			       p->type);
+		break;
+	}
+	case BTF_KIND_VAR:
+		printf(" type_id=%u, linkage=%s", t->type,
+		       btf_var_linkage_str(btf_var(t)->linkage));
+		break;
+	case BTF_KIND_DATASEC: {
+		const struct btf_var_secinfo *v = btf_var_secinfos(t);
+		const struct btf_type *vt;
+
+		printf(" size=%u vlen=%u", t->size, btf_vlen(t));
+		for (i = 0; i < btf_vlen(t); i++, v++) {
+			printf("\n\ttype_id=%u offset=%u size=%u", v->type,
+			       v->offset, v->size);
+			if (v->type < btf__type_cnt(btf)) {
+				vt = btf__type_by_id(btf, v->type);
+				printf(" (%s '%s')", btf_kind_str[btf_kind(vt)],
+				       btf_str(btf, vt->name_off));
+			}
+		}
+		break;
+	}
+	case BTF_KIND_FLOAT:
+		printf(" size=%u", t->size);
+		break;
+	case BTF_KIND_DECL_TAG:
+		printf(" type_id=%u component_idx=%d", t->type,
+		       btf_decl_tag(t)->component_idx);
+		break;
+	default:
+		break;
+	}
+
+	printf("\n");
+}
+
+static char *decl_buf;
+static size_t decl_len;
+
+static void decl_printf(void *ctx, const char *fmt, va_list args)
+{
+	FILE *f = ctx;
+
+	vfprintf(f, fmt, args);
+}
+
+/* Print the declaration of a type with a given name, if it fits a line. */
+static void dump_decl(const struct btf *btf, __u32 id, const char *key,
+		      __u32 type_id, const char *name)
+{
+	LIBBPF_OPTS(btf_dump_emit_type_decl_opts, opts,
+		.field_name = name,
+	);
+	struct btf_dump *d;
+	FILE *f;
+
+	/* Use a new dumper for each declaration, as btf_dump disambiguates
+	 * names shared by different types (e.g. "foo___2") across calls.
+	 */
+	f = open_memstream(&decl_buf, &decl_len);
+	d = btf_dump__new(btf, decl_printf, f, NULL);
+	if (!f || !d) {
+		fprintf(stderr, "Could not allocate a dumper\n");
+		exit(1);
+	}
+
+	if (btf_dump__emit_type_decl(d, type_id, &opts)) {
+		fprintf(stderr, "Could not emit the declaration of [%u]\n", id);
+		exit(1);
+	}
+	btf_dump__free(d);
+	fclose(f);
+
+	if (!strchr(decl_buf, '\n'))
+		printf("%u:%s\t%s\n", id, key, decl_buf);
+	free(decl_buf);
+}
+
+static void dump_c(const struct btf *btf, __u32 id)
+{
+	const struct btf_type *t = btf__type_by_id(btf, id);
+	char key[16];
+	int i;
+
+	switch (btf_kind(t)) {
+	case BTF_KIND_FUNC:
+	case BTF_KIND_VAR:
+	case BTF_KIND_TYPEDEF:
+		dump_decl(btf, id, "-", t->type, btf_str(btf, t->name_off));
+		break;
+	case BTF_KIND_STRUCT:
+	case BTF_KIND_UNION: {
+		const struct btf_member *m = btf_members(t);
+
+		for (i = 0; i < btf_vlen(t); i++, m++) {
+			if (!m->name_off)
+				continue;
+			snprintf(key, sizeof(key), "%d", i);
+			dump_decl(btf, id, key, m->type, btf_str(btf, m->name_off));
+		}
+		break;
+	}
+	case BTF_KIND_FUNC_PROTO: {
+		const struct btf_param *p = btf_params(t);
+
+		for (i = 0; i < btf_vlen(t); i++, p++) {
+			if (!p->name_off)
+				continue;
+			snprintf(key, sizeof(key), "%d", i);
+			dump_decl(btf, id, key, p->type, btf_str(btf, p->name_off));
+		}
+		break;
+	}
+	default:
+		break;
+	}
+}
+
+int main(int argc, char **argv)
+{
+	struct btf *base, *split = NULL;
+	void (*dump)(const struct btf *, __u32);
+	__u32 id;
+	int i = 3;
+
+	if (argc < 3) {
+		fprintf(stderr, "usage: %s raw|c <base> [<split>] [<id>...]\n",
+			argv[0]);
+		return 1;
+	}
+	dump = strcmp(argv[1], "c") ? dump_raw : dump_c;
+
+	base = btf__parse_raw(argv[2]);
+	if (!base) {
+		fprintf(stderr, "Could not parse %s: %d\n", argv[2], -errno);
+		return 1;
+	}
+
+	if (argc > 3 && (argv[3][0] < '0' || argv[3][0] > '9')) {
+		split = btf__parse_raw_split(argv[3], base);
+		if (!split) {
+			fprintf(stderr, "Could not parse %s: %d\n", argv[3], -errno);
+			return 1;
+		}
+		for (id = btf__type_cnt(base); id < btf__type_cnt(split); id++)
+			dump(split, id);
+		i++;
+	}
+
+	for (; i < argc; i++)
+		dump(base, strtoul(argv[i], NULL, 10));
+
+	btf__free(split);
+	btf__free(base);
+	return 0;
+}
@@ -99,10 +99,7 @@ fn iter_types(btf: Btf) {
 
     let types: Vec<Type> = btf
         .type_iter(ml.unwrap())
-        .filter(|t| match t {
-            Type::Typedef(_) | Type::Int(_) => true,
-            _ => false,
-        })
+        .filter(|t| matches!(t, Type::Typedef(_) | Type::Int(_)))
         .collect::<Vec<_>>();
 
     assert_eq!(types.len(), 2);
@@ -130,6 +127,15 @@ fn resolve_types_by_name_unknown(btf: Btf) {
     assert!(btf.resolve_types_by_name("not_a_known_function").is_err());
 }
 
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn resolve_struct_by_name(btf: Btf) {
+    let skb = btf.resolve_struct_by_name("sk_buff").unwrap();
+    assert_eq!(btf.resolve_name(&skb).unwrap(), "sk_buff");
+    assert!(btf.resolve_struct_by_name("consume_skb").is_err());
+    assert!(btf.resolve_struct_by_name("not_a_known_struct").is_err());
+}
+
 #[test_case(bytes())]
 #[test_case(file())]
 #[cfg_attr(feature = "elf", test_case(elf()))]
@@ -235,6 +241,16 @@ fn resolve_function(btf: Btf) {
     assert!(!arg.is_signed());
     assert!(!arg.is_char());
     assert!(!arg.is_bool());
+    assert_eq!(arg.encoding(), IntEncoding::None);
+    assert_eq!((arg.offset(), arg.bits()), (0, 32));
+
+    match btf.resolve_types_by_name("_Bool").unwrap().pop().unwrap() {
+        Type::Int(int) => {
+            assert_eq!(int.encoding(), IntEncoding::Bool);
+            assert_eq!((int.size(), int.bits()), (1, 8));
+        }
+        _ => panic!("Resolved type is not an integer"),
+    }
 }
 
 #[test]
@@ -375,6 +391,29 @@ fn test_split_files() {
     }
 }
 
+#[test]
+#[cfg_attr(not(feature = "test_runtime"), ignore)]
+fn sys_btf() {
+    let Ok(vmlinux) = Btf::from_file("/sys/kernel/btf/vmlinux") else {
+        return;
+    };
+    // Requires CAP_SYS_ADMIN.
+    let Ok(ids) = utils::sys::ids() else {
+        return;
+    };
+    assert!(!ids.is_empty());
+
+    let kernel = utils::sys::kernel_btfs().unwrap();
+    assert_eq!(kernel.iter().filter(|b| b.name == "vmlinux").count(), 1);
+    assert_eq!(
+        utils::sys::vmlinux().unwrap().to_bytes().unwrap(),
+        vmlinux.to_bytes().unwrap()
+    );
+
+    let btfc = utils::sys::collection().unwrap();
+    assert_eq!(btfc.split_btfs().count(), kernel.len() - 1);
+}
+
 fn btfc_files() -> utils::collection::BtfCollection {
     let mut btfc = utils::collection::BtfCollection::from_file("tests/data/btf/vmlinux").unwrap();
     btfc.add_split_btf_from_file("tests/data/btf/openvswitch")
@@ -409,14 +448,21 @@ fn btfc_elf() -> utils::collection::BtfCollection {
 fn btfc(btfc: utils::collection::BtfCollection) {
     // Resolve a function from vmlinux.
     let mut types = btfc.resolve_types_by_name("vmalloc").unwrap();
-    let (nbtf, func) = match types.pop().unwrap() {
-        (nbtf, Type::Func(func)) => (nbtf, func),
+    let m = types.pop().unwrap();
+    let func = match m.r#type {
+        Type::Func(func) => func,
         _ => panic!("Resolved type is not a function"),
     };
 
-    assert_eq!(nbtf.resolve_name(&func).unwrap(), "vmalloc");
+    assert_eq!(m.btf.resolve_name(&func).unwrap(), "vmalloc");
+    assert_eq!(
+        m.btf.resolve_type_by_id(m.id).unwrap(),
+        Type::Func(func.clone())
+    );
 
-    let (nbtf, func_id) = btfc.resolve_ids_by_name("vmalloc").unwrap().pop().unwrap();
+    let m = btfc.resolve_ids_by_name("vmalloc").unwrap().pop().unwrap();
+    let (nbtf, func_id) = (m.btf, m.id);
+    assert!(matches!(m.resolve().unwrap().r#type, Type::Func(_)));
     let func = match nbtf.resolve_type_by_id(func_id).unwrap() {
         Type::Func(func) => func,
         _ => panic!("Resolved type is not a function"),
@@ -428,18 +474,21 @@ fn btfc(btfc: utils::collection::BtfCollection) {
     let mut types = btfc
         .resolve_types_by_name("queue_userspace_packet")
         .unwrap();
-    let (nbtf, func) = match types.pop().unwrap() {
-        (nbtf, Type::Func(func)) => (nbtf, func),
+    let m = types.pop().unwrap();
+    let func = match m.r#type {
+        Type::Func(func) => func,
         _ => panic!("Resolved type is not a function"),
     };
 
-    assert_eq!(nbtf.resolve_name(&func).unwrap(), "queue_userspace_packet");
+    assert_eq!(m.btf.name, "openvswitch");
+    assert_eq!(m.btf.resolve_name(&func).unwrap(), "queue_userspace_packet");
 
-    let (nbtf, func_id) = btfc
+    let m = btfc
         .resolve_ids_by_name("queue_userspace_packet")
         .unwrap()
         .pop()
         .unwrap();
+    let (nbtf, func_id) = (m.btf, m.id);
     let func = match nbtf.resolve_type_by_id(func_id).unwrap() {
         Type::Func(func) => func,
         _ => panic!("Resolved type is not a function"),
@@ -458,3 +507,2860 @@ fn btfc(btfc: utils::collection::BtfCollection) {
     };
     assert_eq!(ovs.resolve_name(&func).unwrap(), "queue_userspace_packet");
 }
+
+#[test]
+#[cfg(feature = "elf")]
+fn btfc_elf_symlinks() {
+    use std::{fs, os::unix::fs::symlink, path::Path};
+
+    let data = fs::canonicalize("tests/data/linux_build").unwrap();
+    let dir = std::env::temp_dir().join(format!("btf-rs-kernel-dir-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("a")).unwrap();
+    fs::create_dir_all(dir.join("b")).unwrap();
+
+    symlink(data.join("vmlinux"), dir.join("vmlinux")).unwrap();
+    // Symlink loop.
+    symlink(&dir, dir.join("a/loop")).unwrap();
+    // Same module found twice.
+    let ovs = data.join("net/openvswitch/openvswitch.ko");
+    symlink(&ovs, dir.join("a/openvswitch.ko")).unwrap();
+    symlink(&ovs, dir.join("b/openvswitch.ko")).unwrap();
+    // Compressed variant of a module, along with its uncompressed version.
+    fs::write(dir.join("a/veth.ko.xz"), b"").unwrap();
+    symlink(data.join("drivers/net/veth.ko"), dir.join("b/veth.ko")).unwrap();
+    // Compressed-only module.
+    fs::write(dir.join("b/mpls_gso.ko.zst"), b"").unwrap();
+
+    let btfc = utils::elf::collection_from_kernel_dir(Path::new(&dir));
+    let report = utils::elf::KernelDirLoader::new(&dir).load_with_report();
+    fs::remove_dir_all(&dir).unwrap();
+    let btfc = btfc.unwrap();
+
+    assert!(btfc.get_named_btf("openvswitch").is_some());
+    assert!(btfc.get_named_btf("veth").is_some());
+    assert!(btfc.get_named_btf("mpls_gso").is_none());
+
+    let (_, report) = report.unwrap();
+    assert_eq!(report.loaded, vec!["openvswitch", "veth"]);
+    assert_eq!(report.compressed, vec!["mpls_gso"]);
+    assert_eq!(report.duplicates, vec!["openvswitch", "veth"]);
+}
+
+#[test_case(btfc_dir())]
+#[cfg_attr(feature = "elf", test_case(btfc_elf()))]
+fn module_deps(btfc: utils::collection::BtfCollection) {
+    let deps = utils::moddep::ModuleDeps::from_kernel_dir("tests/data/linux_build").unwrap();
+
+    assert_eq!(deps.modules().count(), 6);
+    assert_eq!(deps.dependencies("veth").unwrap().len(), 0);
+    assert_eq!(deps.dependencies("openvswitch").unwrap()[0], "nsh");
+    assert!(deps.dependencies("invalid_module").is_none());
+
+    let chain = deps.dependency_chain("vport_gre").unwrap();
+    assert_eq!(chain.len(), 11);
+    assert!(chain.contains(&"openvswitch"));
+    assert!(!chain.contains(&"vport_gre"));
+
+    // Dashes are converted to underscores, both in modules and dependencies.
+    assert!(deps.dependencies("vport-geneve").is_none());
+    let geneve = deps.dependencies("vport_geneve").unwrap();
+    assert!(geneve.iter().any(|m| m == "ip6_udp_tunnel"));
+    assert!(geneve.iter().any(|m| m == "openvswitch"));
+    assert!(deps.modules().all(|m| !m.contains('-')));
+
+    let order = deps.load_order().unwrap();
+    let pos = |m| order.iter().position(|x| *x == m).unwrap();
+    assert!(pos("nsh") < pos("openvswitch"));
+    assert!(pos("openvswitch") < pos("vport_gre"));
+    assert!(pos("openvswitch") < pos("vport_vxlan"));
+
+    let sorted = deps.sort_collection(&btfc).unwrap();
+    assert_eq!(sorted.len(), btfc.split_btfs().count());
+    if sorted.len() > 1 {
+        let pos = |m| sorted.iter().position(|x| x.name == m).unwrap();
+        assert!(pos("openvswitch") < pos("vport_gre"));
+        assert!(btfc.get_named_btf("vport_geneve").is_some());
+    }
+
+    let deps = utils::moddep::ModuleDeps::parse("a-b.ko: c-d.ko\nc-d.ko:\n").unwrap();
+    assert_eq!(deps.dependency_chain("a_b").unwrap(), vec!["c_d"]);
+
+    assert!(utils::moddep::ModuleDeps::parse("a.ko: b.ko\nb.ko: a.ko\n")
+        .unwrap()
+        .load_order()
+        .is_err());
+}
+
+#[test]
+fn probe_report() {
+    use std::fs;
+    use utils::probe::*;
+
+    let root = std::env::temp_dir().join(format!("btf-rs-probe-{}", std::process::id()));
+    _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("proc/sys/kernel")).unwrap();
+    fs::create_dir_all(root.join("boot")).unwrap();
+    fs::write(root.join("proc/sys/kernel/osrelease"), "6.0.0-test\n").unwrap();
+    fs::write(root.join("boot/config-6.0.0-test"), "CONFIG_BPF=y\n").unwrap();
+
+    // Nothing available, kernel built without BTF.
+    let report = report_from(&root);
+    assert_eq!(report.release.as_deref(), Some("6.0.0-test"));
+    assert_eq!(report.config_btf, Some(false));
+    assert_eq!(report.vmlinux.status, Status::Missing);
+    assert_eq!(report.modules_dir.status, Status::Missing);
+    assert!(!report.is_available());
+    assert!(report.reason().unwrap().contains("CONFIG_DEBUG_INFO_BTF"));
+
+    // Invalid vmlinux BTF.
+    fs::create_dir_all(root.join("sys/kernel/btf")).unwrap();
+    fs::write(root.join("sys/kernel/btf/vmlinux"), b"").unwrap();
+    let report = report_from(&root);
+    assert!(matches!(report.vmlinux.status, Status::Invalid(_)));
+    assert!(report.reason().unwrap().starts_with("could not parse"));
+
+    // BTF available.
+    fs::copy(
+        "tests/data/btf/vmlinux",
+        root.join("sys/kernel/btf/vmlinux"),
+    )
+    .unwrap();
+    fs::copy(
+        "tests/data/btf/openvswitch",
+        root.join("sys/kernel/btf/openvswitch"),
+    )
+    .unwrap();
+    let report = report_from(&root);
+    assert_eq!(report.vmlinux.status, Status::Available);
+    assert_eq!(report.sysfs_modules, 1);
+    assert!(report.is_available());
+    assert!(report.reason().is_none());
+    assert!(report.modules_reason().is_none());
+
+    // Stripped modules.
+    #[cfg(feature = "elf")]
+    {
+        fs::remove_file(root.join("sys/kernel/btf/openvswitch")).unwrap();
+        let dir = root.join("usr/lib/modules/6.0.0-test/kernel/net/openvswitch");
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(
+            "tests/data/nobtf/openvswitch.ko",
+            dir.join("openvswitch.ko"),
+        )
+        .unwrap();
+        let report = report_from(&root);
+        assert_eq!(report.modules_dir.status, Status::Available);
+        let module = report.module.as_ref().unwrap();
+        assert_eq!(module.path, dir.join("openvswitch.ko"));
+        assert_eq!(module.status, Status::NoBtf);
+        assert!(report.modules_reason().unwrap().contains("stripped"));
+
+        fs::copy(
+            "tests/data/linux_build/net/openvswitch/openvswitch.ko",
+            dir.join("openvswitch.ko"),
+        )
+        .unwrap();
+        let report = report_from(&root);
+        assert_eq!(report.module.unwrap().status, Status::Available);
+    }
+
+    // Kernel configuration from /proc/config.gz.
+    #[cfg(feature = "gzip")]
+    {
+        use std::io::Write;
+
+        fs::remove_file(root.join("boot/config-6.0.0-test")).unwrap();
+        assert_eq!(report_from(&root).config_btf, None);
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(b"CONFIG_BPF=y\nCONFIG_DEBUG_INFO_BTF=y\n")
+            .unwrap();
+        fs::write(root.join("proc/config.gz"), gz.finish().unwrap()).unwrap();
+        assert_eq!(report_from(&root).config_btf, Some(true));
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn btfc_concatenated() {
+    let mut bytes = read("tests/data/btf/vmlinux").unwrap();
+    bytes.extend(read("tests/data/btf/openvswitch").unwrap());
+
+    let btfc = utils::collection::BtfCollection::from_concatenated_bytes(&bytes, |i| match i {
+        0 => "vmlinux".to_string(),
+        _ => format!("split{i}"),
+    })
+    .unwrap();
+
+    let split = btfc.get_named_btf("split1").unwrap();
+    assert_eq!(split.source(), SourceKind::Bytes);
+    assert!(split.is_split());
+    assert!(split.resolve_ids_by_name("queue_userspace_packet").is_ok());
+    assert_eq!(btfc.split_btfs().count(), 1);
+
+    // Truncated stream.
+    assert!(utils::collection::BtfCollection::from_concatenated_bytes(
+        &bytes[..bytes.len() - 1],
+        |i| i.to_string()
+    )
+    .is_err());
+}
+
+#[test]
+fn extended_header() {
+    let raw = read("tests/data/btf/vmlinux").unwrap();
+    assert_eq!(Btf::from_bytes(&raw).unwrap().flags(), 0);
+
+    // Build a BTF object with an extended header.
+    let extend = |tail: &[u8]| {
+        let mut ext = raw[..24].to_vec();
+        let hdr_len = u32::from_le_bytes(ext[4..8].try_into().unwrap()) + tail.len() as u32;
+        ext[4..8].copy_from_slice(&hdr_len.to_le_bytes());
+        ext.extend_from_slice(tail);
+        ext.extend_from_slice(&raw[24..]);
+        ext
+    };
+
+    let btf = Btf::from_bytes(&extend(&[0; 8])).unwrap();
+    assert_eq!(btf.type_ids(), bytes().type_ids());
+    assert_eq!(btf.resolve_ids_by_name("sk_buff").unwrap(), vec![4984]);
+
+    // Unknown non-zero header fields.
+    assert!(Btf::from_bytes(&extend(&[0, 0, 0, 0, 1, 0, 0, 0])).is_err());
+
+    // Header too short.
+    let mut short = raw.clone();
+    short[4..8].copy_from_slice(&16u32.to_le_bytes());
+    assert!(Btf::from_bytes(&short).is_err());
+
+    // Header length larger than the object itself.
+    let mut huge = raw[..24].to_vec();
+    huge[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(Btf::from_bytes(&huge).is_err());
+}
+
+#[test]
+fn lossy_strings() {
+    // Corrupt the "sk_buff" string with an invalid UTF-8 byte.
+    let mut raw = read("tests/data/btf/vmlinux").unwrap();
+    let pos = raw.windows(9).position(|w| w == b"\0sk_buff\0").unwrap();
+    raw[pos + 1] = 0xff;
+
+    assert!(Btf::from_bytes(&raw).is_err());
+
+    let opts = BtfOptions::new().string_decoding(StringDecoding::Lossy);
+    let btf = Btf::from_bytes_with_options(&raw, &opts).unwrap();
+    let skb = match btf.resolve_type_by_id(4984).unwrap() {
+        Type::Struct(skb) => skb,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    assert_eq!(btf.resolve_name(&skb).unwrap(), "\u{fffd}k_buff");
+    assert_eq!(btf.resolve_name_bytes(&skb).unwrap(), b"\xffk_buff");
+    assert_eq!(
+        btf.resolve_name_bytes(&skb.members[0]).unwrap(),
+        btf.resolve_name(&skb.members[0]).unwrap().as_bytes()
+    );
+
+    assert_eq!(
+        btf.resolve_ids_by_name_bytes(b"\xffk_buff").unwrap(),
+        vec![4984]
+    );
+    assert_eq!(
+        btf.resolve_ids_by_name("\u{fffd}k_buff").unwrap(),
+        vec![4984]
+    );
+    assert!(btf.names().iter().all(|n| !n.is_empty()));
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn resolve_by_name_bytes(btf: Btf) {
+    assert_eq!(
+        btf.resolve_ids_by_name_bytes(b"sk_buff").unwrap(),
+        btf.resolve_ids_by_name("sk_buff").unwrap()
+    );
+    assert!(btf.resolve_ids_by_name_bytes(b"not_a_symbol").is_err());
+    assert!(btf.resolve_ids_by_name_bytes(b"\xff\xfe").is_err());
+}
+
+#[test]
+fn btfc_dir_loader() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("btf-rs-dir-loader-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy("tests/data/btf/vmlinux", dir.join("vmlinux")).unwrap();
+    fs::copy("tests/data/btf/openvswitch", dir.join("openvswitch")).unwrap();
+    fs::write(dir.join("broken"), b"not BTF").unwrap();
+
+    let mut loader = utils::collection::BtfCollection::dir_loader(&dir, "vmlinux").unwrap();
+    assert_eq!(loader.remaining(), 2);
+    assert!(matches!(loader.next(), Some(Err((name, _))) if name == "broken"));
+    assert!(matches!(loader.next(), Some(Ok(name)) if name == "openvswitch"));
+    assert!(loader.next().is_none());
+    assert!(loader.collection().get_named_btf("broken").is_none());
+    assert!(loader
+        .into_collection()
+        .get_named_btf("openvswitch")
+        .is_some());
+
+    assert!(utils::collection::BtfCollection::from_dir(&dir, "vmlinux").is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn btfc_dir_denied() {
+    use std::{fs, os::unix::fs::PermissionsExt};
+    use utils::collection::{BtfCollection, DirOptions};
+
+    let dir = std::env::temp_dir().join(format!("btf-rs-dir-denied-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy("tests/data/btf/vmlinux", dir.join("vmlinux")).unwrap();
+    fs::copy("tests/data/btf/openvswitch", dir.join("openvswitch")).unwrap();
+
+    let opts = DirOptions::new().skip_permission_denied(true);
+    let btfc = BtfCollection::from_dir_with_options(&dir, "vmlinux", &opts).unwrap();
+    assert!(btfc.get_named_btf("openvswitch").is_some());
+    assert!(btfc.denied_split_btfs().is_empty());
+
+    fs::copy("tests/data/btf/openvswitch", dir.join("denied")).unwrap();
+    fs::set_permissions(dir.join("denied"), fs::Permissions::from_mode(0o000)).unwrap();
+
+    // Permissions are not enforced for privileged users.
+    if fs::read(dir.join("denied")).is_err() {
+        assert!(BtfCollection::from_dir(&dir, "vmlinux").is_err());
+
+        let btfc = BtfCollection::from_dir_with_options(&dir, "vmlinux", &opts).unwrap();
+        assert!(btfc.get_named_btf("openvswitch").is_some());
+        assert!(btfc.get_named_btf("denied").is_none());
+        assert_eq!(btfc.denied_split_btfs(), ["denied"]);
+        assert_eq!(btfc.clone().denied_split_btfs(), ["denied"]);
+    }
+
+    // Other errors are still reported.
+    fs::write(dir.join("broken"), b"not BTF").unwrap();
+    assert!(BtfCollection::from_dir_with_options(&dir, "vmlinux", &opts).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "elf")]
+fn btfc_elf_base_detection() {
+    use std::{fs, os::unix::fs::symlink};
+    use utils::elf::KernelDirLoader;
+
+    let data = fs::canonicalize("tests/data/linux_build").unwrap();
+    let dir = std::env::temp_dir().join(format!("btf-rs-kernel-base-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    fs::write(dir.join("README"), b"Linux kernel").unwrap();
+    symlink(data.join("vmlinux"), dir.join("vmlinux.o")).unwrap();
+    symlink(
+        data.join("net/openvswitch/openvswitch.ko"),
+        dir.join("openvswitch.ko"),
+    )
+    .unwrap();
+
+    let detected = KernelDirLoader::new(&dir).load();
+    let explicit = KernelDirLoader::new(&dir).base("vmlinux.o").load();
+    let invalid = KernelDirLoader::new(&dir).base("README").load();
+    fs::remove_dir_all(&dir).unwrap();
+
+    for btfc in [detected.unwrap(), explicit.unwrap()] {
+        let ovs = btfc.get_named_btf("openvswitch").unwrap();
+        assert!(ovs.resolve_ids_by_name("queue_userspace_packet").is_ok());
+        let m = btfc.resolve_ids_by_name("sk_buff").unwrap().pop().unwrap();
+        let nbtf = m.btf;
+        assert_eq!(nbtf.name, "vmlinux");
+    }
+    assert!(invalid.is_err());
+}
+
+#[test]
+#[cfg(feature = "elf")]
+fn btfc_elf_missing_btf() {
+    use std::{fs, os::unix::fs::symlink};
+    use utils::elf::KernelDirLoader;
+
+    let data = fs::canonicalize("tests/data").unwrap();
+    let dir = std::env::temp_dir().join(format!("btf-rs-kernel-nobtf-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    symlink(data.join("linux_build/vmlinux"), dir.join("vmlinux")).unwrap();
+    symlink(
+        data.join("linux_build/drivers/net/veth.ko"),
+        dir.join("veth.ko"),
+    )
+    .unwrap();
+    // ELF module without a .BTF section.
+    symlink(
+        data.join("nobtf/openvswitch.ko"),
+        dir.join("openvswitch.ko"),
+    )
+    .unwrap();
+    fs::write(dir.join("mpls_gso.ko.xz"), b"").unwrap();
+
+    let strict = KernelDirLoader::new(&dir).load();
+    let skipped = KernelDirLoader::new(&dir)
+        .skip_missing_btf(true)
+        .load_with_report();
+    let fallback = KernelDirLoader::new(&dir)
+        .fallback_dir(data.join("btf"))
+        .load_with_report();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(strict.is_err());
+
+    let (btfc, report) = skipped.unwrap();
+    assert!(btfc.get_named_btf("openvswitch").is_none());
+    assert_eq!(report.loaded, vec!["veth"]);
+    assert_eq!(report.skipped, vec!["openvswitch"]);
+    assert_eq!(report.compressed, vec!["mpls_gso"]);
+
+    let (btfc, report) = fallback.unwrap();
+    let ovs = btfc.get_named_btf("openvswitch").unwrap();
+    assert!(ovs.resolve_ids_by_name("queue_userspace_packet").is_ok());
+    assert_eq!(report.fallback, vec!["openvswitch"]);
+    assert!(report.skipped.is_empty());
+}
+
+#[test]
+#[cfg(feature = "elf")]
+fn elf_build_id() {
+    let (btf, id) =
+        utils::elf::extract_btf_and_build_id("tests/data/linux_build/drivers/net/veth.ko").unwrap();
+    assert_eq!(
+        btf,
+        utils::elf::extract_btf_from_file("tests/data/linux_build/drivers/net/veth.ko").unwrap()
+    );
+    assert_eq!(
+        id.unwrap()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>(),
+        "4d85fd54b9524985c5cddd3b090440a965fa313c"
+    );
+
+    // The test vmlinux image has no build-id.
+    let (_, id) = utils::elf::extract_btf_and_build_id("tests/data/linux_build/vmlinux").unwrap();
+    assert!(id.is_none());
+}
+
+#[test]
+fn btfc_lookup_cache() {
+    use utils::collection::*;
+
+    let mut btfc = BtfCollection::from_file("tests/data/btf/vmlinux").unwrap();
+    assert!(btfc.resolve_ids_by_name("queue_userspace_packet").is_err());
+    assert!(btfc.resolve_ids_by_name("queue_userspace_packet").is_err());
+    let m = btfc.resolve_ids_by_name("sk_buff").unwrap().pop().unwrap();
+    let (nbtf, id) = (m.btf, m.id);
+    assert_eq!((nbtf.name.as_str(), id), ("vmlinux", TypeId::from(4984)));
+    let m = btfc.resolve_ids_by_name("sk_buff").unwrap().pop().unwrap();
+    let (nbtf, id) = (m.btf, m.id);
+    assert_eq!((nbtf.name.as_str(), id), ("vmlinux", TypeId::from(4984)));
+    assert_eq!(
+        btfc.lookup_cache_stats(),
+        LookupCacheStats {
+            hits: 1,
+            misses: 3,
+            len: 1,
+            ..Default::default()
+        }
+    );
+
+    // Adding a split BTF invalidates the cache.
+    btfc.add_split_btf_from_file("tests/data/btf/openvswitch")
+        .unwrap();
+    assert_eq!(btfc.lookup_cache_stats().len, 0);
+    let m = btfc
+        .resolve_types_by_name("queue_userspace_packet")
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(m.btf.name, "openvswitch");
+
+    // Least recently used entries are evicted.
+    btfc.set_lookup_cache_capacity(2);
+    for name in ["sk_buff", "u64", "sk_buff", "int"] {
+        btfc.resolve_ids_by_name(name).unwrap();
+    }
+    assert_eq!(btfc.lookup_cache_stats().len, 2);
+    btfc.resolve_ids_by_name("sk_buff").unwrap();
+    assert_eq!(btfc.lookup_cache_stats().hits, 2);
+    btfc.resolve_ids_by_name("u64").unwrap();
+    assert_eq!(btfc.lookup_cache_stats().hits, 2);
+
+    btfc.set_lookup_cache_capacity(0);
+    btfc.resolve_ids_by_name("sk_buff").unwrap();
+    assert_eq!(btfc.lookup_cache_stats(), LookupCacheStats::default());
+}
+
+#[test]
+fn btfc_negative_lookup_cache() {
+    let mut btfc = utils::collection::BtfCollection::from_file("tests/data/btf/vmlinux").unwrap();
+    btfc.set_negative_lookup_cache_capacity(1);
+
+    for _ in 0..3 {
+        assert!(btfc.resolve_ids_by_name("queue_userspace_packet").is_err());
+    }
+    let stats = btfc.lookup_cache_stats();
+    assert_eq!((stats.misses, stats.negative_hits), (1, 2));
+    assert_eq!(stats.negative_len, 1);
+
+    // Bounded.
+    assert!(btfc.resolve_types_by_name("not_a_symbol").is_err());
+    assert_eq!(btfc.lookup_cache_stats().negative_len, 1);
+
+    // Adding a split BTF invalidates negative results.
+    btfc.add_split_btf_from_file("tests/data/btf/openvswitch")
+        .unwrap();
+    assert_eq!(btfc.lookup_cache_stats().negative_len, 0);
+    assert!(btfc.resolve_ids_by_name("queue_userspace_packet").is_ok());
+}
+
+#[test]
+#[cfg(feature = "metrics")]
+fn metrics() {
+    let btf = bytes();
+    assert_eq!(btf.metrics(), Metrics::default());
+
+    btf.resolve_ids_by_name("sk_buff").unwrap();
+    btf.resolve_types_by_name("sk_buff").unwrap();
+    assert!(btf.resolve_ids_by_name("not_a_symbol").is_err());
+    btf.resolve_type_by_id(4984).unwrap();
+    assert!(btf.resolve_type_by_id(u32::MAX).is_err());
+
+    let metrics = btf.metrics();
+    assert_eq!(metrics.name_hits, 2);
+    assert_eq!(metrics.name_misses, 1);
+    assert_eq!(metrics.id_hits, 1);
+    assert_eq!(metrics.id_misses, 1);
+    assert_eq!(metrics.lookup_time.iter().sum::<u64>(), 5);
+}
+
+#[test]
+fn from_reader() {
+    use std::io::{Cursor, Seek, SeekFrom};
+
+    // Objects don't have to start at the beginning of the reader.
+    let raw = read("tests/data/btf/vmlinux").unwrap();
+    let mut reader = Cursor::new([b"junk".as_slice(), &raw].concat());
+    reader.seek(SeekFrom::Start(4)).unwrap();
+    let btf = Btf::from_reader(&mut reader).unwrap();
+    assert_eq!(btf.source(), SourceKind::Reader);
+    assert_eq!(btf.to_bytes().unwrap(), raw);
+
+    let file = std::fs::File::open("tests/data/btf/openvswitch").unwrap();
+    let ovs = Btf::from_split_reader(file, &btf).unwrap();
+    assert!(ovs.is_split());
+    assert_eq!(ovs.resolve_ids_by_name("ovs_vport_send").unwrap().len(), 1);
+
+    assert!(Btf::from_reader(Cursor::new(&raw[..1024])).is_err());
+}
+
+#[test]
+fn clone_threads() {
+    let btf = bytes();
+    let btfc = btfc_files();
+    btfc.resolve_ids_by_name("sk_buff").unwrap();
+    let fingerprint = btf.fingerprint();
+
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            let (btf, btfc) = (btf.clone(), btfc.clone());
+            s.spawn(move || {
+                assert_eq!(btf.fingerprint(), fingerprint);
+                assert_eq!(btf.resolve_ids_by_name("sk_buff").unwrap().len(), 1);
+                // Clones have their own, empty, lookup cache.
+                assert_eq!(btfc.lookup_cache_stats().len, 0);
+                assert_eq!(btfc.resolve_ids_by_name("ovs_vport_send").unwrap().len(), 1);
+            });
+        }
+    });
+    assert_eq!(btfc.lookup_cache_stats().len, 1);
+}
+
+#[test]
+fn send_sync() {
+    fn is_send_sync<T: Send + Sync>(_: &T) {}
+
+    let btf = bytes();
+    let btfc = btfc_files();
+    is_send_sync(&btf);
+    is_send_sync(&btfc);
+
+    // Share the same objects, not clones, between threads.
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                let skb = btf.resolve_named_types_by_name("sk_buff").unwrap();
+                is_send_sync(&skb);
+                assert_eq!(skb[0].name.as_deref(), Some("sk_buff"));
+                assert_eq!(btfc.resolve_ids_by_name("ovs_vport_send").unwrap().len(), 1);
+            });
+        }
+    });
+    assert_eq!(btfc.lookup_cache_stats().len, 1);
+}
+
+#[test]
+fn error_context() {
+    fn is_send_sync<T: Send + Sync + 'static>(_: &T) {}
+
+    let err = Btf::from_file("tests/data/btf/not_a_file").err().unwrap();
+    is_send_sync(&err);
+    assert!(err.to_string().contains("tests/data/btf/not_a_file"));
+    assert_eq!(
+        err.downcast_ref::<std::io::Error>().unwrap().kind(),
+        std::io::ErrorKind::NotFound
+    );
+
+    // Set an invalid kind to the first type.
+    let mut raw = read("tests/data/btf/vmlinux").unwrap();
+    raw[31] = 0x1f;
+    let err = Btf::from_bytes(&raw).err().unwrap();
+    assert_eq!(err.to_string(), "Could not parse type 1 at offset 24");
+    assert_eq!(err.root_cause().to_string(), "Unsupported BTF type '31'");
+}
+
+#[test]
+fn tolerant_parsing() {
+    let mut builder = encode::BtfBuilder::new();
+    let int = builder.add_int("int", 4, true).unwrap();
+    let ptr = builder.add_ptr(int).unwrap();
+    builder.add_typedef("t", ptr).unwrap();
+    builder.add_typedef("u", int).unwrap();
+    let mut raw = builder.to_bytes().unwrap();
+
+    // Turn the pointer (second record, after the 16 bytes int one) into a
+    // vendor specific kind.
+    assert_eq!(
+        Btf::from_bytes(&raw).unwrap().endianness(),
+        Endianness::Little
+    );
+    raw[24 + 16 + 7] = 25;
+
+    assert!(Btf::from_bytes(&raw).is_err());
+    // Unknown layout.
+    let opts = BtfOptions::new().tolerant(true);
+    assert!(Btf::from_bytes_with_options(&raw, &opts).is_err());
+
+    let opts = opts.kind_layout(25, 0, 0);
+    let btf = Btf::from_bytes_with_options(&raw, &opts).unwrap();
+    assert_eq!(btf.skipped_types().len(), 1);
+    let skipped = &btf.skipped_types()[0];
+    assert_eq!((skipped.id, skipped.kind, skipped.offset), (ptr, 25, 16));
+    assert_eq!(skipped.reason, "Unsupported BTF type '25'");
+
+    // Ids are preserved, the skipped one being unused.
+    assert_eq!(btf.type_ids(), 0..5);
+    assert!(btf.resolve_type_by_id(ptr).is_err());
+    assert_eq!(btf.resolve_ids_by_name("u").unwrap(), vec![TypeId::from(4)]);
+    assert_eq!(btf.dangling_references(), vec![(TypeId::from(3), ptr)]);
+    assert!(btf.to_bytes().is_err());
+
+    assert!(bytes().skipped_types().is_empty());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn dump_record(btf: Btf) {
+    use utils::debug::dump_record_to_string;
+
+    let id = btf.resolve_ids_by_name("int").unwrap()[0];
+    assert_eq!(
+        dump_record_to_string(&btf, id).unwrap(),
+        "[11] INT 'int' size=4 bits_offset=0 nr_bits=32 encoding=SIGNED
+  header 0000: 5c 00 00 00 00 00 00 01 04 00 00 00  name_off=92 info=0x01000000 (kind=1 kind_flag=0 vlen=0) size_type=4
+  data   000c: 20 00 00 01
+"
+    );
+
+    let id = btf.resolve_ids_by_name("sk_buff").unwrap()[0];
+    let out = dump_record_to_string(&btf, id).unwrap();
+    // Title, header and one line per member.
+    assert_eq!(out.lines().count(), 2 + 28);
+    assert!(out
+        .lines()
+        .nth(2)
+        .unwrap()
+        .ends_with("'(anon)' type_id=5280 bits_offset=0"));
+
+    if btf.is_split() {
+        let id = btf.resolve_ids_by_name("ovs_vport_send").unwrap()[0];
+        assert!(dump_record_to_string(&btf, id)
+            .unwrap()
+            .contains("FUNC 'ovs_vport_send'"));
+    }
+    assert!(dump_record_to_string(&btf, 0).is_err());
+}
+
+#[test]
+fn dump_record_original_bytes() {
+    // Set unused bits of the info field of the first record, which are
+    // ignored by the parser.
+    let mut raw = read("tests/data/btf/vmlinux").unwrap();
+    raw[24 + 6] = 0x42;
+    let hex: Vec<String> = raw[24..36].iter().map(|b| format!("{b:02x}")).collect();
+
+    // Records are either kept or encoded back, which preserves unused bits.
+    for keep in [false, true] {
+        let opts = BtfOptions::new().keep_raw_records(keep);
+        let btf = Btf::from_bytes_with_options(&raw, &opts).unwrap();
+
+        let out = utils::debug::dump_record_to_string(&btf, 1).unwrap();
+        let header = out.lines().nth(1).unwrap();
+        assert!(header.starts_with(&format!("  header 0000: {}", hex.join(" "))));
+    }
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+#[cfg(feature = "serde")]
+fn resolved_types(btf: Btf) {
+    use utils::resolved::*;
+
+    let int = resolve_by_id(&btf, 11).unwrap();
+    assert_eq!(
+        serde_json::to_string(&int).unwrap(),
+        r#"{"kind":"Int","name":"int","size":4,"signed":true,"char":false,"bool":false,"encoding":"Signed","bit_offset":0,"bits":32}"#
+    );
+    assert_eq!(
+        serde_json::from_str::<ResolvedType>(&serde_json::to_string(&int).unwrap()).unwrap(),
+        int
+    );
+
+    // Data serialized before the encoding field was added still loads.
+    assert_eq!(
+        serde_json::from_str::<ResolvedType>(
+            r#"{"kind":"Int","name":"int","size":4,"signed":true,"char":false,"bool":false,"bit_offset":0,"bits":32}"#
+        )
+        .unwrap(),
+        int
+    );
+    let ResolvedType::Int { encoding, .. } = serde_json::from_str(
+        r#"{"kind":"Int","name":"_Bool","size":1,"signed":false,"char":false,"bool":true,"bit_offset":0,"bits":8}"#,
+    )
+    .unwrap() else {
+        panic!("Not an Int");
+    };
+    assert_eq!(encoding, IntEncoding::Bool);
+
+    let id = btf.resolve_ids_by_name("sk_buff").unwrap()[0];
+    let sk_buff = resolve_by_id(&btf, id).unwrap();
+    let ResolvedType::Struct {
+        name,
+        size,
+        members,
+    } = &sk_buff
+    else {
+        panic!("sk_buff is not a struct");
+    };
+    assert_eq!(
+        (name.as_deref(), *size, members.len()),
+        (Some("sk_buff"), 232, 28)
+    );
+    assert_eq!(members[2].name, None);
+    let fclone = members
+        .iter()
+        .find(|m| m.name.as_deref() == Some("fclone"))
+        .unwrap();
+    assert_eq!((fclone.bit_offset, fclone.bitfield_size), (1010, Some(2)));
+
+    let json = serde_json::to_string(&sk_buff).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ResolvedType>(&json).unwrap(),
+        sk_buff
+    );
+
+    if btf.is_split() {
+        let id = btf.resolve_ids_by_name("ovs_vport_send").unwrap()[0];
+        assert!(matches!(
+            resolve_by_id(&btf, id).unwrap(),
+            ResolvedType::Func { name, linkage: Linkage::Static, .. } if name == "ovs_vport_send"
+        ));
+    }
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn type_id(btf: Btf) {
+    let id = btf.resolve_ids_by_name("sk_buff").unwrap().pop().unwrap();
+    assert_eq!(id, TypeId::from(4984));
+    assert_eq!(id, 4984);
+    assert_eq!(u32::from(id), 4984);
+    assert_eq!(id.to_string(), "4984");
+
+    // Resolvers accept both TypeId and u32.
+    assert_eq!(
+        btf.resolve_type_by_id(id).unwrap(),
+        btf.resolve_type_by_id(4984).unwrap()
+    );
+    let err = btf
+        .resolve_type_by_id(TypeId::from(u32::MAX))
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), format!("No type with id {}", u32::MAX));
+}
+
+#[test]
+fn str_offset() {
+    let btf = split_file();
+
+    let skb = match btf.resolve_type_by_id(4984).unwrap() {
+        Type::Struct(skb) => skb,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    let skb_off = skb.get_name_offset().unwrap();
+    assert_eq!(StrOffset::from(u32::from(skb_off)), skb_off);
+    assert_eq!(skb_off.to_string(), u32::from(skb_off).to_string());
+
+    // Split strings offsets follow the base ones.
+    let datapath = match btf
+        .resolve_types_by_name("datapath")
+        .unwrap()
+        .pop()
+        .unwrap()
+    {
+        Type::Struct(datapath) => datapath,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    assert!(datapath.get_name_offset().unwrap() > skb_off);
+    assert_eq!(btf.resolve_name(&datapath).unwrap(), "datapath");
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn as_btf_type(btf: Btf) {
+    let r#type = btf.resolve_type_by_id(4984).unwrap();
+    assert_eq!(btf.resolve_name(&r#type).unwrap(), "sk_buff");
+    assert_eq!(btf.resolve_name_bytes(&r#type).unwrap(), b"sk_buff");
+
+    let ptr = btf
+        .resolve_types_by_name("kfree_skb_reason")
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert!(matches!(
+        btf.resolve_chained_type(&ptr).unwrap(),
+        Type::FuncProto(_)
+    ));
+
+    assert!(btf.resolve_name(&Type::Void).is_err());
+    assert!(btf.resolve_chained_type(&Type::Void).is_err());
+    assert_eq!(btf.type_iter(&Type::Void).count(), 0);
+}
+
+#[test]
+fn void_type() {
+    assert!(Type::Void.as_btf_type().get_name_offset().is_err());
+    assert!(Type::Void.as_btf_type().get_type_id().is_err());
+    assert!(VoidType.get_type_id().is_err());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn expect_chain(btf: Btf) {
+    let func = btf
+        .resolve_types_by_name("kfree_skb_reason")
+        .unwrap()
+        .pop()
+        .unwrap();
+    let proto = match btf.expect_chain(&func, &[Kind::FuncProto]).unwrap() {
+        Type::FuncProto(proto) => proto,
+        _ => panic!("Resolved type is not a function prototype"),
+    };
+
+    let skb = btf
+        .expect_chain(&proto.parameters[0], &[Kind::Ptr, Kind::Struct])
+        .unwrap();
+    assert_eq!(btf.resolve_name(&skb).unwrap(), "sk_buff");
+
+    let err = btf
+        .expect_chain(&proto.parameters[0], &[Kind::Ptr, Kind::Int])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected int at level 1 of the chain, found struct"
+    );
+    assert!(btf
+        .expect_chain(&proto.parameters[0], &[Kind::Ptr, Kind::Struct, Kind::Int])
+        .is_err());
+    assert!(btf.expect_chain(&func, &[]).is_err());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn struct_of_member(btf: Btf) {
+    let skb = match btf.resolve_type_by_id(4984).unwrap() {
+        Type::Struct(skb) => skb,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    let member = &skb.members[25];
+    assert_eq!(member.parent_id(), 4984);
+    assert_eq!(btf.resolve_struct_of_member(member).unwrap(), skb);
+
+    // Members of anonymous unions point to the union itself.
+    let union = match btf.resolve_chained_type(&skb.members[0]).unwrap() {
+        Type::Union(union) => union,
+        _ => panic!("Resolved type is not a union"),
+    };
+    assert_ne!(union.members[0].parent_id(), 4984);
+    assert_eq!(
+        btf.resolve_struct_of_member(&union.members[0]).unwrap(),
+        union
+    );
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn bitfield_members(btf: Btf) {
+    let skb = match btf.resolve_type_by_id(4984).unwrap() {
+        Type::Struct(skb) => skb,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    assert!(skb.has_bitfield_members());
+    assert_eq!(btf.resolve_name(&skb.members[14]).unwrap(), "fclone");
+    assert!(skb.members[14].is_bitfield());
+    assert_eq!(skb.members[14].bitfield_size(), Some(2));
+    assert!(!skb.members[25].is_bitfield());
+    assert_eq!(skb.members[25].bitfield_size(), Some(0));
+
+    let list_head = match btf
+        .resolve_types_by_name("list_head")
+        .unwrap()
+        .pop()
+        .unwrap()
+    {
+        Type::Struct(list_head) => list_head,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    assert!(!list_head.has_bitfield_members());
+    assert!(!list_head.members[0].is_bitfield());
+    assert_eq!(list_head.members[0].bitfield_size(), None);
+
+    assert_eq!(skb.members[14].effective_bit_size(&btf).unwrap(), 2);
+    assert_eq!(btf.resolve_name(&skb.members[9]).unwrap(), "hdr_len");
+    assert_eq!(skb.members[9].effective_bit_size(&btf).unwrap(), 16);
+    assert_eq!(list_head.members[0].effective_bit_size(&btf).unwrap(), 64);
+}
+
+#[test]
+fn bitfields_without_kind_flag() {
+    // Without kind_flag, the width of bitfields is the number of bits of
+    // their integer type.
+    let mut builder = encode::BtfBuilder::new();
+    let int = builder.add_int("int", 4, false).unwrap();
+    let bits = builder.add_int("bits", 4, false).unwrap();
+    builder
+        .add_struct("foo", 4, &[("a", bits, 0), ("b", int, 32)])
+        .unwrap();
+    let mut raw = builder.to_bytes().unwrap();
+    // Set the number of bits of the second int (after the 16 bytes first one).
+    assert_eq!(raw[24 + 16 + 12], 32);
+    raw[24 + 16 + 12] = 3;
+
+    let btf = Btf::from_bytes(&raw).unwrap();
+    let foo = match btf.resolve_types_by_name("foo").unwrap().pop().unwrap() {
+        Type::Struct(foo) => foo,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    assert!(!foo.has_bitfield_members());
+    assert_eq!(foo.members[0].effective_bit_size(&btf).unwrap(), 3);
+    assert_eq!(foo.members[1].effective_bit_size(&btf).unwrap(), 32);
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn member_at_offset(btf: Btf) {
+    let skb = match btf.resolve_type_by_id(4984).unwrap() {
+        Type::Struct(skb) => skb,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    let names = |offset| {
+        skb.member_at_offset(&btf, offset)
+            .unwrap()
+            .iter()
+            .map(|m| btf.resolve_name(m).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names(0), vec!["", "", "next"]);
+    assert_eq!(names(16), vec!["", "", "", "dev"]);
+    assert_eq!(names(112), vec!["len"]);
+    assert_eq!(names(126), vec!["cloned"]);
+    assert_eq!(names(219), vec!["truesize"]);
+    assert!(names(232).is_empty());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn net_info(btf: Btf) {
+    let info = utils::net::NetInfo::from_btf(&btf).unwrap();
+    assert_eq!(info.skb_len, 112);
+    assert_eq!(info.skb_data, 208);
+    assert_eq!(info.skb_dev, 16);
+    assert_eq!(info.dev_name, 0);
+    assert_eq!(
+        info.drop_reasons.get(&2).map(|s| s.as_str()),
+        Some("SKB_DROP_REASON_NOT_SPECIFIED")
+    );
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn func_proto_types(btf: Btf) {
+    let func = btf
+        .resolve_types_by_name("kfree_skb_reason")
+        .unwrap()
+        .pop()
+        .unwrap();
+    let proto = match btf.resolve_chained_type(&func).unwrap() {
+        Type::FuncProto(proto) => proto,
+        _ => panic!("Resolved type is not a function prototype"),
+    };
+
+    assert_eq!(proto.return_type(&btf).unwrap(), Type::Void);
+    assert!(matches!(
+        proto.parameters[0].r#type(&btf).unwrap(),
+        Type::Ptr(_)
+    ));
+    assert!(matches!(
+        proto.parameters[1].r#type(&btf).unwrap(),
+        Type::Enum(_)
+    ));
+
+    let (index, param) = proto.param_by_name(&btf, "reason").unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(param, &proto.parameters[1]);
+    assert!(proto.param_by_name(&btf, "dev").is_none());
+}
+
+#[test]
+fn dangling_references() {
+    assert!(bytes().dangling_references().is_empty());
+    assert!(split_file().dangling_references().is_empty());
+
+    // Make the second type (a pointer) reference a non-existing type.
+    let mut raw = read("tests/data/btf/vmlinux").unwrap();
+    raw[48..52].copy_from_slice(&0xffffffu32.to_le_bytes());
+    let btf = Btf::from_bytes(&raw).unwrap();
+    assert_eq!(
+        btf.dangling_references(),
+        vec![(TypeId::from(2), TypeId::from(0xffffff))]
+    );
+
+    let opts = BtfOptions::new().check_references(true);
+    assert!(Btf::from_bytes_with_options(&read("tests/data/btf/vmlinux").unwrap(), &opts).is_ok());
+    let err = Btf::from_bytes_with_options(&raw, &opts).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Type 2 references non-existing type 16777215"
+    );
+}
+
+#[test]
+fn source_name() {
+    let opts = BtfOptions::new().source_name("vmlinux");
+    let btf = Btf::from_file_with_options("tests/data/btf/vmlinux", &opts).unwrap();
+    assert_eq!(
+        btf.resolve_type_by_id(u32::MAX).err().unwrap().to_string(),
+        format!("No type with id {} in vmlinux", u32::MAX)
+    );
+
+    let btfc = btfc_files();
+    let ovs = btfc.get_named_btf("openvswitch").unwrap();
+    assert_eq!(
+        ovs.btf
+            .resolve_ids_by_name("not_a_type")
+            .err()
+            .unwrap()
+            .to_string(),
+        "No id linked to name not_a_type in openvswitch"
+    );
+
+    // Names are set per collection.
+    let raw = read("tests/data/btf/vmlinux").unwrap();
+    for name in ["a", "b"] {
+        let btfc = utils::collection::BtfCollection::from_bytes(name, &raw).unwrap();
+        assert_eq!(
+            btfc.base()
+                .resolve_type_by_id(u32::MAX)
+                .err()
+                .unwrap()
+                .to_string(),
+            format!("No type with id {} in {name}", u32::MAX)
+        );
+    }
+}
+
+#[test_case(0, 0)]
+#[test_case(177768, 179234)]
+fn capacity(types: usize, strings: usize) {
+    let raw = read("tests/data/btf/vmlinux").unwrap();
+    let opts = BtfOptions::new().capacity(types, strings);
+    let btf = Btf::from_bytes_with_options(&raw, &opts).unwrap();
+    assert_eq!(btf.type_ids(), 0..177769);
+    assert_eq!(btf.to_bytes().unwrap(), raw);
+}
+
+#[test]
+fn capacity_estimates() {
+    // Capacity estimates do not trust the section lengths of the header.
+    let mut raw = vec![0x9f, 0xeb, 1, 0];
+    [24, 0, u32::MAX - 64, 0, u32::MAX - 64]
+        .iter()
+        .for_each(|x: &u32| raw.extend(x.to_le_bytes()));
+    assert!(Btf::from_bytes(&raw).is_err());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn resolve_name_ref(btf: Btf) {
+    let skb = match btf.resolve_types_by_name("sk_buff").unwrap().pop().unwrap() {
+        Type::Struct(skb) => skb,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    assert_eq!(btf.resolve_name_ref(&skb).unwrap(), "sk_buff");
+    for member in skb.members.iter() {
+        assert_eq!(
+            btf.resolve_name_ref(member).unwrap(),
+            btf.resolve_name(member).unwrap()
+        );
+    }
+    assert!(btf.resolve_name_ref(&Type::Void).is_err());
+
+    if btf.is_split() {
+        let id = btf.resolve_ids_by_name("ovs_vport_send").unwrap()[0];
+        let func = btf.resolve_type_by_id(id).unwrap();
+        assert_eq!(btf.resolve_name_ref(&func).unwrap(), "ovs_vport_send");
+    }
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn named_types(btf: Btf) {
+    let skb = btf
+        .resolve_named_types_by_name("sk_buff")
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(skb.name.as_deref(), Some("sk_buff"));
+    assert_eq!(skb.id, btf.resolve_ids_by_name("sk_buff").unwrap()[0]);
+    assert_eq!(btf.resolve_named_type_by_id(skb.id).unwrap(), skb);
+
+    let skb = match skb.r#type {
+        Type::Struct(skb) => skb,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    for member in skb.members.iter() {
+        let r#type = btf.resolve_named_chained_type(member).unwrap();
+        assert_eq!(r#type.r#type, btf.resolve_chained_type(member).unwrap());
+        match &r#type.name {
+            Some(name) => assert_eq!(name.as_ref(), btf.resolve_name(&r#type.r#type).unwrap()),
+            None => assert!(btf
+                .resolve_name(&r#type.r#type)
+                .map_or(true, |n| n.is_empty())),
+        }
+    }
+
+    let dev = btf.resolve_named_chained_type(&skb.members[2]).unwrap();
+    assert!(matches!(dev.r#type, Type::Union(_)));
+    assert!(dev.name.is_none());
+
+    assert!(btf.resolve_named_type_by_id(0).unwrap().name.is_none());
+    assert!(btf.resolve_named_types_by_name("not_a_type").is_err());
+
+    if btf.is_split() {
+        let func = btf.resolve_named_types_by_name("ovs_vport_send").unwrap();
+        assert_eq!(func[0].name.as_deref(), Some("ovs_vport_send"));
+    }
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn resolve_named_types(btf: Btf) {
+    let types = btf.resolve_named_types("sk_buff", None).unwrap();
+    assert!(types.windows(2).all(|w| w[0].name <= w[1].name));
+    assert!(types
+        .iter()
+        .all(|t| t.name.as_deref().unwrap().starts_with("sk_buff")));
+    assert!(types
+        .iter()
+        .any(|t| t.name.as_deref() == Some("sk_buff") && matches!(t.r#type, Type::Struct(_))));
+
+    let structs = btf
+        .resolve_named_types("sk_buff", Some(Kind::Struct))
+        .unwrap();
+    assert!(!structs.is_empty() && structs.len() < types.len());
+    assert!(structs.iter().all(|t| t.r#type.kind() == Kind::Struct));
+    for t in structs {
+        assert_eq!(
+            btf.resolve_name(&t.r#type).unwrap(),
+            t.name.as_deref().unwrap()
+        );
+        assert_eq!(btf.resolve_type_by_id(t.id).unwrap(), t.r#type);
+    }
+
+    let enums = btf.resolve_named_types("", Some(Kind::Enum)).unwrap();
+    assert!(enums
+        .iter()
+        .any(|t| t.name.as_deref() == Some("skb_drop_reason")));
+    assert!(btf
+        .resolve_named_types("not_a_type", None)
+        .unwrap()
+        .is_empty());
+
+    if btf.is_split() {
+        let funcs = btf
+            .resolve_named_types("ovs_vport_", Some(Kind::Func))
+            .unwrap();
+        assert!(funcs
+            .iter()
+            .any(|t| t.name.as_deref() == Some("ovs_vport_send")));
+    }
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn field_reloc(btf: Btf) {
+    use btf_rs::{ext::CoreReloKind, utils::reloc::field_reloc};
+
+    let len = field_reloc(&btf, "sk_buff", "len").unwrap();
+    assert!(len.exists);
+    assert_eq!(len.bit_offset, 112 * 8);
+    assert_eq!((len.byte_offset, len.byte_size), (112, 4));
+    assert_eq!((len.lshift_u64, len.rshift_u64), (32, 32));
+    assert!(!len.signed);
+    assert_eq!(len.value(CoreReloKind::FieldByteOffset), Some(112));
+    assert_eq!(len.value(CoreReloKind::FieldExists), Some(1));
+    assert_eq!(len.value(CoreReloKind::TypeSize), None);
+
+    // Same field, using a CO-RE access string.
+    let skb = match btf.resolve_types_by_name("sk_buff").unwrap().pop().unwrap() {
+        Type::Struct(skb) => skb,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    let index = skb
+        .members
+        .iter()
+        .position(|m| btf.resolve_name(m).unwrap() == "len")
+        .unwrap();
+    assert_eq!(
+        field_reloc(&btf, "sk_buff", &format!("0:{index}")).unwrap(),
+        len
+    );
+
+    let fclone = field_reloc(&btf, "sk_buff", "fclone").unwrap();
+    assert_eq!(fclone.bit_offset, 1010);
+    assert_eq!(fclone.bitfield_size, Some(2));
+    assert_eq!((fclone.byte_offset, fclone.byte_size), (126, 1));
+    assert_eq!((fclone.lshift_u64, fclone.rshift_u64), (60, 62));
+
+    // Second element of an array of sk_buff.
+    let len1 = field_reloc(&btf, "sk_buff", &format!("1:{index}")).unwrap();
+    assert_eq!(len1.byte_offset, skb.size() + 112);
+
+    assert!(!field_reloc(&btf, "sk_buff", "not_a_member").unwrap().exists);
+    assert!(!field_reloc(&btf, "sk_buff", "0:1000").unwrap().exists);
+    assert!(!field_reloc(&btf, "not_a_type", "0:0").unwrap().exists);
+    assert!(field_reloc(&btf, "sk_buff", "dev.name").is_err());
+    assert!(field_reloc(&btf, "sk_buff", "0:x").is_err());
+}
+
+#[test]
+fn compat_check() {
+    use utils::compat::{check, Difference};
+
+    let skb = |btf: &Btf| btf.resolve_types_by_name("sk_buff").unwrap().pop().unwrap();
+    let (a, b) = (bytes(), split_file());
+    assert!(check(&a, &skb(&a), &b, &skb(&b)).unwrap().is_empty());
+
+    let foo = |sign: bool, size: usize, members: &[(&str, u32)]| {
+        let mut builder = encode::BtfBuilder::new();
+        let int = builder.add_int("int", 4, sign).unwrap();
+        let ptr = builder.add_ptr(int).unwrap();
+        let members: Vec<_> = members
+            .iter()
+            .map(|(name, offset)| (*name, if *name == "b" { ptr } else { int }, *offset))
+            .collect();
+        builder.add_struct("foo", size, &members).unwrap();
+        let btf = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+        let foo = btf.resolve_types_by_name("foo").unwrap().pop().unwrap();
+        (btf, foo)
+    };
+    let (btf_a, a) = foo(true, 16, &[("a", 0), ("b", 64), ("c", 32)]);
+    let (btf_b, b) = foo(false, 24, &[("a", 0), ("b", 128), ("d", 32)]);
+
+    let diffs = check(&btf_a, &a, &btf_b, &b).unwrap();
+    assert_eq!(
+        diffs,
+        vec![
+            Difference::Size {
+                path: "foo".to_string(),
+                a: 16,
+                b: 24
+            },
+            Difference::Signedness {
+                path: "foo.a".to_string(),
+                a: true,
+                b: false
+            },
+            Difference::Offset {
+                path: "foo.b".to_string(),
+                a: 64,
+                b: 128
+            },
+            Difference::OnlyInA("foo.c".to_string()),
+            Difference::OnlyInB("foo.d".to_string()),
+        ]
+    );
+    assert_eq!(diffs[2].to_string(), "foo.b is at bit offset 64 vs 128");
+
+    let diffs = check(&btf_a, &a, &btf_a, &Type::Void).unwrap();
+    assert!(matches!(
+        &diffs[..],
+        [Difference::Kind {
+            a: Kind::Struct,
+            b: Kind::Void,
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn btf_diff() {
+    use utils::{
+        compat::Difference,
+        diff::{diff, Change},
+    };
+
+    let btf = bytes();
+    assert!(diff(&btf, &btf).unwrap().is_empty());
+
+    let mut builder = encode::BtfBuilder::new();
+    let int = builder.add_int("int", 4, true).unwrap();
+    builder.add_struct("foo", 8, &[("a", int, 0)]).unwrap();
+    builder.add_enum("e", 4, &[("X", 1), ("Y", 2)]).unwrap();
+    builder.add_typedef("t", int).unwrap();
+    let proto = builder.add_func_proto(int, &[("x", int)]).unwrap();
+    builder.add_func("f", proto).unwrap();
+    let a = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+
+    let mut builder = encode::BtfBuilder::new();
+    let int = builder.add_int("int", 4, true).unwrap();
+    builder.add_struct("foo", 8, &[("a", int, 32)]).unwrap();
+    builder
+        .add_enum("e", 4, &[("X", 1), ("Y", 3), ("Z", 4)])
+        .unwrap();
+    builder.add_struct("bar", 4, &[("b", int, 0)]).unwrap();
+    let proto = builder
+        .add_func_proto(int, &[("x", int), ("y", int)])
+        .unwrap();
+    builder.add_func("f", proto).unwrap();
+    let b = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+
+    let diff = diff(&a, &b).unwrap();
+    assert_eq!(diff.added, vec![(Kind::Struct, "bar".to_string())]);
+    assert_eq!(diff.removed, vec![(Kind::Typedef, "t".to_string())]);
+
+    let changed: Vec<_> = diff
+        .changed
+        .iter()
+        .map(|t| (t.kind, t.name.as_str()))
+        .collect();
+    assert_eq!(
+        changed,
+        vec![(Kind::Enum, "e"), (Kind::Func, "f"), (Kind::Struct, "foo")]
+    );
+    assert_eq!(
+        diff.changed[0].changes,
+        vec![
+            Change::EnumValueChanged {
+                name: "Y".to_string(),
+                a: EnumValue::Unsigned(2),
+                b: EnumValue::Unsigned(3)
+            },
+            Change::EnumValueAdded("Z".to_string(), EnumValue::Unsigned(4)),
+        ]
+    );
+    assert_eq!(
+        diff.changed[1].changes[0].to_string(),
+        "changed from `int f(int x);` to `int f(int x, int y);`"
+    );
+    assert_eq!(
+        diff.changed[2].changes,
+        vec![Change::Layout(Difference::Offset {
+            path: "foo.a".to_string(),
+            a: 0,
+            b: 32
+        })]
+    );
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn ids_for_exact_name(btf: Btf) {
+    assert_eq!(
+        btf.ids_for_exact_name("sk_buff").collect::<Vec<_>>(),
+        btf.resolve_ids_by_name("sk_buff").unwrap()
+    );
+    assert_eq!(btf.ids_for_exact_name("not_a_type").count(), 0);
+    assert_eq!(
+        btf.ids_for_exact_name_bytes(b"sk_buff").collect::<Vec<_>>(),
+        btf.resolve_ids_by_name("sk_buff").unwrap()
+    );
+    assert_eq!(btf.ids_for_exact_name_bytes(b"not_a_type").count(), 0);
+}
+
+#[test]
+fn resolution_order() {
+    let base = bytes();
+    let opts = BtfOptions::new().resolution_order(ResolutionOrder::SplitFirst);
+    let split = Btf::from_split_file("tests/data/btf/openvswitch", &base).unwrap();
+    let split_first =
+        Btf::from_split_file_with_options("tests/data/btf/openvswitch", &base, &opts).unwrap();
+
+    // The percpu datasec is defined in both objects.
+    let ids = split.resolve_ids_by_name(".data..percpu").unwrap();
+    assert_eq!(ids.len(), 2);
+    assert!(base.resolve_type_by_id(ids[0]).is_ok());
+    let mut reversed = split_first.resolve_ids_by_name(".data..percpu").unwrap();
+    reversed.reverse();
+    assert_eq!(ids, reversed);
+
+    let mut btfc = btfc_files();
+    let names = |btfc: &utils::collection::BtfCollection| {
+        btfc.resolve_ids_by_name(".data..percpu")
+            .unwrap()
+            .iter()
+            .map(|m| m.btf.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&btfc), vec!["openvswitch", "vmlinux"]);
+    btfc.set_resolution_order(ResolutionOrder::BaseFirst);
+    assert_eq!(names(&btfc), vec!["vmlinux", "openvswitch"]);
+}
+
+#[test]
+fn btfc_replace_split() {
+    let mut btfc = btfc_files();
+    btfc.resolve_ids_by_name("datapath").unwrap();
+    assert!(btfc
+        .add_split_btf_from_file("tests/data/btf/openvswitch")
+        .is_err());
+
+    btfc.replace_split_btf_from_file("tests/data/btf/openvswitch")
+        .unwrap();
+    assert_eq!(btfc.split_btfs().count(), 1);
+    let m = btfc.resolve_ids_by_name("datapath").unwrap().pop().unwrap();
+    let nbtf = m.btf;
+    assert_eq!(nbtf.name, "openvswitch");
+
+    let ovs = read("tests/data/btf/openvswitch").unwrap();
+    assert!(btfc.replace_split_btf_from_bytes("ovs", &ovs).is_err());
+    btfc.upsert_split_btf_from_bytes("ovs", &ovs).unwrap();
+    btfc.upsert_split_btf_from_bytes("ovs", &ovs).unwrap();
+    assert_eq!(btfc.split_btfs().count(), 2);
+}
+
+#[test_case(btfc_files())]
+#[test_case(btfc_dir())]
+fn btfc_base(btfc: utils::collection::BtfCollection) {
+    assert_eq!(btfc.base_name(), "vmlinux");
+    assert_eq!(btfc.base().name, "vmlinux");
+    assert!(!btfc.base().btf.is_split());
+
+    let m = btfc.resolve_ids_by_name("sk_buff").unwrap().pop().unwrap();
+    let nbtf = m.btf;
+    assert!(std::ptr::eq(nbtf, btfc.base()));
+}
+
+#[test]
+fn type_resolver() {
+    use utils::resolver::TypeResolver;
+
+    fn count<R: TypeResolver>(resolver: &R, name: &str) -> usize {
+        resolver
+            .resolve_types(name)
+            .map(|t| t.len())
+            .unwrap_or_default()
+    }
+
+    let btf = bytes();
+    let btfc = btfc_files();
+    assert_eq!(count(&btf, "sk_buff"), 1);
+    assert_eq!(count(&btfc, "sk_buff"), 1);
+    assert_eq!(count(btfc.base(), "sk_buff"), 1);
+    assert_eq!(count(&btf, "datapath"), 0);
+    assert_eq!(count(&btfc, "datapath"), 1);
+
+    let (ovs, proto) = btfc
+        .resolve_chains("ovs_vport_send", &[Kind::FuncProto])
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert!(ovs.is_split());
+    assert!(matches!(proto, Type::FuncProto(_)));
+    assert!(btf.resolve_chains("sk_buff", &[Kind::Ptr]).is_err());
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn name_matcher() {
+    use utils::matcher::NameMatcher;
+
+    let matcher = NameMatcher::new(["^sk_buff$", "^ovs_vport_send$"]).unwrap();
+    let btf = bytes();
+    assert_eq!(matcher.scan(&btf), vec![TypeId::from(4984)]);
+    assert_eq!(matcher.scan(&btf), vec![TypeId::from(4984)]);
+
+    let mut btfc = btfc_files();
+    let names = |matches: Vec<(&utils::collection::NamedBtf, TypeId)>| {
+        matches
+            .iter()
+            .map(|(nbtf, id)| {
+                format!(
+                    "{}:{}",
+                    nbtf.name,
+                    nbtf.resolve_type_by_id(*id)
+                        .map(|t| nbtf.resolve_name(&t).unwrap())
+                        .unwrap()
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = vec!["vmlinux:sk_buff", "openvswitch:ovs_vport_send"];
+    assert_eq!(names(matcher.scan_collection(&btfc)), expected);
+
+    // Replacing a split BTF changes its fingerprint.
+    let fingerprint = btfc.get_named_btf("openvswitch").unwrap().fingerprint();
+    btfc.replace_split_btf_from_file("tests/data/btf/openvswitch")
+        .unwrap();
+    assert_ne!(
+        btfc.get_named_btf("openvswitch").unwrap().fingerprint(),
+        fingerprint
+    );
+    assert_eq!(names(matcher.scan_collection(&btfc)), expected);
+
+    assert!(NameMatcher::new(["("]).is_err());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn layout_validation(btf: Btf) {
+    use utils::layout::*;
+
+    let layout = ExpectedLayout::new("sk_buff")
+        .size(232)
+        .field("len", 112, 4)
+        .field_kind("data", 208, 8, Kind::Ptr)
+        .field_kind("dev", 16, 8, Kind::Ptr);
+    assert!(validate(&btf, &layout).unwrap().is_empty());
+
+    let layout = ExpectedLayout::new("sk_buff")
+        .size(240)
+        .field("len", 116, 4)
+        .field("data_len", 116, 8)
+        .field_kind("truesize", 216, 4, Kind::Ptr)
+        .field("cloned", 126, 1)
+        .field("not_a_field", 0, 1);
+    assert_eq!(
+        validate(&btf, &layout).unwrap(),
+        vec![
+            Mismatch::Size {
+                expected: 240,
+                found: 232
+            },
+            Mismatch::Offset {
+                field: "len".to_string(),
+                expected: 116,
+                found: 112
+            },
+            Mismatch::FieldSize {
+                field: "data_len".to_string(),
+                expected: 8,
+                found: 4
+            },
+            Mismatch::Kind {
+                field: "truesize".to_string(),
+                expected: Kind::Ptr,
+                found: Kind::Int
+            },
+            Mismatch::Bitfield("cloned".to_string()),
+            Mismatch::MissingField("not_a_field".to_string()),
+        ]
+    );
+
+    assert!(validate(&btf, &ExpectedLayout::new("not_a_struct")).is_err());
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn from_btf_derive() {
+    use utils::layout::{BtfOffsets, FromBtf};
+
+    #[derive(FromBtf)]
+    #[btf(name = "sk_buff")]
+    struct Skb {
+        len: u32,
+        #[btf(name = "data_len")]
+        dlen: u32,
+        dev: u64,
+    }
+
+    #[allow(dead_code)]
+    #[derive(FromBtf)]
+    #[btf(name = "sk_buff")]
+    struct BadSkb {
+        len: u64,
+    }
+
+    let btf = bytes();
+    let offsets = BtfOffsets::<Skb>::resolve(&btf).unwrap();
+    assert_eq!(offsets.size(), 232);
+    assert_eq!(offsets.offset(Skb::BTF_LEN), 112);
+    assert_eq!(offsets.offset(Skb::BTF_DLEN), 116);
+    assert_eq!(offsets.offset(Skb::BTF_DEV), 16);
+
+    let mut raw = vec![0; 232];
+    raw[112..116].copy_from_slice(&42u32.to_ne_bytes());
+    raw[116..120].copy_from_slice(&10u32.to_ne_bytes());
+    raw[16..24].copy_from_slice(&0xffffu64.to_ne_bytes());
+    let skb = offsets.read(&raw).unwrap();
+    assert_eq!((skb.len, skb.dlen, skb.dev), (42, 10, 0xffff));
+    assert!(offsets.read(&raw[..200]).is_err());
+
+    // The field size does not match.
+    assert!(BtfOffsets::<BadSkb>::resolve(&btf).is_err());
+    assert_eq!(BadSkb::BTF_FIELDS, &[("len", 8)]);
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn struct_layout(btf: Btf) {
+    let resolve = |name| match btf.resolve_types_by_name(name).unwrap().pop().unwrap() {
+        Type::Struct(s) => s,
+        _ => panic!("Resolved type is not a struct"),
+    };
+
+    let layout = btf.resolve_layout(&resolve("list_head")).unwrap();
+    assert_eq!((layout.size, layout.alignment), (16, 8));
+    assert_eq!(layout.members.len(), 2);
+    assert_eq!(layout.members[1].name, "prev");
+    assert_eq!(layout.members[1].offset, 8);
+    assert!(layout.holes.is_empty());
+
+    let layout = btf.resolve_layout(&resolve("sk_buff")).unwrap();
+    assert_eq!((layout.size, layout.alignment), (232, 8));
+    assert_eq!(layout.members.len(), 28);
+    assert_eq!(
+        layout.holes,
+        vec![LayoutHole {
+            offset: 196,
+            size: 4
+        }]
+    );
+
+    let len = &layout.members[6];
+    assert_eq!(len.name, "len");
+    assert_eq!((len.offset, len.size, len.alignment), (112, 4, 4));
+
+    let fclone = &layout.members[14];
+    assert_eq!(fclone.name, "fclone");
+    assert_eq!((fclone.offset, fclone.bit_offset), (126, 1010));
+    assert_eq!(fclone.bitfield_size, Some(2));
+
+    let cb = &layout.members[3];
+    assert_eq!(cb.name, "cb");
+    assert_eq!((cb.size, cb.alignment), (48, 1));
+
+    // Anonymous union holding an anonymous struct.
+    let first = layout.members[0].layout.as_ref().unwrap();
+    assert_eq!(first.size, 24);
+    let nested = first.members[0].layout.as_ref().unwrap();
+    assert_eq!(nested.members[1].name, "prev");
+    assert_eq!(nested.members[1].offset, 8);
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn cbtf_type_derive() {
+    use utils::raw::CBtfType;
+
+    #[derive(CBtfType, Debug, PartialEq)]
+    struct Record {
+        a: u32,
+        b: u16,
+        c: i32,
+    }
+
+    let raw = [0, 0, 0, 1, 0, 2, 0xff, 0xff, 0xff, 0xfe];
+    assert_eq!(
+        Record::from_reader(&mut raw.as_slice(), Endianness::Big).unwrap(),
+        Record { a: 1, b: 2, c: -2 }
+    );
+    assert_eq!(
+        Record::from_reader(&mut raw.as_slice(), Endianness::Little).unwrap(),
+        Record {
+            a: 0x1000000,
+            b: 0x200,
+            c: -0x1000001
+        }
+    );
+    assert!(Record::from_reader(&mut &raw[..8], Endianness::Big).is_err());
+
+    let raw = [raw.as_slice(), &[42, 0]].concat();
+    let (record, len) = Record::from_bytes(&raw, Endianness::Big).unwrap();
+    assert_eq!((record, len), (Record { a: 1, b: 2, c: -2 }, 10));
+    assert_eq!(
+        u16::from_bytes(&raw[len..], Endianness::Little).unwrap(),
+        (42, 2)
+    );
+    assert!(u32::from_bytes(&raw[len..], Endianness::Little).is_err());
+
+    assert_eq!(Record::SIZE, 10);
+    assert_eq!((Record::OFFSET_B, Record::OFFSET_C), (4, 6));
+    assert_eq!(<[Record; 3]>::SIZE, 30);
+
+    assert_eq!(bytes().endianness(), Endianness::Little);
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn c_printer(btf: Btf) {
+    use utils::printer::*;
+
+    let resolve = |name| btf.resolve_types_by_name(name).unwrap().pop().unwrap();
+
+    let func = resolve("kfree_skb_reason");
+    assert_eq!(
+        decl_to_c(&btf, &func, "").unwrap(),
+        "void kfree_skb_reason(struct sk_buff *skb, enum skb_drop_reason reason)"
+    );
+    assert_eq!(
+        type_to_c(&btf, &btf.resolve_chained_type(&func).unwrap()).unwrap(),
+        "void (struct sk_buff *skb, enum skb_drop_reason reason)"
+    );
+
+    // Qualifiers and pointers.
+    let ptr = btf.resolve_type_by_id(2).unwrap();
+    assert_eq!(type_to_c(&btf, &ptr).unwrap(), "const char *");
+    let r#const = btf.resolve_type_by_id(3).unwrap();
+    assert_eq!(
+        decl_to_c(&btf, &r#const, "p").unwrap(),
+        "const char *const p"
+    );
+    let array = btf.resolve_type_by_id(60).unwrap();
+    assert_eq!(
+        decl_to_c(&btf, &array, "bits").unwrap(),
+        "long unsigned int bits[16]"
+    );
+    assert_eq!(type_to_c(&btf, &array).unwrap(), "long unsigned int[16]");
+
+    assert_eq!(
+        definition_to_c(&btf, &resolve("u32")).unwrap(),
+        "typedef __u32 u32;"
+    );
+    assert_eq!(
+        definition_to_c(&btf, &resolve("callback_head")).unwrap(),
+        "struct callback_head {\n\
+         \tstruct callback_head *next; /* 0 */\n\
+         \tvoid (*func)(struct callback_head *); /* 8 */\n\
+         };"
+    );
+
+    let skb = definition_to_c(&btf, &resolve("sk_buff")).unwrap();
+    assert!(skb.starts_with("struct sk_buff {\n\tunion {\n\t\tstruct {\n"));
+    assert!(skb.contains("\n\t\t\t\tstruct net_device *dev; /* 16 */\n"));
+    assert!(skb.contains("\n\tunsigned int len; /* 112 */\n"));
+    assert!(skb.contains("\n\t__u8 nohdr: 1; /* 126: 1 */\n"));
+    assert!(skb.ends_with("\n};"));
+
+    let reasons = definition_to_c(&btf, &resolve("skb_drop_reason")).unwrap();
+    assert!(reasons.starts_with("enum skb_drop_reason {\n\tSKB_NOT_DROPPED_YET = 0,\n"));
+}
+
+#[test]
+fn btf_ext() {
+    use ext::*;
+
+    let mut builder = encode::BtfBuilder::new();
+    let int = builder.add_int("int", 4, true).unwrap();
+    let foo = builder
+        .add_struct("foo", 8, &[("a", int, 0), ("b", int, 32)])
+        .unwrap();
+    let proto = builder.add_func_proto(int, &[]).unwrap();
+    let func = builder.add_func("prog", proto).unwrap();
+    let sec = u32::from(builder.add_string("kprobe/prog").unwrap());
+    let file = u32::from(builder.add_string("prog.bpf.c").unwrap());
+    let line = u32::from(builder.add_string("return x->b;").unwrap());
+    let access = u32::from(builder.add_string("0:1").unwrap());
+    let btf = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+
+    let raw = |func_rec_size: u32, func_id: u32| -> Vec<u8> {
+        let mut func_info = vec![func_rec_size, sec, 1, 0, func_id];
+        func_info.extend(std::iter::repeat_n(0, (func_rec_size as usize - 8) / 4));
+        let line_info = [
+            16,
+            sec,
+            2,
+            0,
+            file,
+            line,
+            12 << 10 | 5,
+            8,
+            file,
+            line,
+            13 << 10,
+        ];
+        let core_relo = [16, sec, 1, 8, u32::from(foo), access, 0];
+
+        let mut data: Vec<u32> = vec![32, 0, func_info.len() as u32 * 4];
+        data.extend([data[2], line_info.len() as u32 * 4]);
+        data.extend([data[3] + data[4], core_relo.len() as u32 * 4]);
+        data.extend(
+            func_info
+                .iter()
+                .chain(line_info.iter())
+                .chain(core_relo.iter()),
+        );
+
+        let mut raw = vec![0x9f, 0xeb, 1, 0];
+        data.iter().for_each(|x| raw.extend(x.to_le_bytes()));
+        raw
+    };
+
+    let ext = BtfExt::from_bytes(&raw(8, func.into()), &btf).unwrap();
+    assert_eq!(ext.func_info.len(), 1);
+    assert_eq!(ext.func_info[0].name, "kprobe/prog");
+    assert_eq!(
+        ext.func_info[0].records,
+        vec![FuncInfo {
+            insn_off: 0,
+            type_id: func
+        }]
+    );
+    let info = &ext.line_info[0].records;
+    assert_eq!(info.len(), 2);
+    assert_eq!(info[0].file_name, "prog.bpf.c");
+    assert_eq!(info[0].line, "return x->b;");
+    assert_eq!((info[0].line_num, info[0].line_col), (12, 5));
+    assert_eq!((info[1].insn_off, info[1].line_num), (8, 13));
+    assert_eq!(
+        ext.core_relos[0].records,
+        vec![CoreRelo {
+            insn_off: 8,
+            type_id: foo,
+            access: "0:1".to_string(),
+            kind: CoreReloKind::FieldByteOffset,
+        }]
+    );
+
+    // Unknown trailing fields of records are skipped.
+    assert_eq!(
+        BtfExt::from_bytes(&raw(16, func.into()), &btf).unwrap(),
+        ext
+    );
+    // Records must reference existing types.
+    assert!(BtfExt::from_bytes(&raw(8, 42), &btf).is_err());
+    assert!(BtfExt::from_bytes(&raw(8, func.into())[..40], &btf).is_err());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn iter(btf: Btf) {
+    let ids = btf.type_ids();
+    assert!(btf.iter().map(|(id, _)| id).eq(ids.clone()));
+    assert!(matches!(btf.iter().next(), Some((id, Type::Void)) if id == 0));
+
+    let (id, r#type) = btf
+        .iter()
+        .find(|(_, t)| matches!(t, Type::Struct(_)))
+        .unwrap();
+    assert_eq!(
+        btf.resolve_name(&r#type).unwrap(),
+        btf.resolve_name(&btf.resolve_type_by_id(id).unwrap())
+            .unwrap()
+    );
+
+    let mut split = btf.split_iter().map(|(id, _)| id);
+    match btf.is_split() {
+        true => {
+            let first = split.next().unwrap();
+            assert!(first > TypeId::from(0));
+            assert!(ids.contains(first));
+            assert_eq!(
+                split.count() as u32,
+                u32::from(ids.end()) - u32::from(first) - 1
+            );
+        }
+        false => assert!(split.eq(ids)),
+    }
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn member_path(btf: Btf) {
+    let len = btf.resolve_member_path("sk_buff.len").unwrap();
+    assert_eq!(btf.resolve_name(&len.member).unwrap(), "len");
+    assert_eq!((len.offset, len.bit_offset), (112, 896));
+    assert!(len.derefs.is_empty());
+    assert!(matches!(len.r#type, Type::Int(_)));
+
+    // Bitfield.
+    let fclone = btf.resolve_member_path("sk_buff.fclone").unwrap();
+    assert_eq!((fclone.offset, fclone.bit_offset), (126, 1010));
+
+    // Pointers, with members of anonymous unions.
+    let name = btf.resolve_member_path("sk_buff.dev.name").unwrap();
+    assert_eq!((name.offset, name.derefs.as_slice()), (0, [16].as_slice()));
+    assert!(matches!(name.r#type, Type::Array(_)));
+    let family = btf
+        .resolve_member_path("sk_buff.sk.__sk_common.skc_family")
+        .unwrap();
+    assert_eq!((family.offset, family.derefs), (16, vec![24]));
+
+    assert!(btf.resolve_member_path("sk_buff").is_err());
+    assert!(btf.resolve_member_path("sk_buff.foo").is_err());
+    assert!(btf.resolve_member_path("sk_buff.len.foo").is_err());
+    assert!(btf.resolve_member_path("kfree_skb_reason.skb").is_err());
+}
+
+#[test]
+fn remap_ids() {
+    let mut builder = encode::BtfBuilder::new();
+    let int = builder.add_int("int", 4, true).unwrap();
+    let ptr = builder.add_ptr(int).unwrap();
+    let foo = builder.add_struct("foo", 8, &[("a", ptr, 0)]).unwrap();
+    let btf = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+
+    // Swap the pointer and the struct.
+    let mut map = IdMap::new();
+    map.insert(ptr, foo);
+    map.insert(foo, ptr);
+    let swapped = btf.remap_ids(&map).unwrap();
+    assert!(swapped.eq_structural(&btf));
+    assert_eq!(swapped.resolve_ids_by_name("foo").unwrap(), vec![ptr]);
+    assert!(matches!(
+        swapped.resolve_type_by_id(foo).unwrap(),
+        Type::Ptr(_)
+    ));
+    assert!(swapped
+        .remap_ids(&map)
+        .unwrap()
+        .to_bytes()
+        .unwrap()
+        .eq(&btf.to_bytes().unwrap()));
+
+    // Drop the struct, or merge the pointer into the int.
+    let drop: IdMap = [(foo, 42.into())].into_iter().collect();
+    assert_eq!(btf.remap_ids(&drop).unwrap().type_ids(), 0..3);
+    let map = drop.compose(&[(ptr, int), (42.into(), ptr)].into_iter().collect());
+    let merged = btf.remap_ids(&map).unwrap();
+    assert_eq!(merged.type_ids(), 0..3);
+    match merged.resolve_type_by_id(ptr).unwrap() {
+        Type::Struct(foo) => assert_eq!(foo.members[0].get_type_id().unwrap(), int),
+        _ => panic!("Resolved type is not a struct"),
+    }
+
+    // Holes and moving Void are not allowed.
+    assert!(btf
+        .remap_ids(&[(ptr, 42.into())].into_iter().collect())
+        .is_err());
+    assert!(btf
+        .remap_ids(&[(0.into(), int), (int, 0.into())].into_iter().collect())
+        .is_err());
+
+    let split = split_file();
+    assert!(split
+        .remap_ids(&IdMap::new())
+        .unwrap()
+        .to_bytes()
+        .unwrap()
+        .eq(&split.to_bytes().unwrap()));
+}
+
+#[test]
+fn endianness_detect() {
+    let raw = read("tests/data/btf/vmlinux").unwrap();
+    assert_eq!(Endianness::detect(&raw).unwrap(), Endianness::Little);
+    assert_eq!(Endianness::detect(&[0xeb, 0x9f]).unwrap(), Endianness::Big);
+    assert!(Endianness::detect(&[0x9f, 0x9f]).is_err());
+    assert!(Endianness::detect(&raw[..1]).is_err());
+}
+
+#[test]
+fn dedup() {
+    let mut builder = encode::BtfBuilder::new();
+    let int = builder.add_int("int", 4, true).unwrap();
+    let int2 = builder.add_int("int", 4, true).unwrap();
+    // Two copies of a self-referencing struct.
+    for id in [4, 6] {
+        let ptr = builder.add_ptr(id.into()).unwrap();
+        builder.add_struct("list", 8, &[("next", ptr, 0)]).unwrap();
+    }
+    builder
+        .add_struct("other", 8, &[("next", 5.into(), 0)])
+        .unwrap();
+    builder.add_ptr(int).unwrap();
+    builder.add_ptr(int2).unwrap();
+
+    let btf = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+    let dedup = btf.dedup().unwrap();
+    assert_eq!(dedup.type_ids(), 0..6);
+    assert_eq!(dedup.resolve_types_by_name("int").unwrap().len(), 1);
+    assert_eq!(dedup.resolve_types_by_name("list").unwrap().len(), 1);
+    assert!(!dedup.eq_structural(&btf));
+
+    let map = btf.dedup_map().unwrap();
+    assert_eq!(map.len(), 8);
+    assert_eq!(map.get(1), None);
+    assert_eq!(map.apply(2), 1);
+    assert_eq!((map.apply(4), map.apply(6)), (3.into(), 3.into()));
+
+    let other = match dedup.resolve_types_by_name("other").unwrap().pop().unwrap() {
+        Type::Struct(other) => other,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    let list = dedup
+        .expect_chain(&other.members[0], &[Kind::Ptr, Kind::Struct])
+        .unwrap();
+    assert_eq!(dedup.resolve_name(&list).unwrap(), "list");
+    assert!(dedup.dangling_references().is_empty());
+
+    // Already deduplicated objects are left unchanged.
+    let split = split_file();
+    assert_eq!(
+        split.dedup().unwrap().to_bytes().unwrap(),
+        split.to_bytes().unwrap()
+    );
+}
+
+#[test]
+fn to_bytes() {
+    let raw = read("tests/data/btf/vmlinux").unwrap();
+    let vmlinux = Btf::from_bytes(&raw).unwrap();
+    assert_eq!(vmlinux.to_bytes().unwrap(), raw);
+
+    let raw = read("tests/data/btf/openvswitch").unwrap();
+    let ovs = Btf::from_split_bytes(&raw, &vmlinux).unwrap();
+    assert_eq!(ovs.to_bytes().unwrap(), raw);
+    assert!(Btf::from_split_bytes(&raw, &vmlinux)
+        .unwrap()
+        .eq_structural(&ovs));
+}
+
+// Type description used to generate BTF objects in property tests: kind,
+// name index, target index and log2 of the size.
+fn arb_types() -> impl proptest::strategy::Strategy<Value = Vec<(u32, u32, u32, u32)>> {
+    proptest::collection::vec((0..6u32, 0..8u32, proptest::num::u32::ANY, 0..4u32), 1..65)
+}
+
+proptest::proptest! {
+    #![proptest_config(proptest::test_runner::Config::with_cases(32))]
+
+    #[test]
+    fn round_trip(types in arb_types()) {
+        // Interning strings upfront changes their offsets, not the structure
+        // of the objects.
+        let mut builders = [encode::BtfBuilder::new(), encode::BtfBuilder::new()];
+        for i in (0..8).rev() {
+            builders[1].add_string(&format!("name{i}")).unwrap();
+        }
+
+        let n = types.len() as u32;
+        for (kind, name, target, size) in types {
+            let (name, target, size) = (format!("name{name}"), target % (n + 1), 1 << size);
+            for builder in builders.iter_mut() {
+                let target = TypeId::from(target);
+                match kind {
+                    0 => builder.add_int(&name, size, true),
+                    1 => builder.add_ptr(target),
+                    2 => builder.add_struct(
+                        &name,
+                        size * 2,
+                        &[("a", target, 0), ("b", target, size as u32 * 8)],
+                    ),
+                    3 => builder.add_typedef(&name, target),
+                    4 => builder.add_const(target),
+                    _ => builder.add_func_proto(target, &[(&name, target)]),
+                }
+                .unwrap();
+            }
+        }
+
+        let btf = Btf::from_bytes(&builders[0].to_bytes().unwrap()).unwrap();
+        let other = Btf::from_bytes(&builders[1].to_bytes().unwrap()).unwrap();
+        proptest::prop_assert!(btf.eq_structural(&other));
+
+        // parse(write(btf)) == btf
+        let raw = btf.to_bytes().unwrap();
+        let parsed = Btf::from_bytes(&raw).unwrap();
+        proptest::prop_assert!(parsed.eq_structural(&btf));
+        proptest::prop_assert_eq!(parsed.to_bytes().unwrap(), raw);
+
+        // Deduplication is idempotent.
+        let dedup = btf.dedup().unwrap();
+        proptest::prop_assert!(dedup.type_ids().end() <= btf.type_ids().end());
+        proptest::prop_assert!(dedup.dedup().unwrap().eq_structural(&dedup));
+    }
+}
+
+#[test]
+fn eq_structural() {
+    let btf = |name| {
+        let mut builder = encode::BtfBuilder::new();
+        builder.add_int(name, 4, true).unwrap();
+        Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap()
+    };
+    assert!(!btf("int").eq_structural(&btf("u32")));
+
+    // Ids and order of the types do not matter.
+    let mut builder = encode::BtfBuilder::new();
+    let int = builder.add_int("int", 4, true).unwrap();
+    builder.add_ptr(int).unwrap();
+    let btf = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+    let mut builder = encode::BtfBuilder::new();
+    builder.add_ptr(2.into()).unwrap();
+    builder.add_int("int", 4, true).unwrap();
+    let reordered = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+    assert!(btf.eq_structural(&reordered));
+    assert!(!bytes().eq_structural(&split_file()));
+}
+
+#[test]
+fn btf_builder() {
+    let mut builder = encode::BtfBuilder::new();
+    let int = builder.add_int("int", 4, true).unwrap();
+    let ptr = builder.add_ptr(int).unwrap();
+    let foo = builder
+        .add_struct("foo", 16, &[("a", int, 0), ("b", ptr, 64)])
+        .unwrap();
+    let proto = builder.add_func_proto(int, &[("foo", foo)]).unwrap();
+    builder.add_func("bar", proto).unwrap();
+    builder
+        .add_enum("baz", 4, &[("BAZ_A", -1), ("BAZ_B", 1)])
+        .unwrap();
+    assert_eq!(builder.add_string("foo").unwrap(), 5);
+
+    let btf = Btf::from_bytes(&builder.to_bytes().unwrap()).unwrap();
+    assert_eq!(btf.type_ids(), 0..7);
+    assert_eq!(btf.resolve_ids_by_name("foo").unwrap(), vec![foo]);
+
+    let bar = match btf.resolve_types_by_name("bar").unwrap().pop().unwrap() {
+        Type::Func(bar) => bar,
+        _ => panic!("Resolved type is not a function"),
+    };
+    assert!(bar.is_global());
+    let proto = match btf.resolve_chained_type(&bar).unwrap() {
+        Type::FuncProto(proto) => proto,
+        _ => panic!("Resolved type is not a function proto"),
+    };
+    assert_eq!(proto.return_type_id(), int);
+    let (_, param) = proto.param_by_name(&btf, "foo").unwrap();
+    let foo = match param.r#type(&btf).unwrap() {
+        Type::Struct(foo) => foo,
+        _ => panic!("Resolved type is not a struct"),
+    };
+    assert_eq!(foo.size(), 16);
+    assert_eq!(btf.resolve_name(&foo.members[1]).unwrap(), "b");
+    assert_eq!(foo.members[1].bit_offset(), 64);
+    assert!(matches!(
+        btf.resolve_chained_type(&foo.members[1]).unwrap(),
+        Type::Ptr(_)
+    ));
+
+    match btf.resolve_types_by_name("baz").unwrap().pop().unwrap() {
+        Type::Enum(baz) => {
+            assert!(baz.is_signed());
+            assert_eq!(baz.members[0].val() as i32, -1);
+        }
+        _ => panic!("Resolved type is not an enum"),
+    }
+
+    // Build a split object on top of the test vmlinux.
+    let vmlinux = bytes();
+    let mut builder = encode::BtfBuilder::new_split(&vmlinux).unwrap();
+    let skb = vmlinux
+        .resolve_ids_by_name("sk_buff")
+        .unwrap()
+        .pop()
+        .unwrap();
+    let ptr = builder.add_ptr(skb).unwrap();
+    let var = builder.add_var("my_skb", ptr).unwrap();
+    assert_eq!(u32::from(var), u32::from(vmlinux.type_ids().end()) + 1);
+
+    let split = Btf::from_split_bytes(&builder.to_bytes().unwrap(), &vmlinux).unwrap();
+    assert_eq!(split.resolve_ids_by_name("my_skb").unwrap(), vec![var]);
+    let r#type = split
+        .resolve_types_by_name("my_skb")
+        .unwrap()
+        .pop()
+        .unwrap();
+    let r#type = split.resolve_chained_type(&r#type).unwrap();
+    let r#type = split.resolve_chained_type(&r#type).unwrap();
+    assert_eq!(split.resolve_name(&r#type).unwrap(), "sk_buff");
+}
+
+#[test]
+fn btf_asm() {
+    use utils::asm::assemble_btf;
+
+    let btf = assemble_btf(
+        "
+        struct bar;
+        struct foo {
+            int a;
+            const struct bar *b;
+            unsigned long c[2], d; // Trailing comment.
+            union { char e; short f; };
+        };
+        enum state { STOPPED, RUNNING = -1 };
+        enum big { BIG = 0x100000000 };
+        typedef struct foo foo_t;
+        int process(foo_t *foo, enum state, ...);
+        _Bool flag;
+        ",
+    )
+    .unwrap();
+    assert_eq!(
+        utils::dump::dump_to_string(&btf).unwrap(),
+        "[1] FWD 'bar' fwd_kind=struct
+[2] STRUCT 'foo' size=48 vlen=5
+\t'a' type_id=3 bits_offset=0
+\t'b' type_id=5 bits_offset=64
+\t'c' type_id=7 bits_offset=128
+\t'd' type_id=6 bits_offset=256
+\t'(anon)' type_id=8 bits_offset=320
+[3] INT 'int' size=4 bits_offset=0 nr_bits=32 encoding=SIGNED
+[4] CONST '(anon)' type_id=1
+[5] PTR '(anon)' type_id=4
+[6] INT 'long unsigned int' size=8 bits_offset=0 nr_bits=64 encoding=(none)
+[7] ARRAY '(anon)' type_id=6 index_type_id=3 nr_elems=2
+[8] UNION '(anon)' size=2 vlen=2
+\t'e' type_id=9 bits_offset=0
+\t'f' type_id=10 bits_offset=0
+[9] INT 'char' size=1 bits_offset=0 nr_bits=8 encoding=(none)
+[10] INT 'short int' size=2 bits_offset=0 nr_bits=16 encoding=SIGNED
+[11] ENUM 'state' encoding=SIGNED size=4 vlen=2
+\t'STOPPED' val=0
+\t'RUNNING' val=-1
+[12] ENUM64 'big' encoding=UNSIGNED size=8 vlen=1
+\t'BIG' val=4294967296
+[13] TYPEDEF 'foo_t' type_id=2
+[14] PTR '(anon)' type_id=13
+[15] FUNC_PROTO '(anon)' ret_type_id=3 vlen=3
+\t'foo' type_id=14
+\t'(anon)' type_id=11
+\t'(anon)' type_id=0
+[16] FUNC 'process' type_id=15 linkage=global
+[17] INT '_Bool' size=1 bits_offset=0 nr_bits=8 encoding=(none)
+[18] VAR 'flag' type_id=17, linkage=global
+"
+    );
+
+    for (src, err) in [
+        ("int a", "line 1: expected ';', found end of input"),
+        (
+            "struct foo {\n  int a : 3;\n};",
+            "line 2: bitfields are not supported",
+        ),
+        (
+            "struct foo { int a; };\nstruct foo { int b; };",
+            "line 2: redefinition of 'struct foo'",
+        ),
+        (
+            "struct foo;\nstruct foo a;",
+            "line 2: incomplete type 'foo'",
+        ),
+        ("unknown_t a;", "line 1: unknown type 'unknown_t'"),
+        ("long short a;", "line 1: invalid type 'long short'"),
+        (
+            "struct a {\n  char x[1000000000];\n  int y;\n};",
+            "line 4: type too large",
+        ),
+        (
+            "struct b {\n  char x[4294967295][4294967295][4294967295];\n};",
+            "line 3: type too large",
+        ),
+        (
+            "struct c {\n  char x[4294967295];\n  char y[4294967295];\n};",
+            "line 4: type too large",
+        ),
+    ] {
+        assert_eq!(utils::asm::assemble(src).unwrap_err().to_string(), err);
+    }
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn export_json() {
+    use utils::export::to_json;
+
+    let btf = utils::asm::assemble_btf(
+        "
+        struct foo { unsigned char a; union { int b; }; };
+        enum state { STOPPED = -1 };
+        int f(struct foo *);
+        ",
+    )
+    .unwrap();
+    assert_eq!(
+        to_json(&btf).unwrap(),
+        concat!(
+            r#"{"schema_version":1,"types":["#,
+            r#"{"id":1,"kind":"STRUCT","name":"foo","size":8,"vlen":2,"members":["#,
+            r#"{"name":"a","type_id":2,"bits_offset":0},"#,
+            r#"{"name":"(anon)","type_id":3,"bits_offset":32}]},"#,
+            r#"{"id":2,"kind":"INT","name":"unsigned char","size":1,"bits_offset":0,"nr_bits":8,"encoding":"(none)"},"#,
+            r#"{"id":3,"kind":"UNION","name":"(anon)","size":4,"vlen":1,"members":["#,
+            r#"{"name":"b","type_id":4,"bits_offset":0}]},"#,
+            r#"{"id":4,"kind":"INT","name":"int","size":4,"bits_offset":0,"nr_bits":32,"encoding":"SIGNED"},"#,
+            r#"{"id":5,"kind":"ENUM","name":"state","encoding":"SIGNED","size":4,"vlen":1,"values":["#,
+            r#"{"name":"STOPPED","val":-1}]},"#,
+            r#"{"id":6,"kind":"PTR","name":"(anon)","type_id":1},"#,
+            r#"{"id":7,"kind":"FUNC_PROTO","name":"(anon)","ret_type_id":4,"vlen":1,"params":["#,
+            r#"{"name":"(anon)","type_id":6}]},"#,
+            r#"{"id":8,"kind":"FUNC","name":"f","type_id":7,"linkage":"global"}"#,
+            r#"]}"#,
+        )
+    );
+
+    // Only the types of split objects are exported.
+    let split = split_file();
+    let json = to_json(&split).unwrap();
+    assert!(json.starts_with(&format!(
+        r#"{{"schema_version":1,"types":[{{"id":{},"#,
+        bytes().type_ids().end()
+    )));
+    assert!(json.contains(r#""kind":"FUNC","name":"ovs_vport_send","type_id":"#));
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn golden_export_json() {
+    assert_eq!(utils::export::SCHEMA_VERSION, 1);
+    check_golden(
+        "tests/data/golden/openvswitch.json",
+        &utils::export::to_json_pretty(&split_file()).unwrap(),
+    );
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn attach_fentry(btf: Btf) {
+    use utils::attach::*;
+
+    // Pointer and enum arguments.
+    assert!(can_fentry(&btf, "kfree_skb_reason"));
+    // 8 bytes struct passed by value.
+    assert!(can_fentry(&btf, "__mem_cgroup_try_charge_swap"));
+    // 56 bytes struct passed by value.
+    assert!(!can_fentry(&btf, "memory_group_register"));
+    // Variadic function.
+    assert!(!can_fentry(&btf, "acpi_os_printf"));
+    // 7 arguments.
+    assert!(can_fentry(&btf, "__blockdev_direct_IO"));
+    // Too many arguments.
+    assert!(!can_fentry(&btf, "tcp_v6_send_response"));
+    // Not a function.
+    assert!(!can_fentry(&btf, "sk_buff"));
+    assert!(!can_fentry(&btf, "not_a_known_function"));
+
+    let func = match btf
+        .resolve_types_by_name("tcp_v6_send_response")
+        .unwrap()
+        .pop()
+        .unwrap()
+    {
+        Type::Func(func) => func,
+        _ => panic!("Resolved type is not a function"),
+    };
+    assert_eq!(
+        check_fentry(&btf, &func).unwrap_err().to_string(),
+        "Function has 14 arguments, at most 12 are supported"
+    );
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn syscalls(btf: Btf) {
+    use utils::{arch::Arch, syscalls::*};
+
+    assert_eq!(
+        syscall_symbols("sys_openat", Arch::X86_64),
+        vec!["__x64_sys_openat", "sys_openat"]
+    );
+
+    let openat = resolve_syscall(&btf, "openat", Arch::X86_64).unwrap();
+    assert_eq!(openat.symbol, "__x64_sys_openat");
+    assert_eq!(btf.resolve_name(&openat.func).unwrap(), "__x64_sys_openat");
+    assert_eq!(openat.proto.parameters.len(), 1);
+    assert!(openat.uses_pt_regs);
+    // __do_sys_openat is inlined and not described in BTF.
+    assert!(openat.args_proto.is_none());
+
+    let capget = resolve_syscall(&btf, "capget", Arch::X86_64).unwrap();
+    assert!(capget.uses_pt_regs);
+    let args = capget.args_proto.unwrap();
+    assert_eq!(
+        args.parameters
+            .iter()
+            .map(|p| btf.resolve_name(p).unwrap())
+            .collect::<Vec<_>>(),
+        vec!["header", "dataptr"]
+    );
+
+    assert!(resolve_syscall(&btf, "sys_openat", Arch::X86_64).is_ok());
+    assert!(resolve_syscall(&btf, "openat", Arch::Arm64).is_err());
+    assert!(resolve_syscall(&btf, "not_a_syscall", Arch::X86_64).is_err());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn aliases(btf: Btf) {
+    use utils::{alias::*, arch::Arch};
+
+    let aliases = Aliases::new()
+        .prefix("sys_", "__x64_sys_")
+        .suffix("", ".cold")
+        .prefix("", "__");
+    assert_eq!(
+        aliases.candidates("sys_openat"),
+        vec![
+            "sys_openat",
+            "__x64_sys_openat",
+            "sys_openat.cold",
+            "__sys_openat"
+        ]
+    );
+
+    let aliased = AliasedBtf::new(&btf, Aliases::syscalls(Arch::X86_64));
+    let func = match aliased
+        .resolve_types_by_name("sys_openat")
+        .unwrap()
+        .pop()
+        .unwrap()
+    {
+        Type::Func(func) => func,
+        _ => panic!("Resolved type is not a function"),
+    };
+    assert_eq!(aliased.resolve_name(&func).unwrap(), "__x64_sys_openat");
+    assert_eq!(
+        aliased.resolve_ids_by_name("sys_openat").unwrap(),
+        btf.resolve_ids_by_name("__x64_sys_openat").unwrap()
+    );
+
+    // Exact matches are used first.
+    assert_eq!(
+        aliased.resolve_ids_by_name("sk_buff").unwrap(),
+        btf.resolve_ids_by_name("sk_buff").unwrap()
+    );
+    assert!(aliased.resolve_ids_by_name("sys_not_a_syscall").is_err());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn param_locations(btf: Btf) {
+    use utils::arch::{ParamLocation::*, *};
+
+    let proto = |name: &str| {
+        let func = match btf.resolve_types_by_name(name).unwrap().pop().unwrap() {
+            Type::Func(func) => func,
+            _ => panic!("Resolved type is not a function"),
+        };
+        match btf.resolve_chained_type(&func).unwrap() {
+            Type::FuncProto(proto) => proto,
+            _ => panic!("Resolved type is not a function proto"),
+        }
+    };
+
+    let kfree_skb_reason = proto("kfree_skb_reason");
+    assert_eq!(
+        param_locations(&btf, &kfree_skb_reason, Arch::X86_64).unwrap(),
+        vec![Register("di"), Register("si")]
+    );
+    assert_eq!(
+        param_locations(&btf, &kfree_skb_reason, Arch::Arm64).unwrap(),
+        vec![Register("regs[0]"), Register("regs[1]")]
+    );
+
+    // 7 parameters, the last one being passed on the stack on x86_64.
+    let locs = param_locations(&btf, &proto("__blockdev_direct_IO"), Arch::X86_64).unwrap();
+    assert_eq!(locs.len(), 7);
+    assert_eq!(locs[5], Register("r9"));
+    assert_eq!(locs[6], Stack);
+    let locs = param_locations(&btf, &proto("__blockdev_direct_IO"), Arch::Arm64).unwrap();
+    assert_eq!(locs[6], Register("regs[6]"));
+
+    // Large struct passed by value.
+    let memory_group_register = proto("memory_group_register");
+    assert_eq!(
+        param_locations(&btf, &memory_group_register, Arch::X86_64).unwrap(),
+        vec![Stack]
+    );
+    assert_eq!(
+        param_locations(&btf, &memory_group_register, Arch::Arm64).unwrap(),
+        vec![Reference("regs[0]")]
+    );
+
+    assert!(param_locations(&btf, &proto("acpi_os_printf"), Arch::X86_64).is_err());
+
+    let (index, r#type) = param_of_register(&btf, &kfree_skb_reason, Arch::X86_64, "si")
+        .unwrap()
+        .unwrap();
+    assert_eq!(index, 1);
+    assert!(matches!(r#type, Type::Enum(_)));
+    assert!(
+        param_of_register(&btf, &kfree_skb_reason, Arch::X86_64, "dx")
+            .unwrap()
+            .is_none()
+    );
+    assert!(param_of_register(&btf, &kfree_skb_reason, Arch::X86_64, "regs[0]").is_err());
+    assert_eq!(
+        param_of_register(&btf, &memory_group_register, Arch::Arm64, "regs[0]")
+            .unwrap()
+            .unwrap()
+            .0,
+        0
+    );
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn struct_members_queries(btf: Btf) {
+    let sk_buff = btf.resolve_ids_by_name("sk_buff").unwrap().pop().unwrap();
+    assert_eq!(btf.struct_member_count(sk_buff).unwrap(), 28);
+    assert!(btf.has_member(sk_buff, "truesize").unwrap());
+    assert!(!btf.has_member(sk_buff, "not_a_member").unwrap());
+
+    let int = btf.resolve_ids_by_name("int").unwrap().pop().unwrap();
+    assert!(btf.struct_member_count(int).is_err());
+    assert!(btf.has_member(int, "truesize").is_err());
+    assert!(btf.struct_member_count(u32::MAX).is_err());
+}
+
+#[test_case(split_file())]
+#[test_case(split_bytes())]
+fn split_struct_members_queries(btf: Btf) {
+    let datapath = btf.resolve_ids_by_name("datapath").unwrap().pop().unwrap();
+    assert_eq!(btf.struct_member_count(datapath).unwrap(), 10);
+    assert!(btf.has_member(datapath, "upcall_portids").unwrap());
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn resolve_type_shallow(btf: Btf) {
+    let sk_buff = btf.resolve_ids_by_name("sk_buff").unwrap().pop().unwrap();
+    let shallow = btf.resolve_type_shallow(sk_buff).unwrap();
+    assert_eq!(shallow.kind(), Kind::Struct);
+    assert_eq!(shallow.size(), Some(232));
+    assert_eq!(shallow.type_id(), None);
+    assert_eq!(shallow.vlen(), 28);
+    assert_eq!(btf.resolve_name(&shallow).unwrap(), "sk_buff");
+
+    let u64 = btf.resolve_ids_by_name("u64").unwrap().pop().unwrap();
+    let shallow = btf.resolve_type_shallow(u64).unwrap();
+    assert_eq!(shallow.kind(), Kind::Typedef);
+    assert_eq!(shallow.size(), None);
+    assert_eq!(
+        btf.resolve_chained_type(&shallow).unwrap().kind(),
+        Kind::Typedef
+    );
+
+    let void = btf.resolve_type_shallow(0).unwrap();
+    assert_eq!(void.kind(), Kind::Void);
+    assert_eq!(void.vlen(), 0);
+    assert!(btf.resolve_name(&void).is_err());
+
+    assert!(btf.resolve_type_shallow(u32::MAX).is_err());
+}
+
+#[test_case(split_file())]
+#[test_case(split_bytes())]
+fn ordered_iteration(btf: Btf) {
+    let ids = btf.type_ids();
+    assert_eq!(ids.start(), 0);
+    assert_eq!(ids.end(), 178512);
+    assert_eq!(ids.len(), 178512);
+    assert_eq!(ids.clone().next_back(), Some(TypeId::from(178511)));
+    assert!(btf.resolve_type_by_id(u32::from(ids.end()) - 1).is_ok());
+    assert!(btf.resolve_type_by_id(ids.end()).is_err());
+
+    let names = btf.names();
+    assert!(names.windows(2).all(|w| w[0] < w[1]));
+    assert!(names.binary_search(&"sk_buff").is_ok());
+    assert!(names.binary_search(&"datapath").is_ok());
+
+    let dump = utils::dump::dump_to_string(&btf).unwrap();
+    assert_eq!(dump, utils::dump::dump_to_string(&btf).unwrap());
+    assert!(dump.starts_with(
+        "[177769] ENUM 'ovs_packet_cmd' encoding=UNSIGNED size=4 vlen=4\n\t'OVS_PACKET_CMD_UNSPEC' val=0\n"
+    ));
+    assert!(dump.contains(
+        "STRUCT 'ovs_flow_stats' size=16 vlen=2\n\t'n_packets' type_id=47 bits_offset=0\n\t'n_bytes' type_id=47 bits_offset=64\n"
+    ));
+}
+
+// Golden files hold the expected output of the crate helpers, generated using
+// libbpf. See tests/data/golden/README.md to regenerate them.
+fn check_golden(path: &str, dump: &str) {
+    let golden = std::fs::read_to_string(path).unwrap();
+
+    // Report the first mismatching line for readability.
+    for (i, (golden, dump)) in golden.lines().zip(dump.lines()).enumerate() {
+        assert_eq!(golden, dump, "Mismatch at {path}:{}", i + 1);
+    }
+    assert_eq!(golden.lines().count(), dump.lines().count());
+}
+
+#[test_case(split_bytes())]
+#[cfg_attr(feature = "elf", test_case(split_elf()))]
+fn golden_split(btf: Btf) {
+    check_golden(
+        "tests/data/golden/openvswitch.raw",
+        &utils::dump::dump_to_string(&btf).unwrap(),
+    );
+}
+
+#[test_case(bytes())]
+#[cfg_attr(feature = "elf", test_case(elf()))]
+fn golden_base(btf: Btf) {
+    // Selection of types covering all kinds found in the base BTF and
+    // interesting corner cases (bitfields, signed enum, enum64, etc.). Keep in
+    // sync with tests/data/golden/Makefile.
+    let ids = [
+        17, 21, 125, 147, 750, 1396, 2030, 2526, 4984, 9431, 14431, 39775, 85696, 97468, 177768,
+    ];
+
+    let mut dump = Vec::new();
+    for id in ids {
+        let r#type = btf.resolve_type_by_id(id).unwrap();
+        utils::dump::dump_type(&btf, id, &r#type, &mut dump).unwrap();
+    }
+    check_golden(
+        "tests/data/golden/vmlinux.raw",
+        &String::from_utf8(dump).unwrap(),
+    );
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn golden_resolved() {
+    use utils::resolved::*;
+
+    // Same selection as golden_base, plus the kinds it does not cover. Keep
+    // in sync with tests/data/golden/vmlinux.resolved.json.
+    let ids = [
+        0, 2, 3, 17, 21, 25, 125, 147, 750, 1396, 2030, 2526, 4984, 9431, 14431, 39775, 85696,
+        97468, 177768,
+    ];
+    let path = "tests/data/golden/vmlinux.resolved.json";
+
+    let btf = bytes();
+    let mut dump = String::new();
+    for id in ids {
+        let json = serde_json::to_string(&resolve_by_id(&btf, id).unwrap()).unwrap();
+        dump.push_str(&json);
+        dump.push('\n');
+    }
+    check_golden(path, &dump);
+
+    // Documents of the current schema version can still be deserialized.
+    // Types are not compared directly, as the signedness of enum values is
+    // not serialized.
+    assert_eq!(SCHEMA_VERSION, 1);
+    for line in std::fs::read_to_string(path).unwrap().lines() {
+        let r#type = serde_json::from_str::<ResolvedType>(line).unwrap();
+        assert_eq!(serde_json::to_string(&r#type).unwrap(), line);
+    }
+}
+
+// Render the C declarations listed in a golden file, see
+// tests/data/golden/gen.c for its format.
+fn golden_decls(btf: &Btf, path: &str) -> String {
+    use utils::printer::decl_to_c;
+
+    let mut out = String::new();
+    for line in std::fs::read_to_string(path).unwrap().lines() {
+        let key = line.split_once('\t').unwrap().0;
+        let (id, index) = key.split_once(':').unwrap();
+        let r#type = btf.resolve_type_by_id(id.parse::<u32>().unwrap()).unwrap();
+
+        let decl = match (&r#type, index.parse::<usize>().ok()) {
+            (Type::Typedef(td), None) => decl_to_c(
+                btf,
+                &btf.resolve_chained_type(td).unwrap(),
+                &btf.resolve_name(td).unwrap(),
+            ),
+            (_, None) => decl_to_c(btf, &r#type, ""),
+            (Type::Struct(s) | Type::Union(s), Some(i)) => decl_to_c(
+                btf,
+                &btf.resolve_chained_type(&s.members[i]).unwrap(),
+                &btf.resolve_name(&s.members[i]).unwrap(),
+            ),
+            (Type::FuncProto(fp), Some(i)) => decl_to_c(
+                btf,
+                &fp.parameters[i].r#type(btf).unwrap(),
+                &btf.resolve_name(&fp.parameters[i]).unwrap(),
+            ),
+            _ => panic!("Unexpected golden entry {key}"),
+        };
+        out.push_str(&format!("{key}\t{}\n", decl.unwrap()));
+    }
+    out
+}
+
+#[test_case(split_bytes())]
+fn golden_split_c(btf: Btf) {
+    let path = "tests/data/golden/openvswitch.decl";
+    check_golden(path, &golden_decls(&btf, path));
+}
+
+#[test_case(bytes())]
+fn golden_base_c(btf: Btf) {
+    let path = "tests/data/golden/vmlinux.decl";
+    check_golden(path, &golden_decls(&btf, path));
+}
+
+#[test_case(bytes())]
+fn enum_values(btf: Btf) {
+    let rpm_status = match btf.resolve_type_by_id(1396).unwrap() {
+        Type::Enum(e) => e,
+        _ => panic!("Resolved type is not an enum"),
+    };
+    assert!(rpm_status.is_signed());
+    assert_eq!(
+        btf.resolve_name(&rpm_status.members[0]).unwrap(),
+        "RPM_INVALID"
+    );
+    assert_eq!(rpm_status.members[0].val(), u32::MAX);
+    assert_eq!(rpm_status.members[0].val_signed(), -1);
+    assert_eq!(
+        rpm_status.members[0].value(&rpm_status),
+        EnumValue::Signed(-1)
+    );
+
+    let caps = match btf
+        .resolve_types_by_name("ib_uverbs_device_cap_flags")
+        .unwrap()
+        .pop()
+        .unwrap()
+    {
+        Type::Enum64(e) => e,
+        _ => panic!("Resolved type is not an enum64"),
+    };
+    assert!(!caps.is_signed());
+    let last = caps.members.last().unwrap();
+    assert_eq!(last.value(&caps), EnumValue::Unsigned(last.val()));
+    assert_eq!(last.val_signed(), last.val() as i64);
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn enum_values_iter(btf: Btf) {
+    use std::collections::HashMap;
+
+    let rpm_status = match btf
+        .resolve_types_by_name("rpm_status")
+        .unwrap()
+        .pop()
+        .unwrap()
+    {
+        Type::Enum(e) => e,
+        _ => panic!("Resolved type is not an enum"),
+    };
+    assert_eq!(rpm_status.len(), 5);
+    assert!(!rpm_status.is_empty());
+
+    let values = rpm_status
+        .values(&btf)
+        .collect::<anyhow::Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(values[0], ("RPM_INVALID".to_string(), -1));
+    assert_eq!(values[4], ("RPM_SUSPENDING".to_string(), 3));
+
+    let caps = match btf
+        .resolve_types_by_name("ib_uverbs_device_cap_flags")
+        .unwrap()
+        .pop()
+        .unwrap()
+    {
+        Type::Enum64(e) => e,
+        _ => panic!("Resolved type is not an enum64"),
+    };
+    let values = caps
+        .values(&btf)
+        .collect::<anyhow::Result<HashMap<String, i64>>>()
+        .unwrap();
+    assert_eq!(values.len(), caps.len());
+    assert_eq!(values["IB_UVERBS_DEVICE_RESIZE_MAX_WR"], 1);
+}
+
+#[test_case(bytes())]
+#[test_case(split_file())]
+fn codegen_enum(btf: Btf) {
+    let code = utils::codegen::enum_to_rust(&btf, "skb_drop_reason").unwrap();
+    assert!(code.contains("#[repr(i64)]\npub enum SkbDropReason {\n"));
+    assert!(code.contains("    NotDroppedYet = 0,\n"));
+    assert!(code.contains("    DropReasonNotSpecified = 2,\n"));
+    assert!(code.contains("    DropReasonSubsysMask = 4294901760,\n"));
+    assert!(code.contains("impl TryFrom<i64> for SkbDropReason {"));
+    assert!(code.contains("            2 => Ok(SkbDropReason::DropReasonNotSpecified),\n"));
+
+    let code = utils::codegen::enum_to_rust(&btf, "rpm_status").unwrap();
+    assert!(code.contains("pub enum RpmStatus {\n    Invalid = -1,\n    Active = 0,\n"));
+
+    assert!(utils::codegen::enum_to_rust(&btf, "sk_buff").is_err());
+    assert!(utils::codegen::enum_to_rust(&btf, "not_an_enum").is_err());
+}
+
+#[test]
+fn source_kind() {
+    assert_eq!(file().source(), SourceKind::File);
+    assert!(!file().is_split());
+    assert_eq!(bytes().source(), SourceKind::Bytes);
+    assert_eq!(split_file().source(), SourceKind::File);
+    assert!(split_file().is_split());
+    assert_eq!(split_bytes().source(), SourceKind::Bytes);
+    assert!(split_bytes().is_split());
+}
+
+#[test]
+#[cfg(feature = "elf")]
+fn source_kind_elf() {
+    let vmlinux = utils::elf::btf_from_file("tests/data/linux_build/vmlinux").unwrap();
+    assert_eq!(vmlinux.source(), SourceKind::Elf);
+    assert!(!vmlinux.is_split());
+
+    let ovs = utils::elf::split_btf_from_file(
+        "tests/data/linux_build/net/openvswitch/openvswitch.ko",
+        &vmlinux,
+    )
+    .unwrap();
+    assert_eq!(ovs.source(), SourceKind::Elf);
+    assert!(ovs.is_split());
+
+    let btfc = btfc_elf();
+    assert_eq!(
+        btfc.get_named_btf("openvswitch").unwrap().source(),
+        SourceKind::Elf
+    );
+}
+
+#[test]
+#[cfg_attr(not(feature = "test_runtime"), ignore)]
+fn source_kind_kernel() {
+    if let Ok(vmlinux) = Btf::from_file("/sys/kernel/btf/vmlinux") {
+        assert_eq!(vmlinux.source(), SourceKind::Kernel);
+    }
+}